@@ -13,13 +13,16 @@ run_test() {
   test "invalid file size" = "$result"
 
   result=$(curl -s -F "file=@bigfile" localhost:8000)
-  test "upload limit exceeded" = "$result"
+  test "upload limit exceeded, limit is 10 KB" = "$result"
 
   result=$(curl -s -F "file=@normalfile" localhost:8000)
-  test "upload limit exceeded" = "$result"
+  test "upload limit exceeded, limit is 10 KB" = "$result"
+
+  result=$(curl -s -H "Transfer-Encoding: chunked" -F "file=@bigfile" localhost:8000)
+  test "upload limit exceeded, limit is 10 KB" = "$result"
 
   result=$(curl -s -F "file=@smallfile" localhost:8000)
-  test "upload limit exceeded" != "$result"
+  test "upload limit exceeded, limit is 10 KB" != "$result"
 }
 
 teardown() {
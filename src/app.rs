@@ -0,0 +1,314 @@
+use crate::auth::AuthFailureTracker;
+use crate::config::Config;
+use crate::middleware::{CanonicalRedirect, ContentLengthLimiter};
+use crate::server::{self, RouteGroup};
+use crate::util;
+use actix_web::middleware::Condition;
+use actix_web::web::{self, Data};
+use awc::ClientBuilder;
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// Default timeout for the HTTP client.
+const DEFAULT_CLIENT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Shared state for mounting the rustypaste routes into an application.
+///
+/// ```no_run
+/// use actix_web::{web, App, HttpServer};
+/// use rustypaste::config::Config;
+/// use rustypaste::AppState;
+///
+/// # async fn run(config: Config) -> std::io::Result<()> {
+/// let state = AppState::new(config);
+/// HttpServer::new(move || {
+///     let state = state.clone();
+///     App::new().service(web::scope("/paste").configure(move |cfg| state.configure(cfg)))
+/// })
+/// .bind("127.0.0.1:8000")?
+/// .run()
+/// .await
+/// # }
+/// ```
+///
+/// Note that [`server.url`] should contain the scope for generating the correct URLs.
+///
+/// [`server.url`]: crate::config::ServerConfig::url
+#[derive(Clone)]
+pub struct AppState {
+    /// Configuration.
+    config: Data<RwLock<Config>>,
+    /// Failed authentication attempts.
+    auth_failure_tracker: Data<AuthFailureTracker>,
+}
+
+impl AppState {
+    /// Constructs a new instance from the given configuration.
+    pub fn new(config: Config) -> Self {
+        Self::from_data(Data::new(RwLock::new(config)))
+    }
+
+    /// Constructs a new instance from the shared configuration.
+    ///
+    /// This is useful for updating the configuration while the server is running.
+    pub fn from_data(config: Data<RwLock<Config>>) -> Self {
+        Self {
+            config,
+            auth_failure_tracker: Data::new(AuthFailureTracker::default()),
+        }
+    }
+
+    /// Returns the shared configuration.
+    pub fn config(&self) -> Data<RwLock<Config>> {
+        Data::clone(&self.config)
+    }
+
+    /// Registers the application data, middleware and routes.
+    ///
+    /// The HTTP client is created here since it cannot be shared between the workers.
+    pub fn configure(&self, cfg: &mut web::ServiceConfig) {
+        self.configure_group(cfg, RouteGroup::All);
+    }
+
+    /// Registers the application data, middleware and the routes of the given group.
+    ///
+    /// This is useful for serving the administrative routes on a separate listener.
+    pub fn configure_group(&self, cfg: &mut web::ServiceConfig, group: RouteGroup) {
+        let (timeout, max_content_length) = match self.config.read() {
+            Ok(config) => {
+                util::set_config_lock_timeout(config.server.config_lock_timeout);
+                (config.server.timeout, config.server.max_content_length)
+            }
+            Err(e) => {
+                error!("Failed to acquire config: {}", e);
+                (None, Default::default())
+            }
+        };
+        let http_client = ClientBuilder::new()
+            .timeout(timeout.unwrap_or(DEFAULT_CLIENT_TIMEOUT))
+            .disable_redirects()
+            .finish();
+        cfg.app_data(Data::clone(&self.config))
+            .app_data(Data::new(http_client))
+            .app_data(Data::clone(&self.auth_failure_tracker))
+            .service(
+                web::scope("")
+                    .wrap(ContentLengthLimiter::new(max_content_length))
+                    // the administrative routes can be served on another host
+                    .wrap(Condition::new(
+                        group != RouteGroup::Admin,
+                        CanonicalRedirect::new(Data::clone(&self.config)),
+                    ))
+                    .configure(|cfg| server::configure_route_group(cfg, group)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::header::{self, HeaderValue};
+    use actix_web::http::{Method, StatusCode};
+    use actix_web::test::{self, TestRequest};
+    use actix_web::{App, Error};
+    use byte_unit::Byte;
+    use std::fs;
+    use std::path::PathBuf;
+
+    #[actix_web::test]
+    async fn test_mount_routes() -> Result<(), Error> {
+        let test_upload_dir = "test_mount_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.url = Some(String::from("http://localhost:8080/paste"));
+        config.server.max_content_length = Byte::from_u64(1000);
+        let state = AppState::new(config);
+
+        let app = test::init_service(
+            App::new().service(web::scope("/paste").configure(|cfg| state.configure(cfg))),
+        )
+        .await;
+
+        let data = "\r\n\
+             --multipart_bound\r\n\
+             Content-Disposition: form-data; name=\"file\"; filename=\"mounted.txt\"\r\n\
+             Content-Type: text/plain; charset=utf-8\r\n\r\n\
+             mounted\r\n\
+             --multipart_bound--\r\n";
+        let request = TestRequest::post()
+            .uri("/paste/")
+            .insert_header((
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("multipart/mixed; boundary=\"multipart_bound\""),
+            ))
+            .set_payload(data)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let body = test::read_body(response).await;
+        assert_eq!(
+            web::Bytes::from_static(b"http://localhost:8080/paste/mounted.txt\n"),
+            body
+        );
+
+        let request = TestRequest::get().uri("/paste/mounted.txt").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let body = test::read_body(response).await;
+        assert_eq!(web::Bytes::from_static(b"mounted"), body);
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_admin_routes() -> Result<(), Error> {
+        let test_upload_dir = "test_admin_routes_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.expose_list = Some(true);
+        config.server.expose_config = Some(true);
+        config.server.delete_tokens = Some(["delete_token".to_string()].into());
+        config.server.max_content_length = Byte::from_u64(1000);
+        let state = AppState::new(config);
+
+        let public_app = test::init_service(
+            App::new().configure(|cfg| state.configure_group(cfg, RouteGroup::Public)),
+        )
+        .await;
+        let admin_app = test::init_service(
+            App::new().configure(|cfg| state.configure_group(cfg, RouteGroup::Admin)),
+        )
+        .await;
+
+        for (method, uri) in [
+            (Method::GET, "/list"),
+            (Method::GET, "/debug/config"),
+            (Method::POST, "/import"),
+            (Method::DELETE, "/purge?older_than=1d"),
+        ] {
+            let request = TestRequest::default()
+                .method(method.clone())
+                .uri(uri)
+                .insert_header((header::AUTHORIZATION, "delete_token"))
+                .to_request();
+            let response = test::call_service(&admin_app, request).await;
+            assert_ne!(StatusCode::NOT_FOUND, response.status(), "{uri}");
+
+            let request = TestRequest::default()
+                .method(method)
+                .uri(uri)
+                .insert_header((header::AUTHORIZATION, "delete_token"))
+                .to_request();
+            let response = test::call_service(&public_app, request).await;
+            assert_eq!(StatusCode::NOT_FOUND, response.status(), "{uri}");
+        }
+
+        let request = TestRequest::get().uri("/").to_request();
+        let response = test::call_service(&admin_app, request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+        let request = TestRequest::get().uri("/").to_request();
+        let response = test::call_service(&public_app, request).await;
+        assert_eq!(StatusCode::FOUND, response.status());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_canonical_redirect() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.url = Some(String::from("https://paste.example.com"));
+        config.server.canonical_redirect = Some(true);
+        config.server.expose_version = Some(true);
+        let state = AppState::new(config);
+
+        let app = test::init_service(App::new().configure(|cfg| state.configure(cfg))).await;
+
+        let request = TestRequest::get()
+            .uri("/version?x=y")
+            .insert_header((header::HOST, "127.0.0.1:8000"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::MOVED_PERMANENTLY, response.status());
+        assert_eq!(
+            Some("https://paste.example.com/version?x=y"),
+            response
+                .headers()
+                .get(header::LOCATION)
+                .and_then(|v| v.to_str().ok())
+        );
+
+        let request = TestRequest::get()
+            .uri("/version")
+            .insert_header((header::HOST, "paste.example.com"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[actix_web::test]
+    async fn test_unix_socket() -> Result<(), Error> {
+        use actix_web::HttpServer;
+        use std::io::{Read, Write};
+        use std::os::unix::fs::PermissionsExt;
+        use std::os::unix::net::UnixStream;
+
+        let mut config = Config::default();
+        config.server.expose_version = Some(true);
+        let state = AppState::new(config);
+
+        let socket_path = crate::util::get_unix_socket_path(&format!(
+            "unix:{}",
+            std::env::temp_dir()
+                .join(format!("rustypaste-{}.sock", std::process::id()))
+                .display()
+        ))
+        .expect("invalid socket address");
+        let server = HttpServer::new(move || {
+            let state = state.clone();
+            App::new().configure(move |cfg| state.configure(cfg))
+        })
+        .workers(1)
+        .bind_uds(&socket_path)?;
+        fs::set_permissions(&socket_path, fs::Permissions::from_mode(0o600))?;
+        assert_eq!(
+            0o600,
+            fs::metadata(&socket_path)?.permissions().mode() & 0o777
+        );
+        let server = server.run();
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+
+        let path = socket_path.clone();
+        let response = actix_web::rt::task::spawn_blocking(move || {
+            let mut stream = UnixStream::connect(path)?;
+            stream.write_all(
+                b"GET /version HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            )?;
+            let mut response = String::new();
+            stream.read_to_string(&mut response)?;
+            Ok::<String, std::io::Error>(response)
+        })
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)??;
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with(&format!("{}\n", env!("CARGO_PKG_VERSION"))));
+
+        handle.stop(true).await;
+        // the socket file is removed by the server on shutdown
+        assert!(!socket_path.exists());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,196 @@
+use crate::util::safe_path_join;
+use actix_web::{error, Error};
+use flate2::read::GzDecoder;
+use std::fmt::Display;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+
+/// Default maximum number of files in an archive.
+pub const DEFAULT_MAX_ENTRIES: usize = 100;
+
+/// File that is extracted from an archive.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// Name of the file without the parent directories.
+    pub file_name: String,
+    /// Contents of the file.
+    pub data: Vec<u8>,
+}
+
+/// Archive extractor that keeps track of the limits.
+struct Extractor {
+    /// Extracted files.
+    entries: Vec<ArchiveEntry>,
+    /// Maximum number of files.
+    max_entries: usize,
+    /// Remaining size for the files in bytes.
+    remaining_size: u64,
+}
+
+impl Extractor {
+    /// Reads the file at the given path in the archive.
+    fn add<R: Read>(&mut self, path: &Path, reader: R) -> Result<(), Error> {
+        if self.entries.len() >= self.max_entries {
+            return Err(error::ErrorPayloadTooLarge(
+                "too many files in the archive\n",
+            ));
+        }
+        // the base directory is only used for rejecting the entries outside of the archive
+        let file_name = safe_path_join("archive", path)
+            .ok()
+            .and_then(|_| path.file_name())
+            .map(|v| v.to_string_lossy().to_string())
+            .ok_or_else(|| {
+                error::ErrorBadRequest(format!("invalid archive entry: {}\n", path.display()))
+            })?;
+        // the size in the header is not trusted
+        let mut data = Vec::new();
+        reader
+            .take(self.remaining_size + 1)
+            .read_to_end(&mut data)
+            .map_err(invalid_archive)?;
+        if data.len() as u64 > self.remaining_size {
+            return Err(error::ErrorPayloadTooLarge("archive is too large\n"));
+        }
+        self.remaining_size -= data.len() as u64;
+        self.entries.push(ArchiveEntry { file_name, data });
+        Ok(())
+    }
+
+    /// Extracts the files in a tar archive.
+    fn extract_tar<R: Read>(&mut self, reader: R) -> Result<(), Error> {
+        let mut archive = tar::Archive::new(reader);
+        for entry in archive.entries().map_err(invalid_archive)? {
+            let entry = entry.map_err(invalid_archive)?;
+            let entry_type = entry.header().entry_type();
+            let path = entry.path().map_err(invalid_archive)?.into_owned();
+            if entry_type.is_dir() || entry_type.is_pax_global_extensions() {
+                continue;
+            } else if !entry_type.is_file() {
+                return Err(error::ErrorBadRequest(format!(
+                    "invalid archive entry: {}\n",
+                    path.display()
+                )));
+            }
+            self.add(&path, entry)?;
+        }
+        Ok(())
+    }
+
+    /// Extracts the files in a zip archive.
+    fn extract_zip(&mut self, data: &[u8]) -> Result<(), Error> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(data)).map_err(invalid_archive)?;
+        for index in 0..archive.len() {
+            let file = archive.by_index(index).map_err(invalid_archive)?;
+            if file.is_dir() {
+                continue;
+            }
+            let path = PathBuf::from(file.name());
+            self.add(&path, file)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the error for the archives that cannot be read.
+fn invalid_archive<E: Display>(e: E) -> Error {
+    error::ErrorBadRequest(format!("invalid archive: {e}\n"))
+}
+
+/// Extracts the files in the given tar, gzip compressed tar or zip archive.
+///
+/// - Directories are skipped and the files are flattened.
+/// - Entries outside of the archive (e.g. `../file`) and links are rejected.
+/// - Extraction fails if there are more than `max_entries` files
+///   or the total size of the files exceeds `max_size`.
+///
+/// Nothing is returned unless the whole archive is valid.
+pub fn extract(data: &[u8], max_entries: usize, max_size: u64) -> Result<Vec<ArchiveEntry>, Error> {
+    let mut extractor = Extractor {
+        entries: Vec::new(),
+        max_entries,
+        remaining_size: max_size,
+    };
+    if data.starts_with(b"PK\x03\x04") {
+        extractor.extract_zip(data)?;
+    } else if data.starts_with(&[0x1f, 0x8b]) {
+        extractor.extract_tar(GzDecoder::new(data))?;
+    } else {
+        extractor.extract_tar(data)?;
+    }
+    if extractor.entries.is_empty() {
+        return Err(error::ErrorBadRequest("archive is empty\n"));
+    }
+    Ok(extractor.entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn create_tar(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, data) in files {
+            // the name is set directly since `set_path` rejects the parent directories
+            let mut header = tar::Header::new_old();
+            header.as_old_mut().name[..path.len()].copy_from_slice(path.as_bytes());
+            header.set_entry_type(tar::EntryType::Regular);
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append(&header, data.as_bytes())
+                .expect("cannot append to archive");
+        }
+        builder.into_inner().expect("cannot create archive")
+    }
+
+    #[test]
+    fn test_extract_tar() -> Result<(), Error> {
+        let archive = create_tar(&[("a.txt", "a"), ("dir/b.txt", "bb")]);
+        let expected = vec![
+            ArchiveEntry {
+                file_name: String::from("a.txt"),
+                data: b"a".to_vec(),
+            },
+            ArchiveEntry {
+                file_name: String::from("b.txt"),
+                data: b"bb".to_vec(),
+            },
+        ];
+        assert_eq!(expected, extract(&archive, 10, 10)?);
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&archive)?;
+        assert_eq!(expected, extract(&encoder.finish()?, 10, 10)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_limits() {
+        for (archive, status) in [
+            (
+                create_tar(&[("a.txt", "a"), ("../b.txt", "b")]),
+                StatusCode::BAD_REQUEST,
+            ),
+            (create_tar(&[("/etc/b.txt", "b")]), StatusCode::BAD_REQUEST),
+            (create_tar(&[]), StatusCode::BAD_REQUEST),
+            (
+                create_tar(&[("a.txt", "a"), ("b.txt", "b"), ("c.txt", "c")]),
+                StatusCode::PAYLOAD_TOO_LARGE,
+            ),
+            (
+                create_tar(&[("a.txt", "a"), ("b.txt", "0123456789")]),
+                StatusCode::PAYLOAD_TOO_LARGE,
+            ),
+        ] {
+            let error = extract(&archive, 2, 10).expect_err("archive is extracted");
+            assert_eq!(status, error.as_response_error().status_code());
+        }
+    }
+}
@@ -1,47 +1,346 @@
-use crate::config::{Config, TokenType};
+use crate::config::{AuthFailureLimitConfig, Config, EndpointGroup, ServerConfig, TokenType};
+use crate::header::{self, ClientConnection};
+use crate::util;
 use actix_web::dev::{ServiceRequest, ServiceResponse};
-use actix_web::http::header::AUTHORIZATION;
+use actix_web::http::header::{HeaderMap, AUTHORIZATION};
 use actix_web::http::Method;
 use actix_web::middleware::ErrorHandlerResponse;
 use actix_web::{error, web, Error};
-use std::collections::HashSet;
-use std::sync::RwLock;
+use ring::hmac;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
 
-/// Extracts the tokens from the authorization header by token type.
+/// Maximum delay that is applied after the failed authentication attempts.
+const MAX_AUTH_FAILURE_DELAY: Duration = Duration::from_secs(10);
+
+/// Query parameter for the expiration timestamp of a signed URL.
+const SIGNATURE_EXPIRATION_PARAM: &str = "exp";
+
+/// Query parameter for the signature of a signed URL.
+const SIGNATURE_PARAM: &str = "sig";
+
+/// Query parameter for the token of a deletion URL.
+const DELETION_TOKEN_PARAM: &str = "token";
+
+/// Failed authentication attempts of a client.
+#[derive(Debug)]
+struct AuthFailures {
+    /// Number of the failed attempts.
+    count: u32,
+    /// Time of the first failed attempt in the window.
+    first_attempt: Instant,
+    /// Time until the client is blocked.
+    blocked_until: Option<Instant>,
+}
+
+impl AuthFailures {
+    /// Returns `true` if the failures are not relevant anymore.
+    fn is_expired(&self, now: Instant, config: &AuthFailureLimitConfig) -> bool {
+        match self.blocked_until {
+            Some(blocked_until) => now >= blocked_until,
+            None => now.duration_since(self.first_attempt) > config.window,
+        }
+    }
+}
+
+/// Tracker for the failed authentication attempts per client IP.
+///
+/// See [`AuthFailureLimitConfig`].
+#[derive(Debug, Default)]
+pub struct AuthFailureTracker {
+    failures: Mutex<HashMap<String, AuthFailures>>,
+}
+
+impl AuthFailureTracker {
+    /// Records a failed authentication attempt of the client.
+    pub fn record_failure(&self, client: &str, config: &AuthFailureLimitConfig) {
+        let now = Instant::now();
+        let mut failures = match self.failures.lock() {
+            Ok(failures) => failures,
+            Err(e) => e.into_inner(),
+        };
+        failures.retain(|_, v| !v.is_expired(now, config));
+        let entry = failures
+            .entry(client.to_string())
+            .or_insert_with(|| AuthFailures {
+                count: 0,
+                first_attempt: now,
+                blocked_until: None,
+            });
+        entry.count += 1;
+        if entry.count >= config.max_attempts && entry.blocked_until.is_none() {
+            warn!(
+                "blocking {client} for {:?} after {} failed authentication attempts",
+                config.block_duration, entry.count
+            );
+            entry.blocked_until = Some(now + config.block_duration);
+        }
+    }
+
+    /// Returns the delay to apply before processing the request of the client.
+    ///
+    /// Returns too many requests (`429`) error if the client is blocked.
+    pub fn check(&self, client: &str, config: &AuthFailureLimitConfig) -> Result<Duration, Error> {
+        let now = Instant::now();
+        let mut failures = match self.failures.lock() {
+            Ok(failures) => failures,
+            Err(e) => e.into_inner(),
+        };
+        let entry = match failures.get(client) {
+            Some(entry) => entry,
+            None => return Ok(Duration::ZERO),
+        };
+        if entry.is_expired(now, config) {
+            failures.remove(client);
+            return Ok(Duration::ZERO);
+        }
+        if entry.blocked_until.is_some() {
+            return Err(error::ErrorTooManyRequests(
+                "too many failed authentication attempts\n",
+            ));
+        }
+        Ok(config
+            .delay
+            .map(|delay| {
+                delay
+                    .saturating_mul(2_u32.saturating_pow(entry.count.saturating_sub(1)))
+                    .min(MAX_AUTH_FAILURE_DELAY)
+            })
+            .unwrap_or_default())
+    }
+
+    /// Clears the failed authentication attempts of the client.
+    pub fn reset(&self, client: &str) {
+        if let Ok(mut failures) = self.failures.lock() {
+            failures.remove(client);
+        }
+    }
+}
+
+/// Extracts the endpoint groups that the request is authorized for.
+///
+/// The tokens are read from the authorization header:
 ///
 /// `Authorization: (type) <token>`
-pub(crate) async fn extract_tokens(req: &ServiceRequest) -> Result<HashSet<TokenType>, Error> {
-    let config = req
-        .app_data::<web::Data<RwLock<Config>>>()
-        .map(|cfg| cfg.read())
-        .and_then(Result::ok)
-        .ok_or_else(|| error::ErrorInternalServerError("cannot acquire config"))?;
+///
+/// Each route declares its [`EndpointGroup`] via the `protect` attribute of the handler.
+/// [`EndpointGroup::Delete`] is only granted for a delete token.
+///
+/// If [`auth_failure_limit`] is set, the requests from the clients with failed authentication
+/// attempts are delayed or blocked.
+///
+/// [`auth_failure_limit`]: crate::config::ServerConfig::auth_failure_limit
+pub(crate) async fn extract_tokens(req: &ServiceRequest) -> Result<HashSet<EndpointGroup>, Error> {
+    let auth_failure_tracker = req.app_data::<web::Data<AuthFailureTracker>>();
+    let (client, auth_failure_limit) = {
+        let config = util::read_config(
+            req.app_data::<web::Data<RwLock<Config>>>()
+                .ok_or_else(|| error::ErrorInternalServerError("cannot acquire config"))?,
+        )?;
+        (
+            ClientConnection::from_request(req.request(), &config.server).remote_addr,
+            config.server.auth_failure_limit.clone(),
+        )
+    };
+    if let (Some(tracker), Some(limit_config)) = (auth_failure_tracker, &auth_failure_limit) {
+        let delay = tracker.check(&client, limit_config)?;
+        if !delay.is_zero() {
+            actix_web::rt::time::sleep(delay).await;
+        }
+    }
+
+    let config = util::read_config(
+        req.app_data::<web::Data<RwLock<Config>>>()
+            .ok_or_else(|| error::ErrorInternalServerError("cannot acquire config"))?,
+    )?;
+
+    let mut endpoint_groups = HashSet::with_capacity(5);
+
+    let auth_header = get_auth_token(req.headers(), &config.server).unwrap_or_default();
+    let auth_tokens = config.get_tokens(TokenType::Auth);
+    let delete_tokens = config.get_tokens(TokenType::Delete);
+    if let Some(tracker) = auth_failure_tracker {
+        if [&auth_tokens, &delete_tokens]
+            .into_iter()
+            .flatten()
+            .any(|tokens| tokens.contains(auth_header))
+        {
+            tracker.reset(&client);
+        }
+    }
+
+    let is_authorized = is_authorized(req.headers(), &config);
+    let auth_config = config.server.auth.clone().unwrap_or_default();
+    // signatures are an alternative to the tokens for reading files,
+    // they are verified while serving the file
+    let is_signed = config.server.signing_secret.is_some() && has_signature(req.query_string());
+    for endpoint_group in [
+        EndpointGroup::Read,
+        EndpointGroup::Write,
+        EndpointGroup::List,
+        EndpointGroup::Sign,
+    ] {
+        // the `read_paths` are checked while serving the files
+        let token_required = auth_config.is_required(endpoint_group);
+        if is_authorized || !token_required || (is_signed && endpoint_group == EndpointGroup::Read)
+        {
+            endpoint_groups.insert(endpoint_group);
+        }
+    }
 
-    let mut user_tokens = HashSet::with_capacity(2);
+    // deletion URL tokens are not granted here since they are only valid for a single file,
+    // they are verified by the delete handler instead
+    let has_deletion_token = req.method() == Method::DELETE
+        && config.server.delete_url_secret.is_some()
+        && get_deletion_token(req.query_string()).is_some();
+    if delete_tokens
+        .as_ref()
+        .is_some_and(|tokens| tokens.contains(auth_header))
+    {
+        endpoint_groups.insert(EndpointGroup::Delete);
+    } else if delete_tokens.is_none() && req.method() == Method::DELETE && !has_deletion_token {
+        // explicitly disable `DELETE` methods if no `delete_tokens` are set
+        warn!("delete endpoint is not served because there are no delete_tokens set");
+        Err(error::ErrorNotFound(""))?;
+    }
+
+    Ok(endpoint_groups)
+}
+
+/// Returns `true` if the request contains one of the [`auth_tokens`].
+///
+/// Not configured `auth_tokens` means that the user is allowed to access the endpoints.
+///
+/// [`auth_tokens`]: crate::config::ServerConfig::auth_tokens
+pub(crate) fn is_authorized(headers: &HeaderMap, config: &Config) -> bool {
+    let auth_header = get_auth_token(headers, &config.server).unwrap_or_default();
+    config
+        .get_tokens(TokenType::Auth)
+        .is_none_or(|tokens| tokens.contains(auth_header))
+}
 
-    let auth_header = req
-        .headers()
-        .get(AUTHORIZATION)
+/// Returns the token in the authorization header.
+///
+/// If [`auth_header`] is set and present in the request, it takes precedence over the
+/// `Authorization` header.
+///
+/// [`auth_header`]: crate::config::ServerConfig::auth_header
+pub(crate) fn get_auth_token<'a>(
+    headers: &'a HeaderMap,
+    server_config: &ServerConfig,
+) -> Option<&'a str> {
+    server_config
+        .auth_header
+        .as_ref()
+        .and_then(|name| headers.get(name.as_str()))
+        .or_else(|| headers.get(AUTHORIZATION))
         .map(|v| v.to_str().unwrap_or_default())
-        .map(|v| v.split_whitespace().last().unwrap_or_default());
+        .map(|v| v.split_whitespace().last().unwrap_or_default())
+}
 
-    for token_type in [TokenType::Auth, TokenType::Delete] {
-        let maybe_tokens = config.get_tokens(token_type);
-        if let Some(configured_tokens) = maybe_tokens {
-            if configured_tokens.contains(auth_header.unwrap_or_default()) {
-                user_tokens.insert(token_type);
-            }
-        } else if token_type == TokenType::Auth {
-            // not configured `auth_tokens` means that the user is allowed to access the endpoints
-            user_tokens.insert(token_type);
-        } else if token_type == TokenType::Delete && req.method() == Method::DELETE {
-            // explicitly disable `DELETE` methods if no `delete_tokens` are set
-            warn!("delete endpoint is not served because there are no delete_tokens set");
-            Err(error::ErrorNotFound(""))?;
+/// Returns the HMAC-SHA256 signature of the given path and expiration timestamp (in seconds).
+pub(crate) fn sign_path(secret: &str, path: &str, expiration: u64) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    encode_hex(hmac::sign(&key, format!("{path}:{expiration}").as_bytes()).as_ref())
+}
+
+/// Returns the owner (HMAC-SHA256 signature) of the files that are uploaded with the given token.
+///
+/// The token cannot be verified from the stored owners without the secret.
+pub(crate) fn sign_owner(secret: &str, token: &str) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    encode_hex(hmac::sign(&key, format!("owner:{token}").as_bytes()).as_ref())
+}
+
+/// Returns the deletion token (HMAC-SHA256 signature) of the given file name and checksum.
+///
+/// The checksum of the content binds the token to the uploaded file, so that it cannot be used
+/// for deleting another file that is uploaded with the same name later on.
+pub(crate) fn sign_deletion(secret: &str, file_name: &str, checksum: &str) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    encode_hex(hmac::sign(&key, format!("delete:{file_name}:{checksum}").as_bytes()).as_ref())
+}
+
+/// Returns the deletion token in the given query string.
+pub(crate) fn get_deletion_token(query: &str) -> Option<String> {
+    url::form_urlencoded::parse(query.as_bytes())
+        .find(|(key, _)| key == DELETION_TOKEN_PARAM)
+        .map(|(_, value)| value.to_string())
+}
+
+/// Verifies the deletion token of the given file name and checksum.
+///
+/// Returns forbidden (`403`) error if the token is invalid.
+pub(crate) fn verify_deletion_token(
+    secret: &str,
+    file_name: &str,
+    checksum: &str,
+    token: &str,
+) -> Result<(), Error> {
+    let token = decode_hex(token).ok_or_else(|| error::ErrorForbidden("invalid token\n"))?;
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    hmac::verify(
+        &key,
+        format!("delete:{file_name}:{checksum}").as_bytes(),
+        &token,
+    )
+    .map_err(|_| error::ErrorForbidden("invalid token\n"))
+}
+
+/// Returns `true` if the given query string contains a signature.
+pub(crate) fn has_signature(query: &str) -> bool {
+    url::form_urlencoded::parse(query.as_bytes()).any(|(key, _)| key == SIGNATURE_PARAM)
+}
+
+/// Verifies the signature in the given query string for the path.
+///
+/// Returns forbidden (`403`) error if the signature is invalid or expired.
+pub(crate) fn verify_signature(
+    secret: &str,
+    path: &str,
+    query: &str,
+    time: Duration,
+) -> Result<(), Error> {
+    let mut expiration = None;
+    let mut signature = None;
+    for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+        if key == SIGNATURE_EXPIRATION_PARAM {
+            expiration = value.parse::<u64>().ok();
+        } else if key == SIGNATURE_PARAM {
+            signature = decode_hex(&value);
         }
     }
+    let (expiration, signature) = match (expiration, signature) {
+        (Some(expiration), Some(signature)) => (expiration, signature),
+        _ => return Err(error::ErrorForbidden("invalid signature\n")),
+    };
+    if time.as_secs() > expiration {
+        return Err(error::ErrorForbidden("signature is expired\n"));
+    }
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    hmac::verify(&key, format!("{path}:{expiration}").as_bytes(), &signature)
+        .map_err(|_| error::ErrorForbidden("invalid signature\n"))
+}
 
-    Ok(user_tokens)
+/// Encodes the given bytes as a hexadecimal string.
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut output, b| {
+        let _ = write!(output, "{b:02x}");
+        output
+    })
+}
+
+/// Decodes the given hexadecimal string.
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    if !value.is_ascii() || !value.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&value[i..i + 2], 16).ok())
+        .collect()
 }
 
 /// Returns `HttpResponse` with unauthorized (`401`) error and `unauthorized\n` as body.
@@ -53,8 +352,20 @@ pub(crate) fn unauthorized_error() -> actix_web::HttpResponse {
 pub(crate) fn handle_unauthorized_error<B>(
     res: ServiceResponse<B>,
 ) -> actix_web::Result<ErrorHandlerResponse<B>> {
-    let connection = res.request().connection_info().clone();
-    let host = connection.realip_remote_addr().unwrap_or("unknown host");
+    let server_config = res
+        .request()
+        .app_data::<web::Data<RwLock<Config>>>()
+        .and_then(|cfg| cfg.read().ok())
+        .map(|cfg| cfg.server.clone())
+        .unwrap_or_default();
+    let host = ClientConnection::from_request(res.request(), &server_config).remote_addr;
+    if let (Some(tracker), Some(limit_config)) = (
+        res.request().app_data::<web::Data<AuthFailureTracker>>(),
+        &server_config.auth_failure_limit,
+    ) {
+        tracker.record_failure(&host, limit_config);
+    }
+    let identity = header::get_log_identity(res.request(), &server_config);
 
     #[cfg(debug_assertions)]
     {
@@ -65,10 +376,10 @@ pub(crate) fn handle_unauthorized_error<B>(
             .and_then(|v| v.to_str().ok())
             .unwrap_or("none");
 
-        warn!("authorization failure for {host} (token: {auth_header})",);
+        warn!("authorization failure for {identity} (token: {auth_header})",);
     }
     #[cfg(not(debug_assertions))]
-    warn!("authorization failure for {host}");
+    warn!("authorization failure for {identity}");
 
     Ok(ErrorHandlerResponse::Response(res.map_into_left_body()))
 }
@@ -76,12 +387,21 @@ pub(crate) fn handle_unauthorized_error<B>(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::AuthConfig;
     use actix_web::http::header::HeaderValue;
     use actix_web::test::TestRequest;
     use actix_web::web::Data;
     use actix_web::HttpResponse;
     use awc::http::StatusCode;
 
+    /// Endpoint groups that are granted for the authorized requests.
+    const AUTHORIZED_GROUPS: [EndpointGroup; 4] = [
+        EndpointGroup::Read,
+        EndpointGroup::Write,
+        EndpointGroup::List,
+        EndpointGroup::Sign,
+    ];
+
     #[actix_web::test]
     async fn test_extract_tokens() -> Result<(), Error> {
         let mut config = Config::default();
@@ -92,7 +412,7 @@ mod tests {
             .insert_header((AUTHORIZATION, HeaderValue::from_static("basic test_token")))
             .to_srv_request();
         let tokens = extract_tokens(&request).await?;
-        assert_eq!(HashSet::from([TokenType::Auth]), tokens);
+        assert_eq!(HashSet::from(AUTHORIZED_GROUPS), tokens);
 
         // request with configured auth-tokens
         config.server.auth_tokens = Some(["test_token".to_string()].into());
@@ -101,7 +421,7 @@ mod tests {
             .insert_header((AUTHORIZATION, HeaderValue::from_static("basic test_token")))
             .to_srv_request();
         let tokens = extract_tokens(&request).await?;
-        assert_eq!(HashSet::from([TokenType::Auth]), tokens);
+        assert_eq!(HashSet::from(AUTHORIZED_GROUPS), tokens);
 
         // request with configured auth-tokens but wrong token in request
         config.server.auth_tokens = Some(["test_token".to_string()].into());
@@ -143,7 +463,16 @@ mod tests {
             ))
             .to_srv_request();
         let tokens = extract_tokens(&request).await?;
-        assert_eq!(HashSet::from([TokenType::Delete]), tokens);
+        assert_eq!(HashSet::from([EndpointGroup::Delete]), tokens);
+
+        // request with an auth token is not allowed to delete
+        let request = TestRequest::default()
+            .method(Method::DELETE)
+            .app_data(Data::new(RwLock::new(config.clone())))
+            .insert_header((AUTHORIZATION, HeaderValue::from_static("basic test_token")))
+            .to_srv_request();
+        let tokens = extract_tokens(&request).await?;
+        assert_eq!(HashSet::from(AUTHORIZED_GROUPS), tokens);
 
         // DELETE request with configured delete-tokens but wrong token in request
         let request = TestRequest::default()
@@ -157,6 +486,196 @@ mod tests {
         let tokens = extract_tokens(&request).await?;
         assert_eq!(HashSet::new(), tokens);
 
+        // DELETE request with a deletion URL token is verified by the handler
+        config.server.delete_url_secret = Some(String::from("secret"));
+        let request = TestRequest::default()
+            .method(Method::DELETE)
+            .uri("/file.txt?token=invalid_token")
+            .app_data(Data::new(RwLock::new(config.clone())))
+            .to_srv_request();
+        let tokens = extract_tokens(&request).await?;
+        assert_eq!(HashSet::new(), tokens);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_extract_tokens_with_auth_header() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.auth_tokens = Some(["test_token".to_string()].into());
+        config.server.auth_header = Some(String::from("X-Auth-Token"));
+
+        for (headers, expected) in [
+            (
+                vec![("x-auth-token", "test_token")],
+                HashSet::from(AUTHORIZED_GROUPS),
+            ),
+            (
+                vec![("authorization", "basic test_token")],
+                HashSet::from(AUTHORIZED_GROUPS),
+            ),
+            (
+                vec![
+                    ("x-auth-token", "test_token"),
+                    ("authorization", "basic gateway_token"),
+                ],
+                HashSet::from(AUTHORIZED_GROUPS),
+            ),
+            (
+                vec![
+                    ("x-auth-token", "invalid_token"),
+                    ("authorization", "basic test_token"),
+                ],
+                HashSet::new(),
+            ),
+        ] {
+            let mut request =
+                TestRequest::default().app_data(Data::new(RwLock::new(config.clone())));
+            for header in headers {
+                request = request.insert_header(header);
+            }
+            let tokens = extract_tokens(&request.to_srv_request()).await?;
+            assert_eq!(expected, tokens);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_deletion_token() -> Result<(), Error> {
+        let token = sign_deletion("secret", "file.txt", "checksum");
+        assert_eq!(
+            Some(token.clone()),
+            get_deletion_token(&format!("token={token}"))
+        );
+        verify_deletion_token("secret", "file.txt", "checksum", &token)?;
+        assert!(verify_deletion_token("secret", "other.txt", "checksum", &token).is_err());
+        assert!(verify_deletion_token("secret", "file.txt", "other", &token).is_err());
+        assert!(verify_deletion_token("other", "file.txt", "checksum", &token).is_err());
+        assert!(verify_deletion_token("secret", "file.txt", "checksum", "xyz").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sign_owner() -> Result<(), Error> {
+        let owner = sign_owner("secret", "token");
+        assert_eq!(owner, sign_owner("secret", "token"));
+        assert_ne!(owner, sign_owner("other", "token"));
+        assert_ne!(owner, sign_owner("secret", "other"));
+        assert_ne!(owner, util::sha256_digest("token".as_bytes())?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_signature() -> Result<(), Error> {
+        let time = Duration::from_secs(1000);
+        let signature = sign_path("secret", "file.txt", 2000);
+        let query = format!("exp=2000&sig={signature}");
+        verify_signature("secret", "file.txt", &query, time)?;
+        assert!(has_signature(&query));
+        assert!(!has_signature("download=true"));
+
+        let status = |result: Result<(), Error>| result.err().map(|e| e.error_response().status());
+        assert_eq!(
+            Some(StatusCode::FORBIDDEN),
+            status(verify_signature(
+                "secret",
+                "file.txt",
+                &query,
+                Duration::from_secs(3000)
+            ))
+        );
+        assert_eq!(
+            Some(StatusCode::FORBIDDEN),
+            status(verify_signature("secret", "other.txt", &query, time))
+        );
+        assert_eq!(
+            Some(StatusCode::FORBIDDEN),
+            status(verify_signature("other", "file.txt", &query, time))
+        );
+        assert_eq!(
+            Some(StatusCode::FORBIDDEN),
+            status(verify_signature(
+                "secret",
+                "file.txt",
+                &format!("exp=3000&sig={signature}"),
+                time
+            ))
+        );
+        assert_eq!(
+            Some(StatusCode::FORBIDDEN),
+            status(verify_signature(
+                "secret",
+                "file.txt",
+                "exp=2000&sig=zz",
+                time
+            ))
+        );
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_extract_tokens_with_auth_config() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.auth_tokens = Some(["test_token".to_string()].into());
+        config.server.auth = Some(AuthConfig {
+            read: false,
+            write: true,
+            list: false,
+            read_paths: HashMap::from([(String::from("private"), true)]),
+        });
+
+        // public read and list requests without a token
+        let request = TestRequest::get()
+            .uri("/file.txt")
+            .app_data(Data::new(RwLock::new(config.clone())))
+            .to_srv_request();
+        let tokens = extract_tokens(&request).await?;
+        assert_eq!(
+            HashSet::from([EndpointGroup::Read, EndpointGroup::List]),
+            tokens
+        );
+
+        // read requests in a private directory are checked while serving
+        let request = TestRequest::get()
+            .uri("/private/file.txt")
+            .app_data(Data::new(RwLock::new(config.clone())))
+            .to_srv_request();
+        let tokens = extract_tokens(&request).await?;
+        assert_eq!(
+            HashSet::from([EndpointGroup::Read, EndpointGroup::List]),
+            tokens
+        );
+
+        // protected write request without a token
+        let request = TestRequest::post()
+            .uri("/")
+            .app_data(Data::new(RwLock::new(config.clone())))
+            .to_srv_request();
+        let tokens = extract_tokens(&request).await?;
+        assert!(!tokens.contains(&EndpointGroup::Write));
+
+        // public read request without a signature
+        config.server.signing_secret = Some(String::from("secret"));
+        let request = TestRequest::get()
+            .uri("/file.txt")
+            .app_data(Data::new(RwLock::new(config.clone())))
+            .to_srv_request();
+        let tokens = extract_tokens(&request).await?;
+        assert!(tokens.contains(&EndpointGroup::Read));
+
+        // protected read request without a token
+        config.server.auth = Some(AuthConfig {
+            read: true,
+            ..Default::default()
+        });
+        let request = TestRequest::get()
+            .uri("/file.txt")
+            .app_data(Data::new(RwLock::new(config.clone())))
+            .to_srv_request();
+        let tokens = extract_tokens(&request).await?;
+        assert_eq!(HashSet::new(), tokens);
+
         Ok(())
     }
 }
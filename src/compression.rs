@@ -0,0 +1,291 @@
+use crate::config::{CompressionAlgorithm, CompressionConfig};
+use actix_web::web::Bytes;
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression;
+use futures_util::stream::{self, Stream, StreamExt};
+use mime::Mime;
+use std::io::{Error as IoError, Result as IoResult, Write};
+use std::mem;
+
+/// Default minimum size of the files to compress.
+pub const DEFAULT_MIN_SIZE: u64 = 1024;
+
+/// Default maximum size of the files to compress.
+pub const DEFAULT_MAX_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Subtypes of the `application` MIME types that are already compressed.
+const COMPRESSED_APPLICATION_SUBTYPES: &[&str] = &[
+    "zip",
+    "gzip",
+    "x-gzip",
+    "x-bzip2",
+    "x-xz",
+    "zstd",
+    "x-7z-compressed",
+    "x-rar-compressed",
+    "vnd.rar",
+    "java-archive",
+    "epub+zip",
+];
+
+/// Default compression level.
+pub const DEFAULT_LEVEL: u32 = 6;
+
+/// Default algorithms in the order of preference.
+pub const DEFAULT_ALGORITHMS: &[CompressionAlgorithm] =
+    &[CompressionAlgorithm::Gzip, CompressionAlgorithm::Deflate];
+
+impl CompressionAlgorithm {
+    /// Returns the value of the `Content-Encoding` header.
+    pub fn encoding(&self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Deflate => "deflate",
+        }
+    }
+
+    /// Compresses the given data with the given level.
+    pub fn compress(&self, data: &[u8], level: u32) -> IoResult<Vec<u8>> {
+        let level = Compression::new(level.min(9));
+        match self {
+            Self::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), level);
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            Self::Deflate => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), level);
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+/// Encoder that compresses the data chunk by chunk.
+enum StreamEncoder {
+    /// Gzip encoder.
+    Gzip(GzEncoder<Vec<u8>>),
+    /// Deflate (zlib) encoder.
+    Deflate(ZlibEncoder<Vec<u8>>),
+}
+
+impl StreamEncoder {
+    /// Compresses the given chunk and returns the compressed data that is ready so far.
+    fn write(&mut self, data: &[u8]) -> IoResult<Bytes> {
+        let buffer = match self {
+            Self::Gzip(encoder) => {
+                encoder.write_all(data)?;
+                encoder.get_mut()
+            }
+            Self::Deflate(encoder) => {
+                encoder.write_all(data)?;
+                encoder.get_mut()
+            }
+        };
+        Ok(Bytes::from(mem::take(buffer)))
+    }
+
+    /// Returns the rest of the compressed data.
+    fn finish(self) -> IoResult<Bytes> {
+        match self {
+            Self::Gzip(encoder) => encoder.finish(),
+            Self::Deflate(encoder) => encoder.finish(),
+        }
+        .map(Bytes::from)
+    }
+}
+
+/// Compresses the given stream with the given algorithm and level without collecting it.
+pub fn compress_stream<S, E>(
+    stream: S,
+    algorithm: CompressionAlgorithm,
+    level: u32,
+) -> impl Stream<Item = Result<Bytes, E>>
+where
+    S: Stream<Item = Result<Bytes, E>>,
+    E: From<IoError>,
+{
+    let level = Compression::new(level.min(9));
+    let encoder = match algorithm {
+        CompressionAlgorithm::Gzip => StreamEncoder::Gzip(GzEncoder::new(Vec::new(), level)),
+        CompressionAlgorithm::Deflate => {
+            StreamEncoder::Deflate(ZlibEncoder::new(Vec::new(), level))
+        }
+    };
+    stream::unfold(
+        (Box::pin(stream), Some(encoder)),
+        |(mut stream, encoder)| async move {
+            let mut encoder = encoder?;
+            match stream.next().await {
+                Some(Ok(chunk)) => {
+                    let chunk = encoder.write(&chunk).map_err(E::from);
+                    Some((chunk, (stream, Some(encoder))))
+                }
+                Some(Err(e)) => Some((Err(e), (stream, None))),
+                None => Some((encoder.finish().map_err(E::from), (stream, None))),
+            }
+        },
+    )
+}
+
+impl CompressionConfig {
+    /// Returns the algorithm for compressing a file with the given size and MIME type.
+    ///
+    /// Returns `None` if compression is disabled, the file size is not between [`min_size`]
+    /// and [`max_size`], the file is already compressed (see [`is_compressed`])
+    /// or the client does not accept any of the configured algorithms.
+    ///
+    /// [`min_size`]: CompressionConfig::min_size
+    /// [`max_size`]: CompressionConfig::max_size
+    pub fn get_algorithm(
+        &self,
+        accept_encoding: &str,
+        size: u64,
+        mime_type: &Mime,
+    ) -> Option<CompressionAlgorithm> {
+        let min_size = self
+            .min_size
+            .map(|v| v.as_u64())
+            .unwrap_or(DEFAULT_MIN_SIZE);
+        let max_size = self
+            .max_size
+            .map(|v| v.as_u64())
+            .unwrap_or(DEFAULT_MAX_SIZE);
+        if !self.enabled || size < min_size || size > max_size || is_compressed(mime_type) {
+            return None;
+        }
+        negotiate(
+            accept_encoding,
+            self.algorithms.as_deref().unwrap_or(DEFAULT_ALGORITHMS),
+        )
+    }
+}
+
+/// Returns `true` if the files with the given MIME type are already compressed.
+///
+/// Images (except SVG), audio, video and archives do not benefit from compression.
+pub fn is_compressed(mime_type: &Mime) -> bool {
+    let (type_, subtype) = (mime_type.type_(), mime_type.subtype());
+    if type_ == mime::IMAGE {
+        subtype != mime::SVG
+    } else if type_ == mime::AUDIO || type_ == mime::VIDEO {
+        true
+    } else if type_ == mime::APPLICATION {
+        COMPRESSED_APPLICATION_SUBTYPES.contains(&subtype.as_str())
+    } else {
+        false
+    }
+}
+
+/// Returns the first algorithm that is accepted by the client.
+///
+/// Encodings with the quality value of zero (e.g. `gzip;q=0`) are not accepted.
+pub fn negotiate(
+    accept_encoding: &str,
+    algorithms: &[CompressionAlgorithm],
+) -> Option<CompressionAlgorithm> {
+    let accepted = accept_encoding
+        .split(',')
+        .filter_map(|v| {
+            let mut parts = v.split(';').map(str::trim);
+            let encoding = parts.next()?.to_ascii_lowercase();
+            let quality = parts
+                .find_map(|v| v.strip_prefix("q="))
+                .and_then(|v| v.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            Some((encoding, quality))
+        })
+        .collect::<Vec<(String, f32)>>();
+    algorithms.iter().copied().find(|algorithm| {
+        accepted
+            .iter()
+            .find(|(encoding, _)| encoding == algorithm.encoding())
+            .or_else(|| accepted.iter().find(|(encoding, _)| encoding == "*"))
+            .is_some_and(|(_, quality)| *quality > 0.0)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::{GzDecoder, ZlibDecoder};
+    use std::io::Read;
+
+    #[test]
+    fn test_negotiate() {
+        let algorithms = DEFAULT_ALGORITHMS;
+        assert_eq!(
+            Some(CompressionAlgorithm::Gzip),
+            negotiate("gzip, deflate, br", algorithms)
+        );
+        assert_eq!(
+            Some(CompressionAlgorithm::Deflate),
+            negotiate("deflate", algorithms)
+        );
+        assert_eq!(
+            Some(CompressionAlgorithm::Deflate),
+            negotiate("GZIP;q=0, deflate;q=0.5", algorithms)
+        );
+        assert_eq!(Some(CompressionAlgorithm::Gzip), negotiate("*", algorithms));
+        assert_eq!(
+            Some(CompressionAlgorithm::Deflate),
+            negotiate("gzip, deflate", &[CompressionAlgorithm::Deflate])
+        );
+        assert_eq!(None, negotiate("br", algorithms));
+        assert_eq!(None, negotiate("identity", algorithms));
+        assert_eq!(None, negotiate("", algorithms));
+    }
+
+    #[test]
+    fn test_is_compressed() {
+        assert!(is_compressed(&mime::IMAGE_PNG));
+        assert!(is_compressed(
+            &"video/mp4".parse().expect("valid MIME type")
+        ));
+        assert!(is_compressed(
+            &"application/zip".parse().expect("valid MIME type")
+        ));
+        assert!(is_compressed(
+            &"application/gzip".parse().expect("valid MIME type")
+        ));
+        assert!(!is_compressed(&mime::IMAGE_SVG));
+        assert!(!is_compressed(&mime::TEXT_PLAIN_UTF_8));
+        assert!(!is_compressed(&mime::APPLICATION_JSON));
+        assert!(!is_compressed(&mime::APPLICATION_OCTET_STREAM));
+    }
+
+    #[test]
+    fn test_compress() -> IoResult<()> {
+        let data = "rustypaste ".repeat(100);
+
+        let mut decompressed = String::new();
+        let compressed = CompressionAlgorithm::Gzip.compress(data.as_bytes(), DEFAULT_LEVEL)?;
+        assert!(compressed.len() < data.len());
+        GzDecoder::new(compressed.as_slice()).read_to_string(&mut decompressed)?;
+        assert_eq!(data, decompressed);
+
+        let mut decompressed = String::new();
+        let compressed = CompressionAlgorithm::Deflate.compress(data.as_bytes(), 9)?;
+        assert!(compressed.len() < data.len());
+        ZlibDecoder::new(compressed.as_slice()).read_to_string(&mut decompressed)?;
+        assert_eq!(data, decompressed);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_compress_stream() -> IoResult<()> {
+        let chunks = (0..100).map(|_| Ok::<Bytes, IoError>(Bytes::from_static(b"rusty")));
+        let compressed = compress_stream(stream::iter(chunks), CompressionAlgorithm::Gzip, 6)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<IoResult<Vec<Bytes>>>()?
+            .concat();
+        let mut decompressed = String::new();
+        GzDecoder::new(compressed.as_slice()).read_to_string(&mut decompressed)?;
+        assert_eq!("rusty".repeat(100), decompressed);
+        Ok(())
+    }
+}
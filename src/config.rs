@@ -1,11 +1,15 @@
-use crate::mime::MimeMatcher;
-use crate::random::RandomURLConfig;
+use crate::mime::{MimeExpiryMatcher, MimeMatcher};
+use crate::paste::PasteType;
+use crate::random::{RandomURLConfig, RandomURLType};
 use crate::{AUTH_TOKENS_FILE_ENV, AUTH_TOKEN_ENV, DELETE_TOKENS_FILE_ENV, DELETE_TOKEN_ENV};
 use byte_unit::Byte;
+use chrono_tz::Tz;
 use config::{self, ConfigError};
-use std::collections::HashSet;
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs::read_to_string;
+use std::net::IpAddr;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 
@@ -21,6 +25,16 @@ pub struct Config {
     pub paste: PasteConfig,
     /// Landing page configuration.
     pub landing_page: Option<LandingPageConfig>,
+    /// Virus scanner configuration.
+    pub scan: Option<ScanConfig>,
+    /// Response compression configuration.
+    pub compression: Option<CompressionConfig>,
+    /// Cache configuration.
+    pub cache: Option<CacheConfig>,
+    /// Archive upload configuration.
+    pub archive: Option<ArchiveConfig>,
+    /// Image transcoding configuration.
+    pub transcode: Option<TranscodeConfig>,
 }
 
 /// General settings for configuration.
@@ -35,13 +49,35 @@ pub struct Settings {
 #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct ServerConfig {
     /// The socket address to bind.
+    ///
+    /// Unix domain sockets are supported in the `unix:/path/to.sock` form.
     pub address: String,
+    /// Permissions of the Unix domain sockets (e.g. `0o660`).
+    pub socket_mode: Option<u32>,
+    /// The socket address to bind for serving the administrative routes only.
+    ///
+    /// If set, the administrative routes are not served on [`address`].
+    ///
+    /// [`address`]: Self::address
+    pub admin_address: Option<String>,
     /// URL that can be used to access the server externally.
     pub url: Option<String>,
+    /// Redirect the requests that are sent to another host to the host of [`url`].
+    ///
+    /// [`url`]: Self::url
+    pub canonical_redirect: Option<bool>,
+    /// Path prefix of the URLs (e.g. when served behind a reverse proxy).
+    pub url_path_prefix: Option<String>,
+    /// Addresses of the reverse proxies whose forwarding headers are trusted.
+    pub trusted_proxies: Option<Vec<IpAddr>>,
     /// Number of workers to start.
     pub workers: Option<usize>,
+    /// Maximum number of concurrent connections per worker.
+    pub max_connections: Option<usize>,
     /// Maximum content length.
     pub max_content_length: Byte,
+    /// Maximum size of the URL fields (`remote`, `url` and `oneshot_url`).
+    pub max_url_size: Option<Byte>,
     /// Storage path.
     pub upload_path: PathBuf,
     /// Maximum upload directory size.
@@ -49,11 +85,59 @@ pub struct ServerConfig {
     /// Request timeout.
     #[serde(default, with = "humantime_serde")]
     pub timeout: Option<Duration>,
+    /// Maximum time to wait for the next chunk of an upload.
+    #[serde(default, with = "humantime_serde")]
+    pub body_read_timeout: Option<Duration>,
+    /// Maximum time to wait for a file that is being written before serving it.
+    ///
+    /// The requests for such files are rejected immediately by default.
+    #[serde(default, with = "humantime_serde")]
+    pub write_wait_timeout: Option<Duration>,
+    /// Maximum time to wait for the configuration lock while the configuration is updated.
+    ///
+    /// The requests are rejected with `503` after the timeout.
+    /// The requests wait until the update is finished by default.
+    #[serde(default, with = "humantime_serde")]
+    pub config_lock_timeout: Option<Duration>,
     /// Authentication token.
     #[deprecated(note = "use [server].auth_tokens instead")]
     pub auth_token: Option<String>,
     /// Authentication tokens.
     pub auth_tokens: Option<HashSet<String>>,
+    /// Alternative header for the authentication tokens (e.g. `X-Auth-Token`).
+    ///
+    /// Takes precedence over the `Authorization` header if both are present.
+    pub auth_header: Option<String>,
+    /// Accept the `x-created-at` header for preserving the creation dates of the uploads
+    /// (e.g. while importing the old pastes).
+    ///
+    /// Only honored if the authentication tokens are configured.
+    pub allow_created_at: Option<bool>,
+    /// Authentication tokens with their own upload settings.
+    pub token_settings: Option<HashMap<String, TokenSettings>>,
+    /// Authentication requirements of the endpoint groups.
+    pub auth: Option<AuthConfig>,
+    /// Limits for the failed authentication attempts.
+    pub auth_failure_limit: Option<AuthFailureLimitConfig>,
+    /// Header to use for identifying the clients in the logs instead of the IP address.
+    pub log_identity_header: Option<String>,
+    /// Log a digest of the file names instead of the names themselves.
+    pub log_redact_filenames: Option<bool>,
+    /// Secret key for signing the URLs.
+    ///
+    /// Signed URLs can be used for reading the files without a token.
+    pub signing_secret: Option<String>,
+    /// Secret key for signing the deletion URLs that are returned at upload time.
+    ///
+    /// The deletion URLs are only valid with the `DELETE` method.
+    pub delete_url_secret: Option<String>,
+    /// Secret key for identifying the owners (authentication tokens) of the files.
+    ///
+    /// Required for the per-token [`dedup_scope`] and the [`quota`] of the tokens.
+    ///
+    /// [`dedup_scope`]: PasteConfig::dedup_scope
+    /// [`quota`]: TokenSettings::quota
+    pub owner_secret: Option<String>,
     /// Expose version.
     pub expose_version: Option<bool>,
     /// Landing page text.
@@ -64,10 +148,52 @@ pub struct ServerConfig {
     pub landing_page_content_type: Option<String>,
     /// Handle spaces either via encoding or replacing.
     pub handle_spaces: Option<SpaceHandlingConfig>,
+    /// Store the conflicting uploads with a numeric suffix if their names may be the result of
+    /// replacing the spaces (e.g. `a b.txt` and `a_b.txt`).
+    ///
+    /// Only applies if [`handle_spaces`] is set to `replace`.
+    ///
+    /// [`handle_spaces`]: Self::handle_spaces
+    pub avoid_space_collisions: Option<bool>,
     /// Path of the JSON index.
     pub expose_list: Option<bool>,
+    /// Compress the file list if the client accepts it (via `Accept-Encoding`).
+    ///
+    /// This is independent of the [`compression`] of the served files.
+    ///
+    /// [`compression`]: Config::compression
+    pub compress_list: Option<bool>,
+    /// Maintain a `manifest.json` file in the upload directory that lists the files
+    /// in the same format as the list endpoint.
+    ///
+    /// The manifest is updated after the files are uploaded, deleted or extended.
+    pub write_manifest: Option<bool>,
+    /// Timezone (IANA name) for displaying the dates in the file list.
+    pub display_timezone: Option<Tz>,
+    /// Expose the effective configuration.
+    pub expose_config: Option<bool>,
+    /// Expose the OpenAPI description of the routes.
+    pub expose_openapi: Option<bool>,
+    /// Contents of `/robots.txt`.
+    ///
+    /// All of the crawlers are disallowed by default.
+    pub robots_txt: Option<String>,
     /// Authentication tokens for deleting.
     pub delete_tokens: Option<HashSet<String>>,
+    /// Maximum number of path segments allowed while serving files.
+    pub max_path_depth: Option<usize>,
+    /// Handling of the trailing slashes while serving files (e.g. `/file.txt/`).
+    pub trailing_slash: Option<TrailingSlash>,
+    /// Recreate the upload directory if it is missing at request time.
+    pub recreate_upload_path: Option<bool>,
+    /// Uploads are rejected while this file exists.
+    pub pause_file: Option<PathBuf>,
+    /// Minimum length of the authentication/deletion tokens.
+    pub min_token_length: Option<usize>,
+    /// Refuse to start if there are tokens shorter than [`min_token_length`].
+    ///
+    /// [`min_token_length`]: Self::min_token_length
+    pub reject_weak_tokens: Option<bool>,
 }
 
 /// Enum representing different strategies for handling spaces in filenames.
@@ -80,6 +206,18 @@ pub enum SpaceHandlingConfig {
     Replace,
 }
 
+impl ServerConfig {
+    /// Returns the [`URL path prefix`] without the leading and trailing slashes.
+    ///
+    /// [`URL path prefix`]: Self::url_path_prefix
+    pub fn get_url_path_prefix(&self) -> Option<&str> {
+        self.url_path_prefix
+            .as_deref()
+            .map(|v| v.trim_matches('/'))
+            .filter(|v| !v.is_empty())
+    }
+}
+
 impl SpaceHandlingConfig {
     /// Processes the given filename based on the specified space handling strategy.
     pub fn process_filename(&self, file_name: &str) -> String {
@@ -90,6 +228,71 @@ impl SpaceHandlingConfig {
     }
 }
 
+/// Authentication requirements of the endpoint groups.
+///
+/// Only applies if authentication tokens are configured.
+/// All of the endpoint groups require a token unless they are explicitly made public.
+/// Deleting files always requires one of the [`delete_tokens`].
+///
+/// [`delete_tokens`]: ServerConfig::delete_tokens
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct AuthConfig {
+    /// Require a token for serving files.
+    pub read: bool,
+    /// Require a token for uploading files.
+    pub write: bool,
+    /// Require a token for listing files and other metadata endpoints.
+    pub list: bool,
+    /// Overrides [`read`] for the files in the given directories (e.g. `private = true`).
+    ///
+    /// The deepest matching directory takes precedence.
+    ///
+    /// [`read`]: Self::read
+    pub read_paths: HashMap<String, bool>,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            read: true,
+            write: true,
+            list: true,
+            read_paths: HashMap::new(),
+        }
+    }
+}
+
+impl AuthConfig {
+    /// Returns `true` if the given endpoint group requires a token.
+    ///
+    /// See [`is_read_required`](Self::is_read_required) for reading the files.
+    pub fn is_required(&self, endpoint_group: EndpointGroup) -> bool {
+        match endpoint_group {
+            // the public `read_paths` are only known while serving the files
+            EndpointGroup::Read => self.read && self.read_paths.values().all(|v| *v),
+            EndpointGroup::Write => self.write,
+            EndpointGroup::List => self.list,
+            EndpointGroup::Delete | EndpointGroup::Sign => true,
+        }
+    }
+
+    /// Returns `true` if serving the file at the given path (e.g. `private/file.txt`) requires a token.
+    pub fn is_read_required(&self, path: &str) -> bool {
+        let path = path.trim_start_matches('/');
+        self.read_paths
+            .iter()
+            .map(|(dir, required)| (dir.trim_matches('/'), required))
+            .filter(|(dir, _)| {
+                path.strip_prefix(dir)
+                    .is_some_and(|rest| rest.starts_with('/'))
+            })
+            .max_by_key(|(dir, _)| dir.len())
+            .map(|(_, required)| *required)
+            .unwrap_or(self.read)
+    }
+}
+
 /// Landing page configuration.
 #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct LandingPageConfig {
@@ -99,6 +302,33 @@ pub struct LandingPageConfig {
     pub file: Option<String>,
     /// Landing page content-type
     pub content_type: Option<String>,
+    /// Landing pages for the hosts (e.g. `paste.example.com`).
+    #[serde(default)]
+    pub hosts: HashMap<String, LandingPageConfig>,
+}
+
+impl LandingPageConfig {
+    /// Returns the landing page for the given host.
+    ///
+    /// The port is ignored if there is no landing page for the exact host.
+    /// Falls back to the default landing page if there is no landing page for the host.
+    pub fn for_host(self, host: &str) -> LandingPageConfig {
+        let host_page = self
+            .hosts
+            .get(host)
+            .or_else(|| {
+                host.rsplit_once(':')
+                    .and_then(|(host, _)| self.hosts.get(host))
+            })
+            .cloned();
+        match host_page {
+            Some(host_page) => LandingPageConfig {
+                content_type: host_page.content_type.or(self.content_type),
+                ..host_page
+            },
+            None => self,
+        }
+    }
 }
 
 /// Paste configuration.
@@ -106,23 +336,189 @@ pub struct LandingPageConfig {
 pub struct PasteConfig {
     /// Random URL configuration.
     pub random_url: Option<RandomURLConfig>,
+    /// Random URL configurations per paste type, override [`random_url`].
+    ///
+    /// [`random_url`]: Self::random_url
+    pub random_url_by_type: Option<RandomURLByType>,
     /// Default file extension.
     pub default_extension: String,
+    /// Reject the uploads that do not have an extension after their name is processed.
+    pub require_extension: Option<bool>,
     /// Media type override options.
     #[serde(default)]
     pub mime_override: Vec<MimeMatcher>,
+    /// Media type overrides for file extensions.
+    #[serde(default)]
+    pub mime_by_extension: HashMap<String, String>,
+    /// Media type of the files that cannot be identified by their extension or contents.
+    ///
+    /// Defaults to `application/octet-stream`.
+    pub default_mime: Option<String>,
+    /// Replace the file extension with the detected one if they disagree.
+    pub trust_magic_extension: Option<bool>,
+    /// Reject the text uploads that only contain whitespace.
+    pub reject_blank_content: Option<bool>,
+    /// Remove a single trailing newline from the text uploads.
+    pub strip_trailing_newline: Option<bool>,
+    /// Lowercase the file extensions and replace the common aliases (e.g. `jpeg` -> `jpg`).
+    pub canonical_extensions: Option<bool>,
     /// Media type blacklist.
+    ///
+    /// Checked against both the detected media type and the one inferred from the file extension.
     #[serde(default)]
     pub mime_blacklist: Vec<String>,
+    /// Remove the stored files that are blacklisted when the configuration is reloaded.
+    pub delete_blacklisted_files: Option<bool>,
+    /// Extensions (e.g. `html`) or media types (e.g. `image/svg+xml`) of the files
+    /// that are always served as attachments.
+    #[serde(default)]
+    pub force_download: Vec<String>,
+    /// Extensions of the files that are never served (e.g. `php`), regardless of how they are stored.
+    #[serde(default)]
+    pub serve_blocked_extensions: Vec<String>,
+    /// Return `410 Gone` instead of `404 Not Found` for the files that are expired.
+    pub gone_for_expired: Option<bool>,
+    /// Maximum size of the files that are rendered as HTML (e.g. `?render`).
+    pub max_render_size: Option<Byte>,
     /// Allow duplicate uploads.
     pub duplicate_files: Option<bool>,
+    /// Scope of the duplicate detection.
+    pub dedup_scope: Option<DedupScope>,
+    /// Calculate the checksums of the existing files in the background on startup.
+    ///
+    /// The duplicate checks calculate the missing checksums on demand until this is finished.
+    pub warm_up_checksums: Option<bool>,
+    /// Strategy for handling the uploads with an existing file name.
+    pub on_conflict: Option<ConflictStrategy>,
+    /// Total number of bytes that a file can be served before it is expired (e.g. `1GB`).
+    ///
+    /// Can be overridden per upload via the `x-max-transfer` header.
+    pub max_transfer: Option<Byte>,
+    /// Maximum number of versions of a file to keep while [`on_conflict`] is `version`.
+    ///
+    /// The oldest versions are deleted when a new version is stored.
+    ///
+    /// [`on_conflict`]: Self::on_conflict
+    pub max_versions: Option<usize>,
+    /// Algorithm for calculating the checksums of the files.
+    pub checksum_algorithm: Option<ChecksumAlgorithm>,
+    /// Number of the directory levels to store the files in, based on their checksums.
+    ///
+    /// e.g. `2` for storing the files as `ab/cd/<file>`.
+    pub fanout_depth: Option<usize>,
+    /// Allowed URL schemes for shortening.
+    pub allowed_url_schemes: Option<Vec<String>>,
+    /// Maximum length of the shortened and remote URLs.
+    pub max_url_length: Option<usize>,
+    /// Origins (e.g. `https://example.com`) that are allowed to upload remote files.
+    ///
+    /// Checked against the `Origin` header or the origin of the `Referer` header.
+    pub remote_allowed_origins: Option<Vec<String>>,
+    /// Number of retries for fetching the remote files.
+    ///
+    /// Only the connection errors and server errors (5xx) are retried.
+    pub remote_retries: Option<u32>,
+    /// Delay before the first retry, doubled after each attempt.
+    #[serde(default, with = "humantime_serde")]
+    pub remote_retry_delay: Option<Duration>,
     /// Default expiry time.
     #[serde(default, with = "humantime_serde")]
     pub default_expiry: Option<Duration>,
+    /// Maximum expiry time that the files can be extended to.
+    #[serde(default, with = "humantime_serde")]
+    pub max_expiry: Option<Duration>,
+    /// Default expiry time overrides for media types.
+    #[serde(default)]
+    pub mime_expiry: Vec<MimeExpiryMatcher>,
+    /// Maximum lifetime of the oneshot files and URLs that are never accessed.
+    #[serde(default, with = "humantime_serde")]
+    pub oneshot_max_lifetime: Option<Duration>,
     /// Delete expired files.
     pub delete_expired_files: Option<CleanupConfig>,
 }
 
+impl PasteConfig {
+    /// Returns the random URL configuration for the given paste type.
+    pub fn get_random_url(&self, paste_type: PasteType) -> Option<&RandomURLConfig> {
+        self.random_url_by_type
+            .as_ref()
+            .and_then(|v| match paste_type {
+                PasteType::File => v.file.as_ref(),
+                PasteType::RemoteFile => v.remote.as_ref(),
+                PasteType::Oneshot => v.oneshot.as_ref(),
+                PasteType::Url => v.url.as_ref(),
+                PasteType::OneshotUrl => v.oneshot_url.as_ref(),
+            })
+            .or(self.random_url.as_ref())
+    }
+}
+
+/// Random URL configurations per paste type.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct RandomURLByType {
+    /// Random URL configuration for the files.
+    pub file: Option<RandomURLConfig>,
+    /// Random URL configuration for the remote files.
+    pub remote: Option<RandomURLConfig>,
+    /// Random URL configuration for the one shot files.
+    pub oneshot: Option<RandomURLConfig>,
+    /// Random URL configuration for the shortened URLs.
+    pub url: Option<RandomURLConfig>,
+    /// Random URL configuration for the one shot URLs.
+    pub oneshot_url: Option<RandomURLConfig>,
+}
+
+/// Enum representing the strategies for handling file name conflicts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictStrategy {
+    /// Reject the upload.
+    #[default]
+    Error,
+    /// Store the upload with the next free numeric suffix (e.g. `report-2.pdf`).
+    Version,
+    /// Replace the existing file.
+    Overwrite,
+}
+
+/// Enum representing the handling of the trailing slashes in the file URLs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrailingSlash {
+    /// Only serve the files without a trailing slash.
+    #[default]
+    Strict,
+    /// Redirect to the URL without the trailing slash (`301`).
+    Redirect,
+    /// Serve the file regardless of the trailing slash.
+    Trim,
+}
+
+/// Enum representing the scopes of the duplicate detection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DedupScope {
+    /// Match the files of all uploaders.
+    #[default]
+    Global,
+    /// Only match the files that are uploaded with the same authentication token.
+    ///
+    /// This prevents revealing the files of the other uploaders.
+    #[serde(alias = "per_namespace")]
+    PerToken,
+}
+
+/// Enum representing the supported checksum algorithms.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumAlgorithm {
+    /// SHA256.
+    #[default]
+    Sha256,
+    /// BLAKE3, which is faster for large files.
+    Blake3,
+}
+
 /// Cleanup configuration.
 #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct CleanupConfig {
@@ -131,8 +527,144 @@ pub struct CleanupConfig {
     /// Interval between clean-ups.
     #[serde(default, with = "humantime_serde")]
     pub interval: Duration,
+    /// Remove the empty subdirectories in the upload path.
+    pub remove_empty_dirs: Option<bool>,
+    /// Maximum random deviation from the interval for spreading the clean-ups of multiple instances.
+    #[serde(default, with = "humantime_serde")]
+    pub jitter: Option<Duration>,
+}
+
+impl CleanupConfig {
+    /// Returns the time to wait until the next clean-up.
+    ///
+    /// The [`interval`] is randomly shortened or extended by up to [`jitter`] if it is set.
+    ///
+    /// [`interval`]: Self::interval
+    /// [`jitter`]: Self::jitter
+    pub fn get_interval(&self) -> Duration {
+        match self.jitter.filter(|v| !v.is_zero()) {
+            Some(jitter) => {
+                let jitter_millis = u64::try_from(jitter.as_millis()).unwrap_or(u64::MAX);
+                let offset = rand::thread_rng().gen_range(0..=jitter_millis.saturating_mul(2));
+                self.interval
+                    .saturating_add(Duration::from_millis(offset))
+                    .saturating_sub(jitter)
+            }
+            None => self.interval,
+        }
+    }
+}
+
+/// Virus scanner configuration.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct ScanConfig {
+    /// Enable scanning the uploads.
+    pub enabled: bool,
+    /// Address of the ClamAV daemon (e.g. `127.0.0.1:3310` or `/run/clamav/clamd.ctl`).
+    pub address: String,
+    /// Timeout for connecting to and communicating with the scanner (defaults to 30 seconds).
+    #[serde(default, with = "humantime_serde")]
+    pub timeout: Option<Duration>,
+    /// Policy to apply when the scanner is unreachable.
+    #[serde(default)]
+    pub on_error: ScanFailurePolicy,
+}
+
+/// Enum representing the policies for handling scanner failures.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanFailurePolicy {
+    /// Accept the upload without scanning it.
+    Open,
+    /// Reject the upload.
+    #[default]
+    Closed,
+}
+
+/// Response compression configuration.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct CompressionConfig {
+    /// Enable compressing the served files.
+    pub enabled: bool,
+    /// Files smaller than this size are served uncompressed.
+    pub min_size: Option<Byte>,
+    /// Files larger than this size are served uncompressed (defaults to 10MB).
+    pub max_size: Option<Byte>,
+    /// Compression level (0-9).
+    pub level: Option<u32>,
+    /// Algorithms to use in the order of preference.
+    pub algorithms: Option<Vec<CompressionAlgorithm>>,
+}
+
+/// Configuration for uploading the files in an archive.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct ArchiveConfig {
+    /// Enable extracting the archives that are uploaded to `/archive`.
+    pub enabled: bool,
+    /// Maximum number of files in an archive.
+    pub max_entries: Option<usize>,
+    /// Maximum total size of the extracted files.
+    ///
+    /// Defaults to [`max_content_length`].
+    ///
+    /// [`max_content_length`]: ServerConfig::max_content_length
+    pub max_size: Option<Byte>,
+}
+
+/// Configuration for the `Cache-Control` header of the served files.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct CacheConfig {
+    /// Enable setting the `Cache-Control` header.
+    pub enabled: bool,
+    /// Maximum time for caching the files.
+    ///
+    /// Files with an expiry date are cached until they expire at most.
+    #[serde(default, with = "humantime_serde")]
+    pub max_age: Option<Duration>,
+    /// Mark the files without an expiry date as immutable.
+    pub immutable: Option<bool>,
+}
+
+/// Configuration for converting the uploaded images to another format.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct TranscodeConfig {
+    /// Enable transcoding the uploaded PNG and JPEG images.
+    pub enabled: bool,
+    /// Target format of the images.
+    #[serde(default)]
+    pub format: TranscodeFormat,
+    /// Quality of the lossy formats (1-100).
+    pub quality: Option<u8>,
+}
+
+/// Enum representing the target formats of transcoding.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscodeFormat {
+    /// WebP (lossless).
+    #[default]
+    Webp,
+    /// AVIF.
+    Avif,
+    /// JPEG.
+    Jpeg,
+    /// PNG.
+    Png,
 }
 
+/// Enum representing the supported compression algorithms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionAlgorithm {
+    /// Gzip.
+    Gzip,
+    /// Deflate (zlib).
+    Deflate,
+}
+
+/// Placeholder for the redacted configuration values.
+const REDACTED: &str = "***";
+
 /// Type of access token.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub enum TokenType {
@@ -142,14 +674,70 @@ pub enum TokenType {
     Delete,
 }
 
+/// Upload settings that are specific to an authentication token.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct TokenSettings {
+    /// Default expiry time, overrides [`PasteConfig::default_expiry`].
+    #[serde(default, with = "humantime_serde")]
+    pub default_expiry: Option<Duration>,
+    /// Use random URLs, overrides [`PasteConfig::random_url`].
+    pub random_url: Option<bool>,
+    /// Maximum total size of the unexpired files and one shot files that are uploaded with the token.
+    pub quota: Option<Byte>,
+}
+
+/// Configuration for limiting the failed authentication attempts per client.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AuthFailureLimitConfig {
+    /// Number of failed attempts before the client is blocked.
+    pub max_attempts: u32,
+    /// Time window for counting the failed attempts.
+    #[serde(with = "humantime_serde")]
+    pub window: Duration,
+    /// Duration of the block.
+    #[serde(with = "humantime_serde")]
+    pub block_duration: Duration,
+    /// Delay that is doubled for each failed attempt.
+    #[serde(default, with = "humantime_serde")]
+    pub delay: Option<Duration>,
+}
+
+/// Group of endpoints that share the same authentication requirement.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum EndpointGroup {
+    /// Endpoints for serving files.
+    Read,
+    /// Endpoints for uploading files.
+    Write,
+    /// Endpoints for deleting files.
+    Delete,
+    /// Endpoints for listing files and other metadata.
+    List,
+    /// Endpoint for signing URLs.
+    Sign,
+}
+
 impl Config {
     /// Parses the config file and returns the values.
+    ///
+    /// The deserialization errors contain the path of the invalid field (e.g. `server.timeout`)
+    /// and the config file.
     pub fn parse(path: &Path) -> Result<Config, ConfigError> {
-        config::Config::builder()
+        let config = config::Config::builder()
             .add_source(config::File::from(path))
             .add_source(config::Environment::default().separator("__"))
-            .build()?
-            .try_deserialize()
+            .build()?;
+        serde_path_to_error::deserialize(config).map_err(|e| {
+            let field = e.path().to_string();
+            ConfigError::Message(match field.as_str() {
+                "." => format!("{} ({})", e.inner(), path.display()),
+                _ => format!(
+                    "invalid value for `{field}`: {} ({})",
+                    e.inner(),
+                    path.display()
+                ),
+            })
+        })
     }
 
     /// Retrieves all configured auth/delete tokens.
@@ -157,6 +745,9 @@ impl Config {
         let mut tokens = match token_type {
             TokenType::Auth => {
                 let mut tokens: HashSet<_> = self.server.auth_tokens.clone().unwrap_or_default();
+                if let Some(token_settings) = &self.server.token_settings {
+                    tokens.extend(token_settings.keys().cloned());
+                }
 
                 #[allow(deprecated)]
                 if let Some(token) = &self.server.auth_token {
@@ -211,6 +802,129 @@ impl Config {
         Some(tokens).filter(|v| !v.is_empty())
     }
 
+    /// Returns a copy of the configuration with the settings of the given token applied.
+    ///
+    /// Returns `None` if there are no [`token_settings`] for the token.
+    ///
+    /// [`token_settings`]: ServerConfig::token_settings
+    pub fn for_token(&self, token: &str) -> Option<Config> {
+        let token_settings = self.server.token_settings.as_ref()?.get(token)?;
+        let mut config = self.clone();
+        if let Some(default_expiry) = token_settings.default_expiry {
+            config.paste.default_expiry = Some(default_expiry);
+        }
+        match token_settings.random_url {
+            Some(true) => {
+                config.paste.random_url = config.paste.random_url.or_else(|| {
+                    Some(RandomURLConfig {
+                        type_: Some(RandomURLType::PetName),
+                        ..Default::default()
+                    })
+                })
+            }
+            Some(false) => {
+                config.paste.random_url = None;
+                config.paste.random_url_by_type = None;
+            }
+            None => {}
+        }
+        Some(config)
+    }
+
+    /// Returns a copy of the configuration with the tokens and paths masked.
+    #[allow(deprecated)]
+    pub fn redacted(&self) -> Config {
+        let mut config = self.clone();
+        let redact = |tokens: &mut Option<HashSet<String>>| {
+            if let Some(tokens) = tokens {
+                *tokens = HashSet::from([REDACTED.to_string()]);
+            }
+        };
+        redact(&mut config.server.auth_tokens);
+        redact(&mut config.server.delete_tokens);
+        if let Some(token_settings) = config.server.token_settings.take() {
+            config.server.token_settings = Some(
+                token_settings
+                    .into_values()
+                    .enumerate()
+                    .map(|(i, v)| (format!("{REDACTED}{}", i + 1), v))
+                    .collect(),
+            );
+        }
+        if config.server.auth_token.is_some() {
+            config.server.auth_token = Some(REDACTED.to_string());
+        }
+        if config.server.signing_secret.is_some() {
+            config.server.signing_secret = Some(REDACTED.to_string());
+        }
+        if config.server.delete_url_secret.is_some() {
+            config.server.delete_url_secret = Some(REDACTED.to_string());
+        }
+        if config.server.owner_secret.is_some() {
+            config.server.owner_secret = Some(REDACTED.to_string());
+        }
+        config.server.upload_path = PathBuf::from(REDACTED);
+        if config.server.pause_file.is_some() {
+            config.server.pause_file = Some(PathBuf::from(REDACTED));
+        }
+        if let Some(ref mut scan) = config.scan {
+            scan.address = REDACTED.to_string();
+        }
+        if let Some(ref mut landing_page) = config.landing_page {
+            if landing_page.file.is_some() {
+                landing_page.file = Some(REDACTED.to_string());
+            }
+        }
+        config
+    }
+
+    /// Checks the configured auth/delete tokens against [`min_token_length`].
+    ///
+    /// A warning is printed for each weak token. If [`reject_weak_tokens`] is `true`,
+    /// an error is returned instead.
+    ///
+    /// Returns an error if the owners of the files are needed without an [`owner_secret`].
+    ///
+    /// [`owner_secret`]: ServerConfig::owner_secret
+    /// [`min_token_length`]: ServerConfig::min_token_length
+    /// [`reject_weak_tokens`]: ServerConfig::reject_weak_tokens
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let has_quota = self
+            .server
+            .token_settings
+            .as_ref()
+            .is_some_and(|v| v.values().any(|v| v.quota.is_some()));
+        if self.server.owner_secret.is_none()
+            && (has_quota || self.paste.dedup_scope == Some(DedupScope::PerToken))
+        {
+            return Err(ConfigError::Message(String::from(
+                "owner_secret is required for the per-token dedup_scope and the quotas",
+            )));
+        }
+        let min_token_length = match self.server.min_token_length {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+        let mut weak_token_count = 0;
+        for token_type in [TokenType::Auth, TokenType::Delete] {
+            for token in self.get_tokens(token_type).unwrap_or_default() {
+                if token.trim().chars().count() < min_token_length {
+                    warn!(
+                        "{} token is shorter than {min_token_length} characters",
+                        format!("{token_type:?}").to_lowercase()
+                    );
+                    weak_token_count += 1;
+                }
+            }
+        }
+        if weak_token_count != 0 && self.server.reject_weak_tokens.unwrap_or(false) {
+            return Err(ConfigError::Message(format!(
+                "{weak_token_count} token(s) are shorter than {min_token_length} characters"
+            )));
+        }
+        Ok(())
+    }
+
     /// Print deprecation warnings.
     #[allow(deprecated)]
     pub fn warn_deprecation(&self) {
@@ -231,6 +945,25 @@ impl Config {
                     "[paste].random_url.enabled is deprecated, disable it by commenting out [paste].random_url"
                 );
             }
+            for warning in random_url.get_warnings() {
+                warn!("{warning}");
+            }
+        }
+        if let Some(random_url_by_type) = &self.paste.random_url_by_type {
+            for random_url in [
+                &random_url_by_type.file,
+                &random_url_by_type.remote,
+                &random_url_by_type.oneshot,
+                &random_url_by_type.url,
+                &random_url_by_type.oneshot_url,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                for warning in random_url.get_warnings() {
+                    warn!("{warning}");
+                }
+            }
         }
     }
 }
@@ -249,6 +982,60 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_invalid_config() -> Result<(), ConfigError> {
+        let config_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("config.toml");
+        let config_content =
+            read_to_string(&config_path).map_err(|e| ConfigError::Foreign(e.into()))?;
+        for (from, to, field, file_name) in [
+            (
+                "timeout = \"30s\"",
+                "timeout = \"soon\"",
+                "server.timeout",
+                "invalid_duration.toml",
+            ),
+            (
+                "max_content_length = \"10MB\"",
+                "max_content_length = \"10 potatoes\"",
+                "server.max_content_length",
+                "invalid_byte.toml",
+            ),
+        ] {
+            let test_dir =
+                env::temp_dir().join(format!("rustypaste-invalid-config-{}", std::process::id()));
+            std::fs::create_dir_all(&test_dir).map_err(|e| ConfigError::Foreign(e.into()))?;
+            let path = test_dir.join(file_name);
+            std::fs::write(&path, config_content.replacen(from, to, 1))
+                .map_err(|e| ConfigError::Foreign(e.into()))?;
+            let error = Config::parse(&path)
+                .expect_err("invalid config is parsed")
+                .to_string();
+            std::fs::remove_dir_all(&test_dir).map_err(|e| ConfigError::Foreign(e.into()))?;
+            assert!(
+                error.starts_with(&format!("invalid value for `{field}`: ")),
+                "{error}"
+            );
+            assert!(error.ends_with(&format!("({})", path.display())), "{error}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_max_connections() -> Result<(), ConfigError> {
+        let content = read_to_string(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("config.toml"))
+            .map_err(|e| ConfigError::Foreign(Box::new(e)))?;
+        let parse = |content: &str| {
+            config::Config::builder()
+                .add_source(config::File::from_str(content, config::FileFormat::Toml))
+                .build()?
+                .try_deserialize::<Config>()
+        };
+        assert_eq!(None, parse(&content)?.server.max_connections);
+        let content = content.replace("#max_connections = 25000", "max_connections = 512");
+        assert_eq!(Some(512), parse(&content)?.server.max_connections);
+        Ok(())
+    }
+
     #[test]
     #[allow(deprecated)]
     fn test_parse_deprecated_config() -> Result<(), ConfigError> {
@@ -273,6 +1060,71 @@ mod tests {
         assert_eq!("file%20with%20spaces.txt", encoded_filename);
     }
 
+    #[test]
+    fn test_cleanup_interval() {
+        let mut cleanup_config = CleanupConfig {
+            enabled: true,
+            interval: Duration::from_secs(60),
+            ..Default::default()
+        };
+        assert_eq!(Duration::from_secs(60), cleanup_config.get_interval());
+        cleanup_config.jitter = Some(Duration::from_secs(10));
+        for _ in 0..100 {
+            let interval = cleanup_config.get_interval();
+            assert!(interval >= Duration::from_secs(50));
+            assert!(interval <= Duration::from_secs(70));
+        }
+        cleanup_config.jitter = Some(Duration::from_secs(120));
+        for _ in 0..100 {
+            assert!(cleanup_config.get_interval() <= Duration::from_secs(180));
+        }
+    }
+
+    #[test]
+    fn test_read_paths() {
+        let auth_config = AuthConfig {
+            read: false,
+            read_paths: HashMap::from([
+                (String::from("private"), true),
+                (String::from("/private/shared/"), false),
+            ]),
+            ..Default::default()
+        };
+        assert!(!auth_config.is_read_required("file.txt"));
+        assert!(!auth_config.is_read_required("private"));
+        assert!(!auth_config.is_read_required("private.txt"));
+        assert!(auth_config.is_read_required("private/file.txt"));
+        assert!(auth_config.is_read_required("/private/file.txt"));
+        assert!(!auth_config.is_read_required("private/shared/file.txt"));
+    }
+
+    #[test]
+    fn test_validate_tokens() -> Result<(), ConfigError> {
+        let mut config = Config::default();
+        config.server.min_token_length = Some(16);
+        config.server.auth_tokens = Some(["abc".to_string()].into());
+        assert!(config.validate().is_ok());
+
+        config.server.reject_weak_tokens = Some(true);
+        assert!(config.validate().is_err());
+
+        config.server.auth_tokens = Some(["may_the_force_be_with_you".to_string()].into());
+        config.server.delete_tokens = Some(["i_am_your_father".to_string()].into());
+        config.validate()?;
+
+        config.server.delete_tokens = Some(["abc".to_string()].into());
+        assert!(config.validate().is_err());
+
+        config.server.min_token_length = None;
+        config.validate()?;
+
+        config.paste.dedup_scope = Some(DedupScope::PerToken);
+        assert!(config.validate().is_err());
+        config.server.owner_secret = Some(String::from("secret"));
+        config.validate()?;
+        Ok(())
+    }
+
     #[test]
     fn test_get_tokens() -> Result<(), ConfigError> {
         let config_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("config.toml");
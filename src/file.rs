@@ -1,17 +1,90 @@
+use crate::config::ChecksumAlgorithm;
+use crate::paste;
 use crate::util;
 use actix_web::{error, Error as ActixError};
 use glob::glob;
+use lazy_regex::Lazy;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
-use std::fs::File as OsFile;
+use std::fs::{self, File as OsFile};
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+use std::time::{Instant, SystemTime};
+
+/// Checksum of a file along with the metadata that is used for validating it.
+struct CachedChecksum {
+    /// Size of the file in bytes.
+    size: u64,
+    /// Modification time of the file.
+    modified: SystemTime,
+    /// Algorithm that is used for calculating the checksum.
+    algorithm: ChecksumAlgorithm,
+    /// Checksum of the file.
+    checksum: String,
+}
+
+/// Checksums of the files that are calculated while checking the duplicates.
+///
+/// The entries are only used if the size and the modification time of the file is not changed.
+static CHECKSUM_CACHE: Lazy<Mutex<HashMap<PathBuf, CachedChecksum>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Returns the checksum of the file at the given path.
+///
+/// The checksum is calculated only if it is not cached or the file is changed.
+pub fn get_checksum(path: &Path, algorithm: ChecksumAlgorithm) -> Option<String> {
+    let metadata = fs::metadata(path).ok()?;
+    let (size, modified) = (metadata.len(), metadata.modified().ok()?);
+    if let Some(cached) = CHECKSUM_CACHE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(path)
+        .filter(|v| v.size == size && v.modified == modified && v.algorithm == algorithm)
+    {
+        return Some(cached.checksum.clone());
+    }
+    // the cache is not locked while the file is read
+    let checksum = util::digest(OsFile::open(path).ok()?, algorithm).ok()?;
+    CHECKSUM_CACHE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(
+            path.to_path_buf(),
+            CachedChecksum {
+                size,
+                modified,
+                algorithm,
+                checksum: checksum.clone(),
+            },
+        );
+    Some(checksum)
+}
+
+/// Calculates the checksums of the files in the given directory in the background.
+///
+/// The duplicate checks calculate the missing checksums on demand until this is finished.
+pub fn warm_up_checksums(directory: PathBuf, algorithm: ChecksumAlgorithm) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let start = Instant::now();
+        match Directory::from_path(&directory, algorithm) {
+            Ok(directory) => info!(
+                "Calculated the checksums of {} files in {:?}",
+                directory.files.len(),
+                start.elapsed()
+            ),
+            Err(e) => error!("Cannot calculate the checksums: {}", e),
+        }
+    })
+}
 
 /// [`PathBuf`] wrapper for storing checksums.
 #[derive(Debug)]
 pub struct File {
     /// Path of the file.
     pub path: PathBuf,
-    /// SHA256 checksum.
-    pub sha256sum: String,
+    /// Checksum of the file.
+    pub checksum: String,
 }
 
 /// Directory that contains [`File`]s.
@@ -23,31 +96,67 @@ pub struct Directory {
 impl<'a> TryFrom<&'a Path> for Directory {
     type Error = ActixError;
     fn try_from(directory: &'a Path) -> Result<Self, Self::Error> {
-        let files = glob(directory.join("**").join("*").to_str().ok_or_else(|| {
+        Self::from_path(directory, ChecksumAlgorithm::default())
+    }
+}
+
+impl Directory {
+    /// Reads the files in the directory and calculates their checksums with the given algorithm.
+    ///
+    /// The checksums are cached along with the algorithm, thus the existing files are always
+    /// compared using the same algorithm even if it is changed in the meantime.
+    pub fn from_path(directory: &Path, algorithm: ChecksumAlgorithm) -> Result<Self, ActixError> {
+        let files: Vec<File> = glob(directory.join("**").join("*").to_str().ok_or_else(|| {
             error::ErrorInternalServerError("directory contains invalid characters")
         })?)
         .map_err(error::ErrorInternalServerError)?
         .filter_map(Result::ok)
         .filter(|path| !path.is_dir())
-        .filter_map(|path| match OsFile::open(&path) {
-            Ok(file) => Some((path, file)),
-            _ => None,
-        })
-        .filter_map(|(path, file)| match util::sha256_digest(file) {
-            Ok(sha256sum) => Some(File { path, sha256sum }),
-            _ => None,
+        .filter(|path| !paste::is_metadata_path(path))
+        .filter_map(|path| {
+            let checksum = get_checksum(&path, algorithm)?;
+            Some(File { path, checksum })
         })
         .collect();
+        // the removed files are evicted from the cache
+        let paths = files
+            .iter()
+            .map(|v| v.path.as_path())
+            .collect::<HashSet<_>>();
+        CHECKSUM_CACHE
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .retain(|path, _| !path.starts_with(directory) || paths.contains(path.as_path()));
         Ok(Self { files })
     }
-}
 
-impl Directory {
     /// Returns the file that matches the given checksum.
-    pub fn get_file<S: AsRef<str>>(self, sha256sum: S) -> Option<File> {
+    pub fn get_file<S: AsRef<str>>(self, checksum: S) -> Option<File> {
+        self.files.into_iter().find(|file| {
+            file.checksum == checksum.as_ref()
+                && !util::TIMESTAMP_EXTENSION_REGEX.is_match(&file.path.to_string_lossy())
+        })
+    }
+
+    /// Returns the file that matches the given checksum and is owned by the given owner.
+    ///
+    /// The owners are read from the given directory (see [`paste::store_owner`]).
+    pub fn get_owned_file<S: AsRef<str>>(
+        self,
+        checksum: S,
+        owner_dir: &Path,
+        owner: Option<&str>,
+    ) -> Option<File> {
         self.files.into_iter().find(|file| {
-            file.sha256sum == sha256sum.as_ref()
+            file.checksum == checksum.as_ref()
                 && !util::TIMESTAMP_EXTENSION_REGEX.is_match(&file.path.to_string_lossy())
+                && file
+                    .path
+                    .file_name()
+                    .map(|v| paste::get_owner(owner_dir, &v.to_string_lossy()))
+                    .unwrap_or_default()
+                    .as_deref()
+                    == owner
         })
     }
 }
@@ -73,4 +182,60 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_file_checksum_blake3() -> Result<(), ActixError> {
+        let directory = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("img");
+        let checksum = util::digest(
+            OsFile::open(directory.join("rustypaste_logo.png"))?,
+            ChecksumAlgorithm::Blake3,
+        )?;
+        assert_eq!(
+            Some(OsString::from("rustypaste_logo.png").as_ref()),
+            Directory::from_path(&directory, ChecksumAlgorithm::Blake3)?
+                .get_file(checksum)
+                .expect("cannot get file with checksum")
+                .path
+                .file_name()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_checksum_cache() -> Result<(), ActixError> {
+        let directory = PathBuf::from("test_checksum_cache");
+        fs::create_dir(&directory)?;
+        let path = directory.join("file.txt");
+        fs::write(&path, "test")?;
+
+        let checksum = util::digest("test".as_bytes(), ChecksumAlgorithm::Sha256)?;
+        let files = Directory::from_path(&directory, ChecksumAlgorithm::Sha256)?;
+        assert!(files.get_file(&checksum).is_some());
+        let files = Directory::from_path(&directory, ChecksumAlgorithm::Sha256)?;
+        assert!(files.get_file(&checksum).is_some());
+
+        fs::write(&path, "changed")?;
+        let files = Directory::from_path(&directory, ChecksumAlgorithm::Sha256)?;
+        let changed_checksum = util::digest("changed".as_bytes(), ChecksumAlgorithm::Sha256)?;
+        assert_ne!(checksum, changed_checksum);
+        assert_eq!(
+            vec![changed_checksum],
+            files
+                .files
+                .into_iter()
+                .map(|v| v.checksum)
+                .collect::<Vec<_>>()
+        );
+
+        fs::remove_dir_all(&directory)?;
+        assert!(Directory::from_path(&directory, ChecksumAlgorithm::Sha256)?
+            .files
+            .is_empty());
+        assert!(!CHECKSUM_CACHE
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .contains_key(&path));
+
+        Ok(())
+    }
 }
@@ -1,8 +1,15 @@
+use crate::config::ServerConfig;
+use crate::util;
 use actix_web::http::header::{
     ContentDisposition as ActixContentDisposition, DispositionParam, DispositionType, HeaderMap,
+    HeaderName, HeaderValue, FORWARDED, HOST, IF_NONE_MATCH,
 };
-use actix_web::{error, Error as ActixError};
-use std::time::Duration;
+use actix_web::{error, Error as ActixError, HttpRequest};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use byte_unit::Byte;
+use std::fmt::{self, Display, Formatter};
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, UNIX_EPOCH};
 
 /// Custom HTTP header for expiry dates.
 pub const EXPIRE: &str = "expire";
@@ -10,6 +17,38 @@ pub const EXPIRE: &str = "expire";
 /// Custom HTTP header to override filename.
 const FILENAME: &str = "filename";
 
+/// Custom HTTP header for the charset of text uploads.
+pub const CHARSET: &str = "x-charset";
+
+/// Maximum length of a charset name.
+const MAX_CHARSET_LENGTH: usize = 40;
+
+/// Custom HTTP header for signaling that the upload matched an existing file.
+pub const EXISTING: &str = "x-existing";
+
+/// Custom HTTP header for the deletion URLs of the uploaded files.
+///
+/// The deletion URLs are requested with the `DELETE` method.
+pub const DELETE_URL: &str = "x-delete-url";
+
+/// De-facto standard HTTP header for the addresses of the client and the proxies.
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// HTTP header for communicating the server timings.
+pub const SERVER_TIMING: &str = "server-timing";
+
+/// HTTP header for the digest of the uploaded content (RFC 3230).
+pub const DIGEST: &str = "digest";
+
+/// Legacy HTTP header for the MD5 digest of the uploaded content (RFC 1864).
+pub const CONTENT_MD5: &str = "content-md5";
+
+/// Custom HTTP header for the original creation date of the uploads.
+pub const CREATED_AT: &str = "x-created-at";
+
+/// Custom HTTP header for the total transfer limit of the uploads.
+pub const MAX_TRANSFER: &str = "x-max-transfer";
+
 /// Parses the expiry date from the [`custom HTTP header`](EXPIRE).
 pub fn parse_expiry_date(headers: &HeaderMap, time: Duration) -> Result<Option<u128>, ActixError> {
     if let Some(expire_time) = headers.get(EXPIRE).and_then(|v| v.to_str().ok()) {
@@ -21,6 +60,47 @@ pub fn parse_expiry_date(headers: &HeaderMap, time: Duration) -> Result<Option<u
     }
 }
 
+/// Parses the creation date from the [`custom HTTP header`](CREATED_AT).
+///
+/// The date is either a UNIX timestamp (in seconds) or an RFC 3339 date-time (e.g. `2020-01-01T00:00:00Z`).
+/// Returns bad request (`400`) error if the date is not valid or it is later than the given time.
+pub fn parse_created_at(
+    headers: &HeaderMap,
+    time: Duration,
+) -> Result<Option<Duration>, ActixError> {
+    let created_at = match headers.get(CREATED_AT).and_then(|v| v.to_str().ok()) {
+        Some(created_at) => created_at.trim(),
+        None => return Ok(None),
+    };
+    let created_at = match created_at.parse::<u64>() {
+        Ok(timestamp) => Duration::from_secs(timestamp),
+        Err(_) => humantime::parse_rfc3339_weak(created_at)
+            .ok()
+            .and_then(|v| v.duration_since(UNIX_EPOCH).ok())
+            .ok_or_else(|| error::ErrorBadRequest("invalid creation date\n"))?,
+    };
+    if created_at > time {
+        return Err(error::ErrorBadRequest("creation date is in the future\n"));
+    }
+    Ok(Some(created_at))
+}
+
+/// Parses the transfer limit (e.g. `1GB`) from the [`custom HTTP header`](MAX_TRANSFER).
+///
+/// Returns bad request (`400`) error if the limit is not valid.
+pub fn parse_max_transfer(headers: &HeaderMap) -> Result<Option<Byte>, ActixError> {
+    match headers.get(MAX_TRANSFER).and_then(|v| v.to_str().ok()) {
+        Some(max_transfer) => max_transfer
+            .trim()
+            .parse::<Byte>()
+            .ok()
+            .filter(|v| v.as_u64() != 0)
+            .map(Some)
+            .ok_or_else(|| error::ErrorBadRequest("invalid transfer limit\n")),
+        None => Ok(None),
+    }
+}
+
 /// Parses the filename from the header.
 pub fn parse_header_filename(headers: &HeaderMap) -> Result<Option<String>, ActixError> {
     if let Some(file_name) = headers.get(FILENAME).and_then(|v| v.to_str().ok()) {
@@ -30,6 +110,137 @@ pub fn parse_header_filename(headers: &HeaderMap) -> Result<Option<String>, Acti
     }
 }
 
+/// Parses the charset from the [`custom HTTP header`](CHARSET).
+///
+/// Returns bad request (`400`) error if the charset name is not valid.
+pub fn parse_charset(headers: &HeaderMap) -> Result<Option<String>, ActixError> {
+    match headers.get(CHARSET).and_then(|v| v.to_str().ok()) {
+        Some(charset)
+            if !charset.is_empty()
+                && charset.len() <= MAX_CHARSET_LENGTH
+                && charset
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_.:+".contains(c)) =>
+        {
+            Ok(Some(charset.to_ascii_lowercase()))
+        }
+        Some(_) => Err(error::ErrorBadRequest("invalid charset\n")),
+        None => Ok(None),
+    }
+}
+
+/// Parses the checksum from the `If-None-Match` header.
+///
+/// The value can be quoted and weak validators (`W/`) are accepted.
+///
+/// Returns bad request (`400`) error if the value is not a 256-bit hex checksum.
+pub fn parse_if_none_match(headers: &HeaderMap) -> Result<Option<String>, ActixError> {
+    match headers.get(IF_NONE_MATCH).and_then(|v| v.to_str().ok()) {
+        Some(value) => {
+            let value = value.trim();
+            let value = value.strip_prefix("W/").unwrap_or(value).trim_matches('"');
+            if value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit()) {
+                Ok(Some(value.to_ascii_lowercase()))
+            } else {
+                Err(error::ErrorBadRequest(
+                    "invalid checksum in If-None-Match\n",
+                ))
+            }
+        }
+        None => Ok(None),
+    }
+}
+
+/// Expected digest of the uploaded content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContentDigest {
+    /// Hex encoded SHA-256 digest.
+    Sha256(String),
+    /// Hex encoded MD5 digest.
+    Md5(String),
+}
+
+impl ContentDigest {
+    /// Checks if the given data matches the digest.
+    pub fn verify(&self, data: &[u8]) -> Result<bool, ActixError> {
+        match self {
+            Self::Sha256(digest) => Ok(util::sha256_digest(data)? == *digest),
+            Self::Md5(digest) => Ok(format!("{:x}", md5::compute(data)) == *digest),
+        }
+    }
+}
+
+/// Parses the expected digest of the content from the [`Digest`](DIGEST) header
+/// or the [`Content-MD5`](CONTENT_MD5) header.
+///
+/// Only the `sha-256` and `md5` algorithms are supported, others are ignored.
+///
+/// Returns bad request (`400`) error if the digest is not a valid base64 value.
+pub fn parse_content_digest(headers: &HeaderMap) -> Result<Option<ContentDigest>, ActixError> {
+    let decode = |value: &str, length: usize| -> Result<String, ActixError> {
+        BASE64
+            .decode(value.trim())
+            .ok()
+            .filter(|v| v.len() == length)
+            .map(|v| v.iter().map(|b| format!("{b:02x}")).collect())
+            .ok_or_else(|| error::ErrorBadRequest("invalid content digest\n"))
+    };
+    let digests = headers
+        .get(DIGEST)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|v| v.trim().split_once('='))
+        .collect::<Vec<(&str, &str)>>();
+    let find_digest = |algorithm: &str| {
+        digests
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(algorithm))
+            .map(|(_, value)| *value)
+    };
+    if let Some(value) = find_digest("sha-256") {
+        return Ok(Some(ContentDigest::Sha256(decode(value, 32)?)));
+    }
+    if let Some(value) =
+        find_digest("md5").or_else(|| headers.get(CONTENT_MD5).and_then(|v| v.to_str().ok()))
+    {
+        return Ok(Some(ContentDigest::Md5(decode(value, 16)?)));
+    }
+    Ok(None)
+}
+
+/// Returns a `Content-Disposition` header value with the given file name (RFC 6266).
+///
+/// The `filename` parameter contains an ASCII fallback where the unsafe characters are replaced
+/// with `_`. If the name is not safe to be quoted as is (e.g. non-ASCII), the `filename*`
+/// parameter is added with the percent-encoded UTF-8 name (RFC 8187).
+///
+/// e.g. `attachment; filename="_.txt"; filename*=UTF-8''%C3%A7.txt`
+pub fn get_content_disposition(
+    disposition: DispositionType,
+    file_name: &str,
+) -> Result<HeaderValue, ActixError> {
+    let fallback_name = file_name
+        .chars()
+        .map(|c| match c {
+            ' '..='~' if c != '"' && c != '\\' => c,
+            _ => '_',
+        })
+        .collect::<String>();
+    let mut value = format!("{disposition}; filename=\"{fallback_name}\"");
+    if fallback_name != file_name {
+        value.push_str("; filename*=UTF-8''");
+        for byte in file_name.bytes() {
+            if byte.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&byte) {
+                value.push(char::from(byte));
+            } else {
+                value.push_str(&format!("%{byte:02X}"));
+            }
+        }
+    }
+    HeaderValue::from_str(&value).map_err(error::ErrorInternalServerError)
+}
+
 /// Wrapper for Actix content disposition header.
 ///
 /// Aims to parse the file data from multipart body.
@@ -70,11 +281,243 @@ impl ContentDisposition {
     }
 }
 
+/// Metrics for the [`Server-Timing`](SERVER_TIMING) header.
+///
+/// e.g. `Server-Timing: hash;dur=0.120, store;dur=1.034, total;dur=1.502`
+#[derive(Debug, Default)]
+pub struct ServerTiming {
+    metrics: Vec<(&'static str, Duration)>,
+}
+
+impl ServerTiming {
+    /// Adds the duration to the metric with the given name.
+    pub fn add(&mut self, name: &'static str, duration: Duration) {
+        match self.metrics.iter_mut().find(|(v, _)| *v == name) {
+            Some((_, total)) => *total += duration,
+            None => self.metrics.push((name, duration)),
+        }
+    }
+
+    /// Inserts the metrics into the given headers.
+    pub fn insert_into(&self, headers: &mut HeaderMap) -> Result<(), ActixError> {
+        headers.insert(
+            HeaderName::from_static(SERVER_TIMING),
+            HeaderValue::from_str(&self.to_string()).map_err(error::ErrorInternalServerError)?,
+        );
+        Ok(())
+    }
+}
+
+impl Display for ServerTiming {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let metrics = self
+            .metrics
+            .iter()
+            .map(|(name, duration)| format!("{name};dur={:.3}", duration.as_secs_f64() * 1000.))
+            .collect::<Vec<String>>();
+        write!(f, "{}", metrics.join(", "))
+    }
+}
+
+/// Parsed [`Forwarded`](FORWARDED) header (RFC 7239).
+///
+/// e.g. `Forwarded: for=192.0.2.60;proto=https;host=paste.example.com`
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Forwarded {
+    /// Client that initiated the request.
+    pub for_: Option<String>,
+    /// Protocol that was used by the client.
+    pub proto: Option<String>,
+    /// Original `Host` header of the request.
+    pub host: Option<String>,
+}
+
+impl Forwarded {
+    /// Parses the `Forwarded` header and returns the element that belongs to the client.
+    ///
+    /// The elements are walked from the right and the ones that are added by the given trusted
+    /// proxies are skipped, since the elements on the left can be spoofed by the client.
+    pub fn parse(headers: &HeaderMap, trusted_proxies: &[IpAddr]) -> Option<Self> {
+        let mut elements = headers
+            .get_all(FORWARDED)
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(Self::parse_element)
+            .collect::<Vec<Self>>();
+        let position = elements
+            .iter()
+            .rposition(|element| !is_trusted_node(element.for_.as_deref(), trusted_proxies))
+            .unwrap_or_default();
+        if position < elements.len() {
+            Some(elements.swap_remove(position))
+        } else {
+            None
+        }
+    }
+
+    /// Parses a single element of the `Forwarded` header.
+    fn parse_element(element: &str) -> Self {
+        let mut forwarded = Self::default();
+        for pair in element.split(';') {
+            let (name, value) = match pair.split_once('=') {
+                Some((name, value)) => (name.trim(), value.trim().trim_matches('"')),
+                None => continue,
+            };
+            if name.eq_ignore_ascii_case("for") {
+                forwarded.for_ = Some(
+                    parse_node_addr(value)
+                        .map(|v| v.to_string())
+                        .unwrap_or_else(|| value.to_string()),
+                );
+            } else if name.eq_ignore_ascii_case("proto") {
+                forwarded.proto = Some(value.to_string());
+            } else if name.eq_ignore_ascii_case("host") {
+                forwarded.host = Some(value.to_string());
+            }
+        }
+        forwarded
+    }
+}
+
+/// Parses the IP address of a proxy node, e.g. `192.0.2.60:4711` or `[2001:db8::17]:4711`.
+fn parse_node_addr(value: &str) -> Option<IpAddr> {
+    let value = value.trim();
+    value
+        .parse::<IpAddr>()
+        .or_else(|_| value.parse::<SocketAddr>().map(|v| v.ip()))
+        .ok()
+        .or_else(|| {
+            value
+                .strip_prefix('[')
+                .and_then(|v| v.strip_suffix(']'))
+                .and_then(|v| v.parse().ok())
+        })
+}
+
+/// Returns `true` if the given node is one of the trusted proxies.
+fn is_trusted_node(node: Option<&str>, trusted_proxies: &[IpAddr]) -> bool {
+    node.and_then(parse_node_addr)
+        .is_some_and(|v| trusted_proxies.contains(&v))
+}
+
+/// Returns the address of the client from the `X-Forwarded-For` header.
+///
+/// The addresses are walked from the right and the trusted proxies are skipped.
+fn parse_forwarded_for(headers: &HeaderMap, trusted_proxies: &[IpAddr]) -> Option<String> {
+    let addresses = headers
+        .get_all(X_FORWARDED_FOR)
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .collect::<Vec<&str>>();
+    addresses
+        .iter()
+        .rev()
+        .find(|v| !is_trusted_node(Some(v), trusted_proxies))
+        .or(addresses.first())
+        .map(|v| {
+            parse_node_addr(v)
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| v.to_string())
+        })
+}
+
+/// Returns the identity of the client for logging.
+///
+/// If [`log_identity_header`] is set and present in the request, its value is used instead of
+/// the IP address of the client. The control characters are removed from the value.
+///
+/// [`log_identity_header`]: ServerConfig::log_identity_header
+pub fn get_log_identity(request: &HttpRequest, server_config: &ServerConfig) -> String {
+    server_config
+        .log_identity_header
+        .as_ref()
+        .and_then(|header| request.headers().get(header.as_str()))
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.chars().filter(|c| !c.is_control()).collect::<String>())
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| ClientConnection::from_request(request, server_config).remote_addr)
+}
+
+/// Connection details of the client.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ClientConnection {
+    /// Scheme of the request (e.g. `https`).
+    pub scheme: String,
+    /// Host of the request (e.g. `paste.example.com`).
+    pub host: String,
+    /// Real IP address of the client.
+    pub remote_addr: String,
+}
+
+impl ClientConnection {
+    /// Returns the connection details of the request.
+    ///
+    /// If [`trusted_proxies`] are set, the proxy headers are only taken into account for the
+    /// requests coming from these proxies. The `Forwarded` header takes precedence over the
+    /// `X-Forwarded-*` headers.
+    ///
+    /// [`trusted_proxies`]: ServerConfig::trusted_proxies
+    pub fn from_request(request: &HttpRequest, server_config: &ServerConfig) -> Self {
+        let connection = request.connection_info();
+        let trusted_proxies = match &server_config.trusted_proxies {
+            Some(trusted_proxies) => trusted_proxies,
+            None => {
+                return Self {
+                    scheme: connection.scheme().to_string(),
+                    host: connection.host().to_string(),
+                    remote_addr: connection
+                        .realip_remote_addr()
+                        .unwrap_or("unknown host")
+                        .to_string(),
+                }
+            }
+        };
+        let peer_addr = request.peer_addr().map(|v| v.ip());
+        if peer_addr.is_some_and(|v| trusted_proxies.contains(&v)) {
+            let forwarded =
+                Forwarded::parse(request.headers(), trusted_proxies).unwrap_or_default();
+            Self {
+                scheme: forwarded
+                    .proto
+                    .unwrap_or_else(|| connection.scheme().to_string()),
+                host: forwarded
+                    .host
+                    .unwrap_or_else(|| connection.host().to_string()),
+                remote_addr: forwarded
+                    .for_
+                    .or_else(|| parse_forwarded_for(request.headers(), trusted_proxies))
+                    .or_else(|| peer_addr.map(|v| v.to_string()))
+                    .unwrap_or_else(|| String::from("unknown host")),
+            }
+        } else {
+            let app_config = request.app_config();
+            Self {
+                scheme: request
+                    .uri()
+                    .scheme_str()
+                    .unwrap_or(if app_config.secure() { "https" } else { "http" })
+                    .to_string(),
+                host: request
+                    .headers()
+                    .get(HOST)
+                    .and_then(|v| v.to_str().ok())
+                    .or_else(|| request.uri().authority().map(|v| v.as_str()))
+                    .unwrap_or(app_config.host())
+                    .to_string(),
+                remote_addr: peer_addr
+                    .map(|v| v.to_string())
+                    .unwrap_or_else(|| String::from("unknown host")),
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::util;
-    use actix_web::http::header::{HeaderName, HeaderValue};
+    use actix_web::test::TestRequest;
     use std::thread;
 
     #[test]
@@ -101,6 +544,277 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_server_timing() -> Result<(), ActixError> {
+        let mut server_timing = ServerTiming::default();
+        server_timing.add("hash", Duration::from_micros(1500));
+        server_timing.add("store", Duration::from_millis(2));
+        server_timing.add("hash", Duration::from_micros(500));
+        assert_eq!("hash;dur=2.000, store;dur=2.000", server_timing.to_string());
+        let mut headers = HeaderMap::new();
+        server_timing.insert_into(&mut headers)?;
+        assert_eq!(
+            Some("hash;dur=2.000, store;dur=2.000"),
+            headers.get(SERVER_TIMING).and_then(|v| v.to_str().ok())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_content_disposition() -> Result<(), ActixError> {
+        for (file_name, expected) in [
+            ("file.txt", r#"attachment; filename="file.txt""#),
+            ("my file.txt", r#"attachment; filename="my file.txt""#),
+            (
+                r#"say "hi".txt"#,
+                r#"attachment; filename="say _hi_.txt"; filename*=UTF-8''say%20%22hi%22.txt"#,
+            ),
+            (
+                "çay☕.txt",
+                r#"attachment; filename="_ay_.txt"; filename*=UTF-8''%C3%A7ay%E2%98%95.txt"#,
+            ),
+        ] {
+            let value = get_content_disposition(DispositionType::Attachment, file_name)?;
+            assert_eq!(
+                expected,
+                value.to_str().map_err(error::ErrorInternalServerError)?
+            );
+            assert!(ActixContentDisposition::from_raw(&value).is_ok());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_charset() -> Result<(), ActixError> {
+        let mut headers = HeaderMap::new();
+        assert_eq!(None, parse_charset(&headers)?);
+        headers.insert(
+            HeaderName::from_static(CHARSET),
+            HeaderValue::from_static("ISO-8859-1"),
+        );
+        assert_eq!(Some(String::from("iso-8859-1")), parse_charset(&headers)?);
+        headers.insert(
+            HeaderName::from_static(CHARSET),
+            HeaderValue::from_static("utf-8; x=y"),
+        );
+        assert!(parse_charset(&headers).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_transfer() -> Result<(), ActixError> {
+        let mut headers = HeaderMap::new();
+        assert_eq!(None, parse_max_transfer(&headers)?);
+        headers.insert(
+            HeaderName::from_static(MAX_TRANSFER),
+            HeaderValue::from_static("1KB"),
+        );
+        assert_eq!(Some(Byte::from_u64(1000)), parse_max_transfer(&headers)?);
+        for value in ["0", "x"] {
+            headers.insert(
+                HeaderName::from_static(MAX_TRANSFER),
+                HeaderValue::from_static(value),
+            );
+            assert!(parse_max_transfer(&headers).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_if_none_match() -> Result<(), ActixError> {
+        let mut headers = HeaderMap::new();
+        assert_eq!(None, parse_if_none_match(&headers)?);
+        headers.insert(
+            IF_NONE_MATCH,
+            HeaderValue::from_static(
+                "W/\"2C26B46B68FFC68FF99B453C1D30413413422D706483BFA0F98A5E886266E7AE\"",
+            ),
+        );
+        assert_eq!(
+            Some(String::from(
+                "2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae"
+            )),
+            parse_if_none_match(&headers)?
+        );
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_static("*"));
+        assert!(parse_if_none_match(&headers).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_created_at() -> Result<(), ActixError> {
+        let time = util::get_system_time()?;
+        for (value, expected) in [
+            ("1577836800", Some(Duration::from_secs(1577836800))),
+            (
+                "2020-01-01T00:00:00Z",
+                Some(Duration::from_secs(1577836800)),
+            ),
+            ("2020-01-01 00:00:00", Some(Duration::from_secs(1577836800))),
+        ] {
+            let request = TestRequest::default()
+                .insert_header((CREATED_AT, value))
+                .to_http_request();
+            assert_eq!(expected, parse_created_at(request.headers(), time)?);
+        }
+        for value in ["yesterday", "99999999999"] {
+            let request = TestRequest::default()
+                .insert_header((CREATED_AT, value))
+                .to_http_request();
+            assert!(parse_created_at(request.headers(), time).is_err());
+        }
+        let request = TestRequest::default().to_http_request();
+        assert_eq!(None, parse_created_at(request.headers(), time)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_content_digest() -> Result<(), ActixError> {
+        let mut headers = HeaderMap::new();
+        assert_eq!(None, parse_content_digest(&headers)?);
+        headers.insert(
+            HeaderName::from_static(CONTENT_MD5),
+            HeaderValue::from_static("CY9rzUYh03PK3k6DJie09g=="),
+        );
+        let digest = parse_content_digest(&headers)?.expect("digest is not parsed");
+        assert_eq!(
+            ContentDigest::Md5(String::from("098f6bcd4621d373cade4e832627b4f6")),
+            digest
+        );
+        assert!(digest.verify(b"test")?);
+        assert!(!digest.verify(b"tset")?);
+        headers.insert(
+            HeaderName::from_static(DIGEST),
+            HeaderValue::from_static(
+                "unixsum=30637, SHA-256=n4bQgYhMfWWaL+qgxVrQFaO/TxsrC4Is0V1sFbDwCgg=",
+            ),
+        );
+        let digest = parse_content_digest(&headers)?.expect("digest is not parsed");
+        assert!(matches!(digest, ContentDigest::Sha256(_)));
+        assert!(digest.verify(b"test")?);
+        headers.insert(
+            HeaderName::from_static(DIGEST),
+            HeaderValue::from_static("sha-256=dGVzdA=="),
+        );
+        assert!(parse_content_digest(&headers).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_forwarded() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(None, Forwarded::parse(&headers, &[]));
+        headers.insert(
+            FORWARDED,
+            HeaderValue::from_static(
+                "for=192.0.2.43, for=\"[2001:db8:cafe::17]:4711\";Proto=https;host=paste.example.com, for=10.0.0.1",
+            ),
+        );
+        assert_eq!(
+            Some(Forwarded {
+                for_: Some(String::from("10.0.0.1")),
+                ..Default::default()
+            }),
+            Forwarded::parse(&headers, &[])
+        );
+        assert_eq!(
+            Some(Forwarded {
+                for_: Some(String::from("2001:db8:cafe::17")),
+                proto: Some(String::from("https")),
+                host: Some(String::from("paste.example.com")),
+            }),
+            Forwarded::parse(&headers, &["10.0.0.1".parse().expect("invalid address")])
+        );
+        assert_eq!(
+            Some(Forwarded {
+                for_: Some(String::from("192.0.2.43")),
+                ..Default::default()
+            }),
+            Forwarded::parse(
+                &headers,
+                &[
+                    "10.0.0.1".parse().expect("invalid address"),
+                    "2001:db8:cafe::17".parse().expect("invalid address")
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_forwarded_for() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(None, parse_forwarded_for(&headers, &[]));
+        headers.insert(
+            HeaderName::from_static(X_FORWARDED_FOR),
+            HeaderValue::from_static("192.0.2.43, 192.0.2.60:4711, 10.0.0.1"),
+        );
+        assert_eq!(
+            Some(String::from("10.0.0.1")),
+            parse_forwarded_for(&headers, &[])
+        );
+        assert_eq!(
+            Some(String::from("192.0.2.60")),
+            parse_forwarded_for(&headers, &["10.0.0.1".parse().expect("invalid address")])
+        );
+    }
+
+    #[test]
+    fn test_client_connection() {
+        let mut server_config = ServerConfig::default();
+        let request = || {
+            TestRequest::default()
+                .peer_addr("127.0.0.1:12345".parse().expect("invalid address"))
+                .insert_header((
+                    FORWARDED,
+                    "for=192.0.2.60;proto=https;host=paste.example.com",
+                ))
+                .to_http_request()
+        };
+        let forwarded_connection = ClientConnection {
+            scheme: String::from("https"),
+            host: String::from("paste.example.com"),
+            remote_addr: String::from("192.0.2.60"),
+        };
+        assert_eq!(
+            forwarded_connection,
+            ClientConnection::from_request(&request(), &server_config)
+        );
+
+        server_config.trusted_proxies = Some(vec!["127.0.0.1".parse().expect("invalid address")]);
+        assert_eq!(
+            forwarded_connection,
+            ClientConnection::from_request(&request(), &server_config)
+        );
+
+        server_config.trusted_proxies = Some(vec!["10.0.0.1".parse().expect("invalid address")]);
+        assert_eq!(
+            ClientConnection {
+                scheme: String::from("http"),
+                host: String::from("localhost:8080"),
+                remote_addr: String::from("127.0.0.1"),
+            },
+            ClientConnection::from_request(&request(), &server_config)
+        );
+    }
+
+    #[test]
+    fn test_log_identity() {
+        let mut server_config = ServerConfig::default();
+        let request = TestRequest::default()
+            .peer_addr("127.0.0.1:12345".parse().expect("invalid address"))
+            .insert_header(("x-user-id", "orhun\t"))
+            .to_http_request();
+        assert_eq!("127.0.0.1", get_log_identity(&request, &server_config));
+
+        server_config.log_identity_header = Some(String::from("X-User-Id"));
+        assert_eq!("orhun", get_log_identity(&request, &server_config));
+
+        let request = TestRequest::default()
+            .peer_addr("127.0.0.1:12345".parse().expect("invalid address"))
+            .to_http_request();
+        assert_eq!("127.0.0.1", get_log_identity(&request, &server_config));
+    }
+
     #[test]
     fn test_expiry_date() -> Result<(), ActixError> {
         let mut headers = HeaderMap::new();
@@ -31,6 +31,25 @@ pub mod util;
 /// Custom middleware implementation.
 pub mod middleware;
 
+/// Virus scanner client.
+pub mod scan;
+
+/// Response compression.
+pub mod compression;
+
+/// OpenAPI description.
+pub mod openapi;
+
+/// Archive extractor.
+pub mod archive;
+
+/// Image transcoder.
+pub mod transcode;
+
+/// Application state for embedding.
+pub mod app;
+pub use app::AppState;
+
 // Use macros from tracing crate.
 #[macro_use]
 extern crate tracing;
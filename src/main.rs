@@ -1,22 +1,23 @@
 use actix_web::middleware::Logger;
 use actix_web::web::Data;
 use actix_web::{App, HttpServer};
-use awc::ClientBuilder;
+use futures_util::future;
 use hotwatch::notify::event::ModifyKind;
 use hotwatch::{Event, EventKind, Hotwatch};
 use rustypaste::config::{Config, ServerConfig};
-use rustypaste::middleware::ContentLengthLimiter;
-use rustypaste::paste::PasteType;
-use rustypaste::server;
+use rustypaste::file;
+use rustypaste::paste;
+use rustypaste::server::RouteGroup;
 use rustypaste::util;
-use rustypaste::CONFIG_ENV;
+use rustypaste::{AppState, CONFIG_ENV};
 use std::env;
 use std::fs;
 use std::io::Result as IoResult;
+use std::mem;
 use std::path::{Path, PathBuf};
 use std::sync::{mpsc, RwLock};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tracing_subscriber::{
     filter::LevelFilter, layer::SubscriberExt as _, util::SubscriberInitExt as _, EnvFilter,
 };
@@ -25,6 +26,53 @@ use tracing_subscriber::{
 #[macro_use]
 extern crate tracing;
 
+/// Binds the HTTP server to the given TCP address or Unix domain socket.
+///
+/// The stale socket file is removed before binding and [`socket_mode`] is applied afterwards.
+///
+/// [`socket_mode`]: ServerConfig::socket_mode
+macro_rules! bind_address {
+    ($http_server:expr, $address:expr, $socket_mode:expr) => {
+        match util::get_unix_socket_path($address) {
+            #[cfg(unix)]
+            Some(socket_path) => {
+                use std::os::unix::fs::PermissionsExt;
+                if socket_path.exists() {
+                    fs::remove_file(&socket_path)?;
+                }
+                let http_server = $http_server.bind_uds(&socket_path)?;
+                if let Some(mode) = $socket_mode {
+                    fs::set_permissions(&socket_path, fs::Permissions::from_mode(mode))?;
+                }
+                http_server
+            }
+            #[cfg(not(unix))]
+            Some(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "unix sockets are not supported on this platform",
+                ));
+            }
+            None => $http_server.bind($address)?,
+        }
+    };
+}
+
+/// Applies the worker count and the connection limit of the server configuration.
+macro_rules! limit_connections {
+    ($http_server:expr, $server_config:expr) => {{
+        let mut http_server = $http_server;
+        if let Some(workers) = $server_config.workers {
+            http_server = http_server.workers(workers);
+        }
+        if let Some(max_connections) = $server_config.max_connections {
+            info!("Limiting the concurrent connections to {max_connections} per worker");
+            http_server = http_server.max_connections(max_connections);
+        }
+        http_server
+    }};
+}
+
 /// Sets up the application.
 ///
 /// * loads the configuration
@@ -60,17 +108,34 @@ fn setup(config_folder: &Path) -> IoResult<(Data<RwLock<Config>>, ServerConfig,
         );
         std::process::exit(1);
     }
-    let config = Config::parse(&config_path).expect("failed to parse config");
+    let config = match Config::parse(&config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            error!("failed to parse config: {e}");
+            std::process::exit(1);
+        }
+    };
     trace!("{:#?}", config);
     config.warn_deprecation();
+    if let Err(e) = config.validate() {
+        error!("invalid configuration: {e}");
+        std::process::exit(1);
+    }
     let server_config = config.server.clone();
     let paste_config = RwLock::new(config.paste.clone());
     let (config_sender, config_receiver) = mpsc::channel::<Config>();
 
     // Create necessary directories.
-    fs::create_dir_all(&server_config.upload_path)?;
-    for paste_type in &[PasteType::Url, PasteType::Oneshot, PasteType::OneshotUrl] {
-        fs::create_dir_all(paste_type.get_path(&server_config.upload_path)?)?;
+    util::create_upload_dirs(&server_config.upload_path)?;
+
+    // Calculate the checksums of the existing files for the duplicate checks.
+    if config.paste.warm_up_checksums.unwrap_or(false)
+        && !config.paste.duplicate_files.unwrap_or(true)
+    {
+        file::warm_up_checksums(
+            server_config.upload_path.clone(),
+            config.paste.checksum_algorithm.unwrap_or_default(),
+        );
     }
 
     // Set up a watcher for the configuration file changes.
@@ -90,20 +155,40 @@ fn setup(config_folder: &Path) -> IoResult<(Data<RwLock<Config>>, ServerConfig,
         if let (EventKind::Modify(ModifyKind::Data(_)), Some(path)) =
             (event.kind, event.paths.first())
         {
-            match Config::parse(path) {
-                Ok(config) => match cloned_config.write() {
-                    Ok(mut cloned_config) => {
-                        *cloned_config = config.clone();
-                        info!("Configuration has been updated.");
-                        if let Err(e) = config_sender.send(config) {
-                            error!("Failed to send config for the cleanup routine: {}", e)
+            match Config::parse(path).and_then(|config| config.validate().map(|_| config)) {
+                Ok(config) => {
+                    if config.paste.delete_blacklisted_files.unwrap_or(false) {
+                        for file in paste::get_blacklisted_files(&config) {
+                            match paste::remove_file(&config.server.upload_path, &file) {
+                                Ok(()) => info!(
+                                    "Removed blacklisted file: {:?}",
+                                    util::get_log_file_name(
+                                        &file.to_string_lossy(),
+                                        &config.server
+                                    )
+                                ),
+                                Err(e) => error!("Cannot remove blacklisted file: {}", e),
+                            }
                         }
-                        cloned_config.warn_deprecation();
                     }
-                    Err(e) => {
-                        error!("Failed to acquire config: {}", e);
+                    // the lock is only held for swapping the config so that the readers are not blocked
+                    let start = Instant::now();
+                    let old_config = match cloned_config.write() {
+                        Ok(mut cloned_config) => mem::replace(&mut *cloned_config, config.clone()),
+                        Err(e) => {
+                            error!("Failed to acquire config: {}", e);
+                            return;
+                        }
+                    };
+                    drop(old_config);
+                    util::set_config_lock_timeout(config.server.config_lock_timeout);
+                    debug!("Swapped the config in {:?}", start.elapsed());
+                    info!("Configuration has been updated.");
+                    config.warn_deprecation();
+                    if let Err(e) = config_sender.send(config) {
+                        error!("Failed to send config for the cleanup routine: {}", e)
                     }
-                },
+                }
                 Err(e) => {
                     error!("Failed to update config: {}", e);
                 }
@@ -116,6 +201,7 @@ fn setup(config_folder: &Path) -> IoResult<(Data<RwLock<Config>>, ServerConfig,
 
     // Create a thread for cleaning up expired files.
     let upload_path = server_config.upload_path.clone();
+    let cleanup_server_config = server_config.clone();
     thread::spawn(move || loop {
         let mut enabled = false;
         if let Some(ref cleanup_config) = paste_config
@@ -126,12 +212,23 @@ fn setup(config_folder: &Path) -> IoResult<(Data<RwLock<Config>>, ServerConfig,
             if cleanup_config.enabled {
                 debug!("Running cleanup...");
                 for file in util::get_expired_files(&upload_path) {
-                    match fs::remove_file(&file) {
-                        Ok(()) => info!("Removed expired file: {:?}", file),
+                    match paste::remove_file(&upload_path, &file) {
+                        Ok(()) => info!(
+                            "Removed expired file: {:?}",
+                            util::get_log_file_name(
+                                &file.to_string_lossy(),
+                                &cleanup_server_config
+                            )
+                        ),
                         Err(e) => error!("Cannot remove expired file: {}", e),
                     }
                 }
-                thread::sleep(cleanup_config.interval);
+                if cleanup_config.remove_empty_dirs.unwrap_or(false) {
+                    for dir in util::remove_empty_dirs(&upload_path) {
+                        info!("Removed empty directory: {:?}", dir);
+                    }
+                }
+                thread::sleep(cleanup_config.get_interval());
             }
             enabled = cleanup_config.enabled;
         }
@@ -159,33 +256,106 @@ async fn main() -> IoResult<()> {
     // Set up the application.
     let (config, server_config, _hotwatch) = setup(&PathBuf::new())?;
 
+    // Share the state between the workers.
+    let state = AppState::from_data(config);
+
+    // Serve the administrative routes separately if configured.
+    let group = if server_config.admin_address.is_some() {
+        RouteGroup::Public
+    } else {
+        RouteGroup::All
+    };
+
     // Create an HTTP server.
-    let mut http_server = HttpServer::new(move || {
-        let http_client = ClientBuilder::new()
-            .timeout(
-                server_config
-                    .timeout
-                    .unwrap_or_else(|| Duration::from_secs(30)),
-            )
-            .disable_redirects()
-            .finish();
+    let public_state = state.clone();
+    let http_server = HttpServer::new(move || {
+        let state = public_state.clone();
         App::new()
-            .app_data(Data::clone(&config))
-            .app_data(Data::new(http_client))
             .wrap(Logger::new(
                 "%{r}a \"%r\" %s %b \"%{Referer}i\" \"%{User-Agent}i\" %T",
             ))
-            .wrap(ContentLengthLimiter::new(server_config.max_content_length))
-            .configure(server::configure_routes)
-    })
-    .bind(&server_config.address)?;
+            .configure(move |cfg| state.configure_group(cfg, group))
+    });
+    let http_server = bind_address!(
+        http_server,
+        &server_config.address,
+        server_config.socket_mode
+    );
 
-    // Set worker count for the server.
-    if let Some(workers) = server_config.workers {
-        http_server = http_server.workers(workers);
-    }
+    // Set worker count for the server and limit the concurrent connections.
+    let http_server = limit_connections!(http_server, server_config);
 
     // Run the server.
     info!("Server is running at {}", server_config.address);
-    http_server.run().await
+    let admin_address = match server_config.admin_address {
+        Some(admin_address) => admin_address,
+        None => return http_server.run().await,
+    };
+
+    // Create an HTTP server for the administrative routes.
+    let admin_server = HttpServer::new(move || {
+        let state = state.clone();
+        App::new()
+            .wrap(Logger::new(
+                "%{r}a \"%r\" %s %b \"%{Referer}i\" \"%{User-Agent}i\" %T",
+            ))
+            .configure(move |cfg| state.configure_group(cfg, RouteGroup::Admin))
+    })
+    .workers(1);
+    let admin_server = bind_address!(admin_server, &admin_address, server_config.socket_mode);
+    info!("Admin server is running at {}", admin_address);
+    future::try_join(http_server.run(), admin_server.run())
+        .await
+        .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Error as IoError, Read, Write};
+    use std::net::TcpStream;
+
+    #[actix_web::test]
+    async fn test_limit_connections() -> IoResult<()> {
+        let mut config = Config::default();
+        config.server.workers = Some(1);
+        config.server.max_connections = Some(2);
+        config.server.expose_version = Some(true);
+        let server_config = config.server.clone();
+        let state = AppState::new(config);
+
+        let http_server = HttpServer::new(move || {
+            let state = state.clone();
+            App::new().configure(move |cfg| state.configure(cfg))
+        });
+        let http_server = limit_connections!(http_server, server_config).bind("127.0.0.1:0")?;
+        let address = http_server.addrs()[0];
+        let server = http_server.run();
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+
+        let response = actix_web::rt::task::spawn_blocking(move || {
+            // the idle connections occupy the slots of the worker
+            let idle_streams = [TcpStream::connect(address)?, TcpStream::connect(address)?];
+            thread::sleep(Duration::from_millis(100));
+            let mut stream = TcpStream::connect(address)?;
+            stream.write_all(
+                b"GET /version HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            )?;
+            stream.set_read_timeout(Some(Duration::from_millis(500)))?;
+            assert!(stream.read(&mut [0; 1]).is_err());
+
+            drop(idle_streams);
+            stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+            let mut response = String::new();
+            stream.read_to_string(&mut response)?;
+            Ok::<String, IoError>(response)
+        })
+        .await
+        .map_err(IoError::other)??;
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+
+        handle.stop(true).await;
+        Ok(())
+    }
 }
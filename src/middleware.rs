@@ -1,15 +1,22 @@
-use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
-use actix_web::http::header::CONTENT_LENGTH;
+use crate::config::Config;
+use crate::header::ClientConnection;
+use actix_web::dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::error::PayloadError;
+use actix_web::http::header::{CONTENT_LENGTH, LOCATION};
 use actix_web::http::StatusCode;
+use actix_web::web::Data;
 use actix_web::{body::EitherBody, Error};
 use actix_web::{HttpMessage, HttpResponseBuilder};
-use byte_unit::Byte;
-use futures_util::{Future, TryStreamExt};
+use byte_unit::{Byte, UnitType};
+use futures_util::{Future, StreamExt, TryStreamExt};
 use std::{
+    cell::Cell,
     future::{ready, Ready},
     pin::Pin,
     rc::Rc,
+    sync::RwLock,
 };
+use url::Url;
 
 /// Content length limiter middleware.
 #[derive(Debug)]
@@ -63,6 +70,10 @@ where
     forward_ready!(service);
     fn call(&self, mut request: ServiceRequest) -> Self::Future {
         let service = Rc::clone(&self.service);
+        let limit_message = format!(
+            "upload limit exceeded, limit is {}",
+            self.max_bytes.get_appropriate_unit(UnitType::Decimal)
+        );
         if let Some(content_length) = request
             .headers()
             .get(CONTENT_LENGTH)
@@ -80,17 +91,139 @@ where
                     while let Ok(Some(_)) = payload.try_next().await {}
                     Ok(request.into_response(
                         HttpResponseBuilder::new(StatusCode::PAYLOAD_TOO_LARGE)
-                            .body("upload limit exceeded")
+                            .body(limit_message)
                             .map_into_right_body(),
                     ))
                 });
             }
         }
+        // enforce the limit on the streamed body in case the content length is missing or wrong
+        let max_bytes = self.max_bytes.as_u64();
+        let limit_exceeded = Rc::new(Cell::new(false));
+        let mut received_bytes: u64 = 0;
+        let payload = request.take_payload().map({
+            let limit_exceeded = Rc::clone(&limit_exceeded);
+            move |chunk| {
+                let chunk = chunk?;
+                received_bytes = received_bytes.saturating_add(chunk.len() as u64);
+                if received_bytes > max_bytes {
+                    limit_exceeded.set(true);
+                    return Err(PayloadError::Overflow);
+                }
+                Ok(chunk)
+            }
+        });
+        request.set_payload(Payload::Stream {
+            payload: Box::pin(payload),
+        });
+        Box::pin(async move {
+            let response = service.call(request).await?;
+            if limit_exceeded.get() {
+                warn!(
+                    "Upload rejected due to exceeded limit. (> {} bytes)",
+                    max_bytes
+                );
+                let (request, _) = response.into_parts();
+                return Ok(ServiceResponse::new(
+                    request,
+                    HttpResponseBuilder::new(StatusCode::PAYLOAD_TOO_LARGE)
+                        .body(limit_message)
+                        .map_into_right_body(),
+                ));
+            }
+            Ok(response.map_into_left_body())
+        })
+    }
+}
+
+/// Middleware for redirecting the requests to the host of [`server.url`].
+///
+/// [`server.url`]: crate::config::ServerConfig::url
+#[derive(Debug)]
+pub struct CanonicalRedirect {
+    /// Configuration.
+    config: Data<RwLock<Config>>,
+}
+
+impl CanonicalRedirect {
+    /// Constructs a new instance.
+    pub fn new(config: Data<RwLock<Config>>) -> Self {
+        Self { config }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for CanonicalRedirect
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = CanonicalRedirectMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CanonicalRedirectMiddleware {
+            service: Rc::new(service),
+            config: Data::clone(&self.config),
+        }))
+    }
+}
+
+/// Canonical redirect middleware implementation.
+#[derive(Debug)]
+pub struct CanonicalRedirectMiddleware<S> {
+    service: Rc<S>,
+    config: Data<RwLock<Config>>,
+}
+
+impl<S> CanonicalRedirectMiddleware<S> {
+    /// Returns the location on the canonical host if the request is sent to another host.
+    fn get_canonical_location(&self, request: &ServiceRequest) -> Option<String> {
+        let config = self.config.read().ok()?;
+        if !config.server.canonical_redirect.unwrap_or(false) {
+            return None;
+        }
+        let canonical_url = Url::parse(config.server.url.as_ref()?).ok()?;
+        let connection = ClientConnection::from_request(request.request(), &config.server);
+        let request_url =
+            Url::parse(&format!("{}://{}", canonical_url.scheme(), connection.host)).ok()?;
+        if request_url.host_str() == canonical_url.host_str()
+            && request_url.port_or_known_default() == canonical_url.port_or_known_default()
+        {
+            return None;
+        }
+        let mut location = canonical_url;
+        location.set_path(request.path());
+        location.set_query(Some(request.query_string()).filter(|v| !v.is_empty()));
+        Some(location.to_string())
+    }
+}
+
+impl<S, B> Service<ServiceRequest> for CanonicalRedirectMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+    forward_ready!(service);
+    fn call(&self, request: ServiceRequest) -> Self::Future {
+        if let Some(location) = self.get_canonical_location(&request) {
+            return Box::pin(ready(Ok(request.into_response(
+                HttpResponseBuilder::new(StatusCode::MOVED_PERMANENTLY)
+                    .insert_header((LOCATION, location))
+                    .finish()
+                    .map_into_right_body(),
+            ))));
+        }
+        let service = Rc::clone(&self.service);
         Box::pin(async move {
-            service
-                .call(request)
-                .await
-                .map(ServiceResponse::map_into_left_body)
+            let response = service.call(request).await?;
+            Ok(response.map_into_left_body())
         })
     }
 }
@@ -1,8 +1,21 @@
 use actix_files::file_extension_to_mime;
 use mime::{FromStrError, Mime};
 use regex::Regex;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::Duration;
+
+/// Aliases of the file extensions and their canonical forms.
+const EXTENSION_ALIASES: &[(&str, &str)] = &[
+    ("jpeg", "jpg"),
+    ("jpe", "jpg"),
+    ("jfif", "jpg"),
+    ("tif", "tiff"),
+    ("htm", "html"),
+    ("mpeg", "mpg"),
+    ("yml", "yaml"),
+];
 
 /// Matcher for MIME types.
 #[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
@@ -14,20 +27,69 @@ pub struct MimeMatcher {
     pub regex: Option<Regex>,
 }
 
+/// Matcher for overriding the expiry time of MIME types.
+#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MimeExpiryMatcher {
+    /// Regex for matching the media type.
+    #[serde(with = "serde_regex")]
+    pub regex: Option<Regex>,
+    /// Expiry time to set for the matched media type.
+    #[serde(with = "humantime_serde")]
+    pub expiry: Duration,
+}
+
+/// Returns the expiry time of the given media type using an array of
+/// [`MIME expiry matcher`]s.
+///
+/// [`MIME expiry matcher`]: MimeExpiryMatcher
+pub fn get_mime_expiry(expiry_matchers: &[MimeExpiryMatcher], mime_type: &str) -> Option<Duration> {
+    expiry_matchers
+        .iter()
+        .find(|matcher| {
+            matcher
+                .regex
+                .as_ref()
+                .map(|r| r.is_match(mime_type))
+                .unwrap_or(false)
+        })
+        .map(|matcher| matcher.expiry)
+}
+
+/// Returns the canonical form of the given file extension.
+///
+/// The extension is lowercased and the common aliases are replaced (e.g. `JPEG` -> `jpg`).
+pub fn get_canonical_extension(extension: &str) -> String {
+    let extension = extension.to_lowercase();
+    EXTENSION_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == extension)
+        .map(|(_, canonical)| canonical.to_string())
+        .unwrap_or(extension)
+}
+
 /// Returns the appropriate media type using an array of
-/// [`MIME matcher`]s and the file name.
+/// [`MIME matcher`]s, the extension mappings and the file name.
+///
+/// [`MIME matcher`]s take precedence over the extension mappings.
+/// Falls back to `application/octet-stream` for the unknown files.
 ///
 /// [`MIME matcher`]: MimeMatcher
 pub fn get_mime_type(
     mime_matchers: &[MimeMatcher],
+    mime_by_extension: &HashMap<String, String>,
     file_name: String,
 ) -> Result<Mime, FromStrError> {
-    let path = PathBuf::from(&file_name);
-    let mut mime_type = file_extension_to_mime(
-        path.extension()
-            .and_then(|v| v.to_str())
-            .unwrap_or_default(),
-    );
+    find_mime_type(mime_matchers, mime_by_extension, file_name)
+        .map(|v| v.unwrap_or(mime::APPLICATION_OCTET_STREAM))
+}
+
+/// Returns the media type like [`get_mime_type`] or `None` if the file is unknown
+/// (e.g. it does not have an extension).
+pub fn find_mime_type(
+    mime_matchers: &[MimeMatcher],
+    mime_by_extension: &HashMap<String, String>,
+    file_name: String,
+) -> Result<Option<Mime>, FromStrError> {
     for matcher in mime_matchers {
         if matcher
             .regex
@@ -35,11 +97,22 @@ pub fn get_mime_type(
             .map(|r| r.is_match(&file_name))
             .unwrap_or(false)
         {
-            mime_type = Mime::from_str(&matcher.mime)?;
-            break;
+            return Mime::from_str(&matcher.mime).map(Some);
         }
     }
-    Ok(mime_type)
+    let path = PathBuf::from(&file_name);
+    let extension = path
+        .extension()
+        .and_then(|v| v.to_str())
+        .unwrap_or_default();
+    match mime_by_extension
+        .iter()
+        .find(|(v, _)| v.trim_start_matches('.').eq_ignore_ascii_case(extension))
+    {
+        Some((_, mime)) => Mime::from_str(mime).map(Some),
+        None => Ok(Some(file_extension_to_mime(extension))
+            .filter(|v| *v != mime::APPLICATION_OCTET_STREAM)),
+    }
 }
 
 #[cfg(test)]
@@ -55,6 +128,7 @@ mod tests {
                     mime: String::from("text/plain"),
                     regex: Regex::new("^.*\\.test$").ok(),
                 }],
+                &HashMap::new(),
                 String::from("mime.test")
             )?
         );
@@ -65,17 +139,111 @@ mod tests {
                     mime: String::from("image/png"),
                     regex: Regex::new("^.*\\.PNG$").ok(),
                 }],
+                &HashMap::new(),
                 String::from("image.PNG")
             )?
         );
         assert_eq!(
             mime::APPLICATION_PDF,
-            get_mime_type(&[], String::from("book.pdf"))?
+            get_mime_type(&[], &HashMap::new(), String::from("book.pdf"))?
         );
         assert_eq!(
             mime::APPLICATION_OCTET_STREAM,
-            get_mime_type(&[], String::from("x.unknown"))?
+            get_mime_type(&[], &HashMap::new(), String::from("x.unknown"))?
         );
         Ok(())
     }
+
+    #[test]
+    fn test_mime_by_extension() -> Result<(), FromStrError> {
+        let mime_by_extension = HashMap::from([
+            (String::from("md"), String::from("text/markdown")),
+            (String::from("log"), String::from("text/plain")),
+        ]);
+        assert_eq!(
+            "text/markdown",
+            get_mime_type(&[], &mime_by_extension, String::from("README.md"))?.essence_str()
+        );
+        assert_eq!(
+            "text/markdown",
+            get_mime_type(&[], &mime_by_extension, String::from("NOTES.MD"))?.essence_str()
+        );
+        assert_eq!(
+            mime::IMAGE_PNG,
+            get_mime_type(
+                &[MimeMatcher {
+                    mime: String::from("image/png"),
+                    regex: Regex::new("^fake\\.md$").ok(),
+                }],
+                &mime_by_extension,
+                String::from("fake.md")
+            )?
+        );
+        assert_eq!(
+            mime::APPLICATION_PDF,
+            get_mime_type(&[], &mime_by_extension, String::from("book.pdf"))?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_mime_type() -> Result<(), FromStrError> {
+        assert_eq!(
+            Some(mime::TEXT_PLAIN),
+            find_mime_type(&[], &HashMap::new(), String::from("notes.txt"))?
+        );
+        assert_eq!(
+            None,
+            find_mime_type(&[], &HashMap::new(), String::from("notes"))?
+        );
+        assert_eq!(
+            None,
+            find_mime_type(&[], &HashMap::new(), String::from("x.unknown"))?
+        );
+        assert_eq!(
+            Some(mime::TEXT_PLAIN),
+            find_mime_type(
+                &[MimeMatcher {
+                    mime: String::from("text/plain"),
+                    regex: Regex::new("^notes$").ok(),
+                }],
+                &HashMap::new(),
+                String::from("notes")
+            )?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_canonical_extension() {
+        assert_eq!("jpg", get_canonical_extension("JPEG"));
+        assert_eq!("jpg", get_canonical_extension("Jpeg"));
+        assert_eq!("jpg", get_canonical_extension("jpe"));
+        assert_eq!("jpg", get_canonical_extension("JPG"));
+        assert_eq!("html", get_canonical_extension("htm"));
+        assert_eq!("txt", get_canonical_extension("TXT"));
+    }
+
+    #[test]
+    fn test_mime_expiry() {
+        let expiry_matchers = [
+            MimeExpiryMatcher {
+                regex: Regex::new("^image/.*$").ok(),
+                expiry: Duration::from_secs(3600),
+            },
+            MimeExpiryMatcher {
+                regex: Regex::new("^video/webm$").ok(),
+                expiry: Duration::from_secs(60),
+            },
+        ];
+        assert_eq!(
+            Some(Duration::from_secs(3600)),
+            get_mime_expiry(&expiry_matchers, "image/png")
+        );
+        assert_eq!(
+            Some(Duration::from_secs(60)),
+            get_mime_expiry(&expiry_matchers, "video/webm")
+        );
+        assert_eq!(None, get_mime_expiry(&expiry_matchers, "text/plain"));
+    }
 }
@@ -0,0 +1,302 @@
+use serde_json::{json, Value};
+
+/// Version of the OpenAPI specification.
+const OPENAPI_VERSION: &str = "3.0.3";
+
+/// Returns the OpenAPI description of the routes.
+///
+/// The description is maintained by hand, it should be updated along with the routes.
+pub fn get_spec(server_url: &str) -> Value {
+    let text_response = |description: &str| {
+        json!({
+            "description": description,
+            "content": { "text/plain": { "schema": { "type": "string" } } }
+        })
+    };
+    let upload = json!({
+        "post": {
+            "summary": "Upload files, URLs or remote files.",
+            "parameters": [
+                { "$ref": "#/components/parameters/expire" },
+                { "$ref": "#/components/parameters/filename" },
+                {
+                    "name": "format",
+                    "in": "query",
+                    "schema": {
+                        "type": "string",
+                        "enum": ["plain", "url-only", "share", "markdown", "html", "bbcode"],
+                    },
+                },
+            ],
+            "requestBody": {
+                "required": true,
+                "content": {
+                    "multipart/form-data": {
+                        "schema": {
+                            "type": "object",
+                            "properties": {
+                                "file": { "type": "string", "format": "binary" },
+                                "oneshot": { "type": "string", "format": "binary" },
+                                "url": { "type": "string", "format": "uri" },
+                                "oneshot_url": { "type": "string", "format": "uri" },
+                                "remote": { "type": "string", "format": "uri" },
+                            },
+                        },
+                    },
+                    "application/x-www-form-urlencoded": {
+                        "schema": {
+                            "type": "object",
+                            "properties": {
+                                "content": { "type": "string" },
+                                "name": { "type": "string" },
+                                "extension": { "type": "string" },
+                            },
+                        },
+                    },
+                },
+            },
+            "responses": {
+                "200": text_response("URLs of the uploaded files, one per line."),
+                "400": text_response("Invalid upload."),
+                "401": text_response("Unauthorized."),
+                "503": text_response("Uploads are paused."),
+            },
+        },
+    });
+    let archive = json!({
+        "post": {
+            "summary": "Upload the files in a tar, gzip compressed tar or zip archive.",
+            "parameters": [{ "$ref": "#/components/parameters/expire" }],
+            "requestBody": {
+                "required": true,
+                "content": {
+                    "application/octet-stream": {
+                        "schema": { "type": "string", "format": "binary" },
+                    },
+                },
+            },
+            "responses": {
+                "200": text_response("URLs of the uploaded files, one per line."),
+                "400": text_response("Invalid archive."),
+                "404": { "description": "Archive uploads are not enabled." },
+                "413": text_response("Archive is too large."),
+            },
+        },
+    });
+    let file = json!({
+        "parameters": [{ "$ref": "#/components/parameters/file" }],
+        "get": {
+            "summary": "Serve a file or redirect to a shortened URL.",
+            "parameters": [
+                { "name": "download", "in": "query", "schema": { "type": "boolean" } },
+                { "name": "render", "in": "query", "schema": { "type": "boolean" } },
+                { "name": "view", "in": "query", "schema": { "type": "boolean" } },
+                { "name": "lines", "in": "query", "schema": { "type": "string" } },
+            ],
+            "responses": {
+                "200": {
+                    "description": "Contents of the file.",
+                    "content": { "*/*": { "schema": { "type": "string", "format": "binary" } } },
+                },
+                "302": { "description": "Redirect to the shortened URL." },
+                "400": text_response("Invalid line range or the file is not a text file."),
+                "404": text_response("File is not found or expired."),
+                "410": text_response("File is expired."),
+                "413": text_response("File is too large to be rendered."),
+            },
+        },
+        "delete": {
+            "summary": "Delete a file.",
+            "parameters": [
+                { "name": "glob", "in": "query", "schema": { "type": "boolean" } },
+                { "name": "token", "in": "query", "schema": { "type": "string" } },
+            ],
+            "responses": {
+                "200": text_response("File is deleted."),
+                "404": text_response("File is not found or expired."),
+            },
+        },
+    });
+    let raw = json!({
+        "parameters": [{ "$ref": "#/components/parameters/file" }],
+        "get": {
+            "summary": "Serve the stored bytes of a file as `application/octet-stream`.",
+            "responses": {
+                "200": {
+                    "description": "Contents of the file.",
+                    "content": {
+                        "application/octet-stream": {
+                            "schema": { "type": "string", "format": "binary" },
+                        },
+                    },
+                },
+                "404": text_response("File is not found or expired."),
+                "410": text_response("File is expired."),
+            },
+        },
+    });
+    let extend = json!({
+        "parameters": [{ "$ref": "#/components/parameters/file" }],
+        "post": {
+            "summary": "Extend the expiry of a file.",
+            "parameters": [{ "$ref": "#/components/parameters/expire" }],
+            "responses": {
+                "200": text_response("File expiry is extended."),
+                "403": text_response("Expiry is shortened without a delete token."),
+                "404": text_response("File is not found or expired."),
+            },
+        },
+    });
+    let list = json!({
+        "get": {
+            "summary": "List the uploaded files.",
+            "parameters": [{
+                "name": "format",
+                "in": "query",
+                "schema": { "type": "string", "enum": ["json", "csv", "ndjson"] },
+            }],
+            "responses": {
+                "200": {
+                    "description": "List of the files.",
+                    "content": {
+                        "application/json": {
+                            "schema": {
+                                "type": "array",
+                                "items": { "$ref": "#/components/schemas/ListItem" },
+                            },
+                        },
+                    },
+                },
+                "304": { "description": "List is not modified." },
+                "404": { "description": "List endpoint is not exposed." },
+            },
+        },
+    });
+    let version = json!({
+        "get": {
+            "summary": "Show the version of the server.",
+            "responses": {
+                "200": text_response("Version of the server."),
+                "404": { "description": "Version endpoint is not exposed." },
+            },
+        },
+    });
+    let sign = json!({
+        "get": {
+            "summary": "Create a signed URL for a file.",
+            "parameters": [
+                { "name": "file", "in": "query", "required": true, "schema": { "type": "string" } },
+                { "name": "expire", "in": "query", "schema": { "type": "string" } },
+            ],
+            "responses": { "200": text_response("Signed URL of the file.") },
+        },
+    });
+    let import = json!({
+        "post": {
+            "summary": "Import the files that are placed into the upload directory.",
+            "parameters": [{ "$ref": "#/components/parameters/expire" }],
+            "responses": { "200": text_response("Names of the imported files.") },
+        },
+    });
+    let purge = json!({
+        "delete": {
+            "summary": "Delete the files by their age and size.",
+            "parameters": [
+                { "name": "older_than", "in": "query", "schema": { "type": "string" } },
+                { "name": "larger_than", "in": "query", "schema": { "type": "string" } },
+                { "name": "dry_run", "in": "query", "schema": { "type": "boolean" } },
+            ],
+            "responses": {
+                "200": text_response("Names of the purged files."),
+                "400": text_response("No criteria is given."),
+            },
+        },
+    });
+    let debug_config = json!({
+        "get": {
+            "summary": "Show the effective configuration.",
+            "responses": {
+                "200": {
+                    "description": "Configuration with the secrets redacted.",
+                    "content": { "application/json": { "schema": { "type": "object" } } },
+                },
+                "404": { "description": "Config endpoint is not exposed." },
+            },
+        },
+    });
+    json!({
+        "openapi": OPENAPI_VERSION,
+        "info": {
+            "title": env!("CARGO_PKG_NAME"),
+            "description": env!("CARGO_PKG_DESCRIPTION"),
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "servers": [{ "url": server_url }],
+        "security": [{ "token": [] }],
+        "paths": {
+            "/": upload,
+            "/archive": archive,
+            "/{file}": file,
+            "/{file}/raw": raw,
+            "/{file}/extend": extend,
+            "/list": list,
+            "/version": version,
+            "/sign": sign,
+            "/import": import,
+            "/purge": purge,
+            "/debug/config": debug_config,
+        },
+        "components": {
+            "securitySchemes": {
+                "token": { "type": "apiKey", "in": "header", "name": "Authorization" },
+            },
+            "parameters": {
+                "file": {
+                    "name": "file",
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "string" },
+                },
+                "expire": {
+                    "name": "expire",
+                    "in": "header",
+                    "description": "Expiry time of the file (e.g. `10min`).",
+                    "schema": { "type": "string" },
+                },
+                "filename": {
+                    "name": "filename",
+                    "in": "header",
+                    "description": "Name of the uploaded file.",
+                    "schema": { "type": "string" },
+                },
+            },
+            "schemas": {
+                "ListItem": {
+                    "type": "object",
+                    "properties": {
+                        "file_name": { "type": "string" },
+                        "file_size": { "type": "integer" },
+                        "creation_date_utc": { "type": "string", "nullable": true },
+                        "creation_date_epoch": { "type": "integer", "nullable": true },
+                        "expires_at_utc": { "type": "string", "nullable": true },
+                        "expires_at_epoch": { "type": "integer", "nullable": true },
+                    },
+                },
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_spec() {
+        let spec = get_spec("http://localhost:8080");
+        assert_eq!(OPENAPI_VERSION, spec["openapi"]);
+        assert_eq!("http://localhost:8080", spec["servers"][0]["url"]);
+        assert!(spec["paths"]["/"]["post"].is_object());
+        assert!(spec["paths"]["/list"]["get"].is_object());
+    }
+}
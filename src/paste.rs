@@ -1,20 +1,84 @@
-use crate::config::Config;
+use crate::config::{Config, ConflictStrategy, DedupScope, ScanFailurePolicy, SpaceHandlingConfig};
 use crate::file::Directory;
 use crate::header::ContentDisposition;
+use crate::mime as mime_util;
+use crate::scan::{self, ScanResult};
 use crate::util;
 use actix_web::{error, Error};
+use awc::error::{ConnectError, SendRequestError};
 use awc::Client;
-use std::fs::{self, File};
-use std::io::{Error as IoError, ErrorKind as IoErrorKind, Result as IoResult, Write};
+use lazy_regex::Lazy;
+use path_clean::PathClean;
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Error as IoError, ErrorKind as IoErrorKind, Read, Result as IoResult, Write};
 use std::path::{Path, PathBuf};
 use std::str;
-use std::sync::RwLock;
+use std::sync::Mutex;
+use std::time::Duration;
 use std::{
     convert::{TryFrom, TryInto},
     ops::Add,
 };
 use url::Url;
 
+/// Default URL schemes that are allowed for shortening.
+const DEFAULT_ALLOWED_URL_SCHEMES: &[&str] = &["http", "https"];
+
+/// Default delay before retrying to fetch a remote file.
+const DEFAULT_REMOTE_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Returns the stored files whose media type is in the [`mime_blacklist`].
+///
+/// This is useful for removing the existing files after the blacklist is extended.
+/// Shortened URLs are not checked.
+///
+/// Fail-safe, omits errors.
+///
+/// [`mime_blacklist`]: crate::config::PasteConfig::mime_blacklist
+pub fn get_blacklisted_files(config: &Config) -> Vec<PathBuf> {
+    if config.paste.mime_blacklist.is_empty() {
+        return Vec::new();
+    }
+    let url_paths = [PasteType::Url, PasteType::OneshotUrl]
+        .into_iter()
+        .filter_map(|v| v.get_path(&config.server.upload_path).ok())
+        .collect::<Vec<PathBuf>>();
+    util::get_stored_files(&config.server.upload_path)
+        .into_iter()
+        .filter(|path| {
+            !path
+                .parent()
+                .is_some_and(|dir| url_paths.iter().any(|v| v == dir))
+        })
+        .filter(|path| {
+            let file_name = path
+                .file_name()
+                .map(|v| v.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let file_name = util::TIMESTAMP_EXTENSION_REGEX.replace(&file_name, "");
+            // only the beginning of the file is needed for detecting the type
+            let mut content = Vec::new();
+            let content_mime_type = File::open(path)
+                .and_then(|file| file.take(8192).read_to_end(&mut content))
+                .ok()
+                .and_then(|_| infer::get(&content))
+                .map(|v| v.mime_type().to_string());
+            let extension_mime_type = mime_util::get_mime_type(
+                &config.paste.mime_override,
+                &config.paste.mime_by_extension,
+                file_name.to_string(),
+            )
+            .ok()
+            .map(|v| v.essence_str().to_string());
+            [content_mime_type, extension_mime_type]
+                .into_iter()
+                .flatten()
+                .any(|v| check_mime_blacklist(&v, config).is_err())
+        })
+        .collect()
+}
+
 /// Type of the data to store.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum PasteType {
@@ -70,12 +134,409 @@ impl PasteType {
         }
     }
 
+    /// Returns `true` if the data of the variant is an URL.
+    pub fn is_url(&self) -> bool {
+        matches!(self, Self::RemoteFile | Self::Url | Self::OneshotUrl)
+    }
+
     /// Returns `true` if the variant is [`Oneshot`](Self::Oneshot).
     pub fn is_oneshot(&self) -> bool {
         self == &Self::Oneshot
     }
 }
 
+/// Checks if the given media type is in the [`mime_blacklist`].
+///
+/// Returns unsupported media type (`415`) error if it is blacklisted.
+///
+/// [`mime_blacklist`]: crate::config::PasteConfig::mime_blacklist
+fn check_mime_blacklist(mime_type: &str, config: &Config) -> Result<(), Error> {
+    if config
+        .paste
+        .mime_blacklist
+        .iter()
+        .any(|v| v.eq_ignore_ascii_case(mime_type))
+    {
+        return Err(error::ErrorUnsupportedMediaType(
+            "this file type is not permitted",
+        ));
+    }
+    Ok(())
+}
+
+/// Checks if the given URL is longer than the [`max_url_length`].
+///
+/// Returns bad request (`400`) error if it is.
+///
+/// [`max_url_length`]: crate::config::PasteConfig::max_url_length
+fn check_url_length(url: &str, config: &Config) -> Result<(), Error> {
+    if let Some(max_url_length) = config.paste.max_url_length {
+        if url.trim().len() > max_url_length {
+            return Err(error::ErrorBadRequest("URL is too long\n"));
+        }
+    }
+    Ok(())
+}
+
+/// Parses the given data as the URL of a remote file.
+///
+/// Only the HTTP(S) URLs are accepted.
+fn get_remote_url(data: &str) -> Result<Url, Error> {
+    let url = Url::parse(data).map_err(error::ErrorBadRequest)?;
+    if !DEFAULT_ALLOWED_URL_SCHEMES.contains(&url.scheme()) {
+        return Err(error::ErrorBadRequest("this URL scheme is not allowed\n"));
+    }
+    Ok(url)
+}
+
+/// Returns the error for the remote files that cannot be fetched.
+///
+/// The unreachable URLs are reported as client (`400`) or upstream (`502`, `504`) errors
+/// so that they are not confused with the server faults.
+fn remote_fetch_error(e: SendRequestError) -> Error {
+    match e {
+        SendRequestError::Url(_) => error::ErrorBadRequest(format!("invalid remote URL: {e}\n")),
+        SendRequestError::Connect(
+            ConnectError::Resolver(_) | ConnectError::NoRecords | ConnectError::Unresolved,
+        ) => error::ErrorBadRequest(format!("remote host cannot be resolved: {e}\n")),
+        SendRequestError::Timeout | SendRequestError::Connect(ConnectError::Timeout) => {
+            error::ErrorGatewayTimeout(format!("remote host timed out: {e}\n"))
+        }
+        SendRequestError::Connect(_)
+        | SendRequestError::Send(_)
+        | SendRequestError::Response(_)
+        | SendRequestError::H2(_) => {
+            error::ErrorBadGateway(format!("remote host is unreachable: {e}\n"))
+        }
+        _ => error::ErrorInternalServerError(e),
+    }
+}
+
+/// Splits the file name into the stem and the last extension (e.g. `my.report` and `.pdf`).
+///
+/// The leading dot of the hidden files is not treated as an extension.
+fn split_file_name(file_name: &str) -> (&str, &str) {
+    match file_name.rfind('.').filter(|i| *i != 0) {
+        Some(i) => file_name.split_at(i),
+        None => (file_name, ""),
+    }
+}
+
+/// Returns the file name with the given version number inserted before the last extension.
+///
+/// e.g. `my.report.pdf` -> `my.report-2.pdf`
+fn get_versioned_file_name(file_name: &str, version: usize) -> String {
+    let (stem, extension) = split_file_name(file_name);
+    format!("{stem}-{version}{extension}")
+}
+
+/// Returns the existing versions of the file in the given directory (or glob pattern),
+/// ordered from the oldest to the newest.
+///
+/// The original file is the first version.
+fn find_file_versions(dir: &Path, file_name: &str) -> Result<Vec<(usize, PathBuf)>, Error> {
+    let (stem, extension) = split_file_name(file_name);
+    let pattern = dir.join(format!("{}*", glob::Pattern::escape(stem)));
+    let mut versions = glob::glob(&pattern.to_string_lossy())
+        .map_err(error::ErrorInternalServerError)?
+        .filter_map(Result::ok)
+        .filter(|path| path.is_file())
+        .filter_map(|path| {
+            let name = path.file_name()?.to_str()?;
+            let name = util::TIMESTAMP_EXTENSION_REGEX.replace(name, "");
+            let version = if name == file_name {
+                1
+            } else {
+                name.strip_prefix(stem)?
+                    .strip_prefix('-')?
+                    .strip_suffix(extension)?
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|v| *v > 1)?
+            };
+            Some((version, path))
+        })
+        .collect::<Vec<(usize, PathBuf)>>();
+    versions.sort();
+    Ok(versions)
+}
+
+/// Paths of the files that are being written.
+static PENDING_WRITES: Lazy<Mutex<HashSet<PathBuf>>> = Lazy::new(Default::default);
+
+/// Guard that marks the file at the given path as being written until it is dropped.
+///
+/// See [`is_being_written`].
+#[derive(Debug)]
+pub struct WriteGuard {
+    /// Path of the file.
+    path: PathBuf,
+}
+
+impl WriteGuard {
+    /// Marks the file at the given path as being written.
+    pub fn new(path: &Path) -> Self {
+        let path = path.clean();
+        if let Ok(mut pending_writes) = PENDING_WRITES.lock() {
+            pending_writes.insert(path.clone());
+        }
+        Self { path }
+    }
+}
+
+impl Drop for WriteGuard {
+    fn drop(&mut self) {
+        if let Ok(mut pending_writes) = PENDING_WRITES.lock() {
+            pending_writes.remove(&self.path);
+        }
+    }
+}
+
+/// Returns `true` if the file at the given path is being written.
+pub fn is_being_written(path: &Path) -> bool {
+    PENDING_WRITES
+        .lock()
+        .is_ok_and(|pending_writes| pending_writes.contains(&path.clean()))
+}
+
+/// Directory for storing the declared charsets of the files.
+pub const CHARSET_DIR: &str = ".charset";
+
+/// Directory for storing the owners of the files.
+///
+/// See [`DedupScope::PerToken`](crate::config::DedupScope::PerToken).
+pub const OWNER_DIR: &str = ".owner";
+
+/// Directory for storing the transferred bytes and the transfer limits of the files.
+pub const TRANSFER_DIR: &str = ".transfer";
+
+/// Directory for storing the fan-out directories of the files.
+///
+/// See [`fanout_depth`](crate::config::PasteConfig::fanout_depth).
+pub const FANOUT_DIR: &str = ".fanout";
+
+/// Directories for storing the metadata of the files.
+pub const METADATA_DIRS: [&str; 4] = [CHARSET_DIR, OWNER_DIR, TRANSFER_DIR, FANOUT_DIR];
+
+/// Directory for the files that are placed into the upload directory manually.
+///
+/// The files are moved into the upload directory by the import endpoint.
+pub const IMPORT_DIR: &str = ".import";
+
+/// Returns `true` if the given path is in one of the [`METADATA_DIRS`] or the [`IMPORT_DIR`].
+pub fn is_metadata_path(path: &Path) -> bool {
+    path.components().any(|v| {
+        METADATA_DIRS
+            .iter()
+            .chain([&IMPORT_DIR])
+            .any(|dir| v.as_os_str() == *dir)
+    })
+}
+
+/// Lock for updating the transferred bytes of the files.
+static TRANSFER_LOCK: Mutex<()> = Mutex::new(());
+
+/// Stores the metadata of the file in the given metadata directory.
+///
+/// If `value` is `None`, the previously stored value (if any) is removed.
+fn store_metadata(
+    dir: &Path,
+    metadata_dir: &str,
+    file_name: &str,
+    value: Option<&str>,
+) -> IoResult<()> {
+    let metadata_dir = dir.join(metadata_dir);
+    let path = util::safe_path_join(&metadata_dir, file_name)?;
+    match value {
+        Some(value) => {
+            fs::create_dir_all(&metadata_dir)?;
+            fs::write(path, value)
+        }
+        None if path.is_file() => fs::remove_file(path),
+        None => Ok(()),
+    }
+}
+
+/// Removes the file at the given path along with its metadata (e.g. charset).
+///
+/// The metadata is kept if another version of the file (e.g. an unexpired one) still exists.
+pub fn remove_file(upload_path: &Path, path: &Path) -> IoResult<()> {
+    fs::remove_file(path)?;
+    let (parent, file_name) = match (path.parent(), path.file_name()) {
+        (Some(parent), Some(file_name)) => (parent, file_name.to_string_lossy()),
+        _ => return Ok(()),
+    };
+    let file_name = util::TIMESTAMP_EXTENSION_REGEX.replacen(&file_name, 1, "");
+    let pattern = parent.join(glob::Pattern::escape(&file_name));
+    if glob::glob(&format!("{}*", pattern.to_string_lossy()))
+        .map_err(|e| IoError::new(IoErrorKind::InvalidInput, e.to_string()))?
+        .filter_map(Result::ok)
+        .any(|v| {
+            v.is_file()
+                && v.file_name()
+                    .map(|v| v.to_string_lossy().to_string())
+                    .is_some_and(|v| {
+                        util::TIMESTAMP_EXTENSION_REGEX.replacen(&v, 1, "") == file_name
+                    })
+        })
+    {
+        return Ok(());
+    }
+    // the metadata is stored in the directories of the paste types, not in the fan-out directories
+    let metadata_dir = [PasteType::Oneshot, PasteType::Url, PasteType::OneshotUrl]
+        .iter()
+        .filter_map(|v| v.get_path(upload_path).ok())
+        .find(|v| v == parent)
+        .unwrap_or_else(|| upload_path.to_path_buf());
+    for metadata in METADATA_DIRS {
+        store_metadata(&metadata_dir, metadata, &file_name, None)?;
+    }
+    Ok(())
+}
+
+/// Returns the stored metadata of the file in the given metadata directory.
+fn get_metadata(dir: &Path, metadata_dir: &str, file_name: &str) -> Option<String> {
+    let path = util::safe_path_join(dir.join(metadata_dir), file_name).ok()?;
+    fs::read_to_string(path)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Stores the charset of the file in the given directory.
+///
+/// If `charset` is `None`, the previously stored charset (if any) is removed.
+pub fn store_charset(dir: &Path, file_name: &str, charset: Option<&str>) -> IoResult<()> {
+    store_metadata(dir, CHARSET_DIR, file_name, charset)
+}
+
+/// Returns the stored charset of the file in the given directory.
+pub fn get_charset(dir: &Path, file_name: &str) -> Option<String> {
+    get_metadata(dir, CHARSET_DIR, file_name)
+}
+
+/// Stores the fan-out directory (e.g. `ab/cd`) of the file in the given directory.
+///
+/// If `fanout_dir` is `None`, the previously stored directory (if any) is removed.
+pub fn store_fanout_dir(dir: &Path, file_name: &str, fanout_dir: Option<&Path>) -> IoResult<()> {
+    let fanout_dir = fanout_dir.map(|v| v.to_string_lossy().replace('\\', "/"));
+    store_metadata(dir, FANOUT_DIR, file_name, fanout_dir.as_deref())
+}
+
+/// Returns the stored fan-out directory of the file in the given directory.
+pub fn get_fanout_dir(dir: &Path, file_name: &str) -> Option<PathBuf> {
+    get_metadata(dir, FANOUT_DIR, file_name).map(PathBuf::from)
+}
+
+/// Stores the owner (keyed hash of the authentication token) of the file in the given directory.
+///
+/// If `owner` is `None`, the previously stored owner (if any) is removed.
+pub fn store_owner(dir: &Path, file_name: &str, owner: Option<&str>) -> IoResult<()> {
+    store_metadata(dir, OWNER_DIR, file_name, owner)
+}
+
+/// Returns the stored owner of the file in the given directory.
+pub fn get_owner(dir: &Path, file_name: &str) -> Option<String> {
+    get_metadata(dir, OWNER_DIR, file_name)
+}
+
+/// Returns the total size of the unexpired files and one shot files that are owned by the
+/// given owner (see [`store_owner`]).
+///
+/// Fail-safe, omits errors.
+pub fn get_owned_size(base_path: &Path, owner: &str) -> u64 {
+    let oneshot_path = match PasteType::Oneshot.get_path(base_path) {
+        Ok(path) => path,
+        Err(_) => return 0,
+    };
+    let url_paths = [PasteType::Url, PasteType::OneshotUrl]
+        .iter()
+        .filter_map(|v| v.get_path(base_path).ok())
+        .collect::<Vec<PathBuf>>();
+    let expired_files = util::get_expired_files(base_path)
+        .into_iter()
+        .collect::<HashSet<PathBuf>>();
+    util::get_stored_files(base_path)
+        .into_iter()
+        .filter(|path| !expired_files.contains(path))
+        .filter_map(|path| {
+            let parent = path.parent()?;
+            let owner_dir = if parent == oneshot_path {
+                &oneshot_path
+            } else if url_paths.iter().any(|v| v == parent) {
+                return None;
+            } else {
+                base_path
+            };
+            let file_name = path.file_name()?.to_string_lossy();
+            let file_name = util::TIMESTAMP_EXTENSION_REGEX.replacen(&file_name, 1, "");
+            if get_owner(owner_dir, &file_name).as_deref() == Some(owner) {
+                fs::metadata(&path).map(|v| v.len()).ok()
+            } else {
+                None
+            }
+        })
+        .sum()
+}
+
+/// Stores the transfer limit (in bytes) of the file in the given directory.
+///
+/// If `limit` is `None`, the previously stored limit (if any) is removed.
+pub fn store_transfer_limit(dir: &Path, file_name: &str, limit: Option<u64>) -> IoResult<()> {
+    let _lock = TRANSFER_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    store_metadata(
+        dir,
+        TRANSFER_DIR,
+        file_name,
+        limit.map(|v| format!("0/{v}")).as_deref(),
+    )
+}
+
+/// State of a file after recording a transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transfer {
+    /// The file can be transferred again.
+    Allowed,
+    /// The file cannot be transferred again after this transfer.
+    Last,
+    /// The transfer would exceed the limit.
+    Exceeded,
+}
+
+/// Adds the given size to the transferred bytes of the file in the given directory.
+///
+/// Returns `None` if the file does not have a transfer limit.
+pub fn record_transfer(dir: &Path, file_name: &str, size: u64) -> IoResult<Option<Transfer>> {
+    let _lock = TRANSFER_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let (used, limit) = match get_metadata(dir, TRANSFER_DIR, file_name)
+        .as_deref()
+        .and_then(|v| v.split_once('/'))
+        .and_then(|(used, limit)| Some((used.parse::<u64>().ok()?, limit.parse::<u64>().ok()?)))
+    {
+        Some(transfer) => transfer,
+        None => return Ok(None),
+    };
+    let used = used.saturating_add(size);
+    if used > limit {
+        return Ok(Some(Transfer::Exceeded));
+    }
+    store_metadata(
+        dir,
+        TRANSFER_DIR,
+        file_name,
+        Some(&format!("{used}/{limit}")),
+    )?;
+    if used.saturating_add(size) > limit {
+        Ok(Some(Transfer::Last))
+    } else {
+        Ok(Some(Transfer::Allowed))
+    }
+}
+
 /// Representation of a single paste.
 #[derive(Debug)]
 pub struct Paste {
@@ -90,11 +551,21 @@ impl Paste {
     ///
     /// - If `file_name` does not have an extension, it is replaced with [`default_extension`].
     /// - If `file_name` is "-", it is replaced with "stdin".
+    /// - If [`canonical_extensions`] is `true`, the extension is lowercased and normalized.
     /// - If [`random_url.enabled`] is `true`, `file_name` is replaced with a pet name or random string.
     /// - If `header_filename` is set, it will override the filename.
+    /// - If [`scan.enabled`] is `true`, the data is scanned for viruses before it is stored.
+    ///
+    /// - If a file with the same name exists, [`on_conflict`] strategy is applied.
+    /// - If [`avoid_space_collisions`] is `true`, the conflicting names with underscores are
+    ///   versioned since they may be the result of replacing the spaces.
     ///
     /// [`default_extension`]: crate::config::PasteConfig::default_extension
+    /// [`canonical_extensions`]: crate::config::PasteConfig::canonical_extensions
     /// [`random_url.enabled`]: crate::random::RandomURLConfig::enabled
+    /// [`scan.enabled`]: crate::config::ScanConfig::enabled
+    /// [`on_conflict`]: crate::config::PasteConfig::on_conflict
+    /// [`avoid_space_collisions`]: crate::config::ServerConfig::avoid_space_collisions
     pub fn store_file(
         &self,
         file_name: &str,
@@ -104,11 +575,23 @@ impl Paste {
     ) -> Result<String, Error> {
         let file_type = infer::get(&self.data);
         if let Some(file_type) = file_type {
-            for mime_type in &config.paste.mime_blacklist {
-                if mime_type == file_type.mime_type() {
-                    return Err(error::ErrorUnsupportedMediaType(
-                        "this file type is not permitted",
-                    ));
+            check_mime_blacklist(file_type.mime_type(), config)?;
+        }
+
+        if let Some(scan_config) = config.scan.as_ref().filter(|v| v.enabled) {
+            match scan::scan_data(scan_config, &self.data) {
+                Ok(ScanResult::Clean) => {}
+                Ok(ScanResult::Infected(signature)) => {
+                    warn!("rejected an infected upload ({signature})");
+                    return Err(error::ErrorUnprocessableEntity("this file is infected\n"));
+                }
+                Err(e) => {
+                    error!("cannot scan the upload: {e}");
+                    if scan_config.on_error == ScanFailurePolicy::Closed {
+                        return Err(error::ErrorServiceUnavailable(
+                            "cannot scan the file, try again later\n",
+                        ));
+                    }
                 }
             }
         }
@@ -169,7 +652,31 @@ impl Paste {
                 .unwrap_or(&config.paste.default_extension)
                 .to_string()
         };
-        if let Some(random_url) = &config.paste.random_url {
+        if let Some(file_type) =
+            file_type.filter(|_| config.paste.trust_magic_extension.unwrap_or(false))
+        {
+            let extension_mime_type = mime_util::get_mime_type(
+                &[],
+                &config.paste.mime_by_extension,
+                format!("file.{extension}"),
+            )
+            .map_err(error::ErrorInternalServerError)?;
+            if extension_mime_type.essence_str() != file_type.mime_type() {
+                extension = match extension.rsplit_once('.') {
+                    Some((prefix, _)) => format!("{prefix}.{}", file_type.extension()),
+                    None => file_type.extension().to_string(),
+                };
+            }
+        }
+        if config.paste.canonical_extensions.unwrap_or(false) {
+            extension = match extension.rsplit_once('.') {
+                Some((prefix, last)) => {
+                    format!("{prefix}.{}", mime_util::get_canonical_extension(last))
+                }
+                None => mime_util::get_canonical_extension(&extension),
+            };
+        }
+        if let Some(random_url) = config.paste.get_random_url(self.type_) {
             if let Some(random_text) = random_url.generate() {
                 if let Some(suffix_mode) = random_url.suffix_mode {
                     if suffix_mode {
@@ -188,21 +695,149 @@ impl Paste {
             file_name = header_filename;
             path.set_file_name(file_name);
         }
-        let file_name = path
+        let mut file_name = path
             .file_name()
             .map(|v| v.to_string_lossy())
             .unwrap_or_default()
             .to_string();
-        let file_path = util::glob_match_file(path.clone())
-            .map_err(|_| IoError::new(IoErrorKind::Other, String::from("path is not valid")))?;
-        if file_path.is_file() && file_path.exists() {
-            return Err(error::ErrorConflict("file already exists\n"));
+        if config.paste.require_extension.unwrap_or(false)
+            && Path::new(&file_name)
+                .extension()
+                .is_none_or(|v| v.is_empty())
+        {
+            return Err(error::ErrorBadRequest("file extension is required\n"));
+        }
+        // formats without magic numbers (e.g. SVG) are only detected by the extension
+        let extension_mime_type = mime_util::get_mime_type(
+            &config.paste.mime_override,
+            &config.paste.mime_by_extension,
+            file_name.clone(),
+        )
+        .map_err(error::ErrorInternalServerError)?;
+        check_mime_blacklist(extension_mime_type.essence_str(), config)?;
+        let fanout_depth = config
+            .paste
+            .fanout_depth
+            .filter(|v| *v != 0 && matches!(self.type_, PasteType::File | PasteType::RemoteFile));
+        // existing files are searched in the stored fan-out directories
+        let find_existing_file = |path: PathBuf| -> Result<PathBuf, Error> {
+            if fanout_depth.is_some() {
+                let file_name = path
+                    .file_name()
+                    .map(|v| v.to_string_lossy())
+                    .unwrap_or_default();
+                if let Some(fanout_path) =
+                    util::find_fanout_file(&config.server.upload_path, &file_name)?
+                {
+                    return Ok(fanout_path);
+                }
+            }
+            util::glob_match_file(path)
+        };
+        let fanout_dir = match fanout_depth {
+            Some(depth) => {
+                let checksum = util::digest(
+                    &*self.data,
+                    config.paste.checksum_algorithm.unwrap_or_default(),
+                )?;
+                let fanout_dir = util::get_fanout_dir(&checksum, depth);
+                let dir = util::safe_path_join(&config.server.upload_path, &fanout_dir)?;
+                fs::create_dir_all(&dir)?;
+                path = dir.join(&file_name);
+                Some(fanout_dir)
+            }
+            None => None,
+        };
+        let file_path =
+            find_existing_file(path.clone()).map_err(|_| IoError::other("path is not valid"))?;
+        let space_collision = config.server.avoid_space_collisions.unwrap_or(false)
+            && matches!(
+                config.server.handle_spaces,
+                Some(SpaceHandlingConfig::Replace)
+            )
+            && file_name.contains('_');
+        let on_conflict = if space_collision {
+            ConflictStrategy::Version
+        } else {
+            config.paste.on_conflict.unwrap_or_default()
+        };
+        let max_versions = config
+            .paste
+            .max_versions
+            .filter(|v| *v != 0 && on_conflict == ConflictStrategy::Version);
+        let mut outdated_versions = Vec::new();
+        if let Some(max_versions) = max_versions {
+            // the original file might be already removed, so all of the versions are searched
+            let dir = match fanout_depth {
+                Some(depth) => util::get_fanout_pattern(&config.server.upload_path, depth),
+                None => path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            };
+            let versions = find_file_versions(&dir, &file_name)?;
+            if let Some((version, _)) = versions.last() {
+                file_name = get_versioned_file_name(&file_name, version + 1);
+                path.set_file_name(&file_name);
+            }
+            outdated_versions = versions
+                .into_iter()
+                .rev()
+                .skip(max_versions - 1)
+                .map(|(_, path)| path)
+                .collect();
+        } else if file_path.is_file() && file_path.exists() {
+            match on_conflict {
+                ConflictStrategy::Error => {
+                    return Err(error::ErrorConflict("file already exists\n"));
+                }
+                ConflictStrategy::Overwrite => {
+                    remove_file(&config.server.upload_path, &file_path)?;
+                }
+                ConflictStrategy::Version => {
+                    let mut version = 2;
+                    loop {
+                        let versioned_file_name = get_versioned_file_name(&file_name, version);
+                        if !find_existing_file(path.with_file_name(&versioned_file_name))?.is_file()
+                        {
+                            file_name = versioned_file_name;
+                            path.set_file_name(&file_name);
+                            break;
+                        }
+                        version += 1;
+                    }
+                }
+            }
         }
         if let Some(timestamp) = expiry_date {
             path.set_file_name(format!("{file_name}.{timestamp}"));
         }
-        let mut buffer = File::create(&path)?;
-        buffer.write_all(&self.data)?;
+        let _write_guard = WriteGuard::new(&path);
+        // create the file exclusively so that only one of the concurrent uploads wins
+        let mut buffer = match on_conflict {
+            ConflictStrategy::Overwrite => File::create(&path)?,
+            ConflictStrategy::Error | ConflictStrategy::Version => {
+                match OpenOptions::new().write(true).create_new(true).open(&path) {
+                    Ok(file) => file,
+                    Err(e) if e.kind() == IoErrorKind::AlreadyExists => {
+                        return Err(error::ErrorConflict("file already exists\n"));
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        };
+        if let Err(e) = buffer.write_all(&self.data) {
+            drop(buffer);
+            fs::remove_file(&path)?;
+            return Err(e.into());
+        }
+        if let Some(fanout_dir) = fanout_dir {
+            // the files are looked up by name without searching all of the fan-out directories
+            store_fanout_dir(&config.server.upload_path, &file_name, Some(&fanout_dir))?;
+        }
+        for outdated_path in outdated_versions {
+            match remove_file(&config.server.upload_path, &outdated_path) {
+                Ok(()) => info!("removed outdated version: {:?}", outdated_path),
+                Err(e) => warn!("cannot remove outdated version: {}", e),
+            }
+        }
         Ok(file_name)
     }
 
@@ -212,47 +847,71 @@ impl Paste {
     /// - Same content length configuration is applied for download limit.
     /// - Checks SHA256 digest of the downloaded file for preventing duplication.
     /// - Assumes `self.data` contains a valid URL, otherwise returns an error.
+    /// - If `header_filename` is set, it will override the filename.
     ///
     /// [`store_file`]: Self::store_file
     pub async fn store_remote_file(
         &mut self,
         expiry_date: Option<u128>,
+        header_filename: Option<String>,
         client: &Client,
-        config: &RwLock<Config>,
+        config: &Config,
     ) -> Result<String, Error> {
         let data = str::from_utf8(&self.data).map_err(error::ErrorBadRequest)?;
-        let url = Url::parse(data).map_err(error::ErrorBadRequest)?;
-        let file_name = url
-            .path_segments()
-            .and_then(|segments| segments.last())
-            .and_then(|name| if name.is_empty() { None } else { Some(name) })
-            .unwrap_or("file");
-        let mut response = client
-            .get(url.as_str())
-            .send()
-            .await
-            .map_err(error::ErrorInternalServerError)?;
+        check_url_length(data, config)?;
         let payload_limit = config
-            .read()
-            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
             .server
             .max_content_length
             .try_into()
             .map_err(error::ErrorInternalServerError)?;
+        let retries = config.paste.remote_retries.unwrap_or(0);
+        let mut retry_delay = config
+            .paste
+            .remote_retry_delay
+            .unwrap_or(DEFAULT_REMOTE_RETRY_DELAY);
+        let url = get_remote_url(data)?;
+        let file_name = url
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .and_then(|name| if name.is_empty() { None } else { Some(name) })
+            .unwrap_or("file");
+        let mut attempt = 0;
+        let mut response = loop {
+            let url = get_remote_url(data)?;
+            let result = client.get(url.as_str()).send().await;
+            let retry_reason = match &result {
+                Ok(response) if response.status().is_server_error() => {
+                    Some(response.status().to_string())
+                }
+                Ok(_) => None,
+                Err(e) => Some(e.to_string()),
+            };
+            let reason = match retry_reason {
+                Some(reason) if attempt < retries => reason,
+                _ => break result.map_err(remote_fetch_error)?,
+            };
+            attempt += 1;
+            warn!("failed to fetch {url} ({reason}), retrying ({attempt}/{retries})");
+            actix_web::rt::time::sleep(retry_delay).await;
+            retry_delay = retry_delay.saturating_mul(2);
+        };
         let bytes = response
             .body()
             .limit(payload_limit)
             .await
             .map_err(error::ErrorInternalServerError)?
             .to_vec();
-        let config = config
-            .read()
-            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
-        let bytes_checksum = util::sha256_digest(&*bytes)?;
+        let checksum_algorithm = config.paste.checksum_algorithm.unwrap_or_default();
+        let bytes_checksum = util::digest(&*bytes, checksum_algorithm)?;
         self.data = bytes;
-        if !config.paste.duplicate_files.unwrap_or(true) && expiry_date.is_none() {
+        // the uploader is not known here, so the files are not matched in the per-token scope
+        if !config.paste.duplicate_files.unwrap_or(true)
+            && expiry_date.is_none()
+            && config.paste.dedup_scope.unwrap_or_default() == DedupScope::Global
+        {
             if let Some(file) =
-                Directory::try_from(config.server.upload_path.as_path())?.get_file(bytes_checksum)
+                Directory::from_path(&config.server.upload_path, checksum_algorithm)?
+                    .get_file(bytes_checksum)
             {
                 return Ok(file
                     .path
@@ -262,14 +921,18 @@ impl Paste {
                     .to_string());
             }
         }
-        self.store_file(file_name, expiry_date, None, &config)
+        self.store_file(file_name, expiry_date, header_filename, config)
     }
 
     /// Writes an URL to a file in upload directory.
     ///
     /// - Checks if the data is a valid URL.
+    /// - Checks if the URL scheme is one of the [`allowed_url_schemes`].
+    /// - Checks if the URL is not longer than the [`max_url_length`].
     /// - If [`random_url.enabled`] is `true`, file name is set to a pet name or random string.
     ///
+    /// [`allowed_url_schemes`]: crate::config::PasteConfig::allowed_url_schemes
+    /// [`max_url_length`]: crate::config::PasteConfig::max_url_length
     /// [`random_url.enabled`]: crate::random::RandomURLConfig::enabled
     #[allow(deprecated)]
     pub fn store_url(
@@ -277,12 +940,19 @@ impl Paste {
         expiry_date: Option<u128>,
         header_filename: Option<String>,
         config: &Config,
-    ) -> IoResult<String> {
-        let data = str::from_utf8(&self.data)
-            .map_err(|e| IoError::new(IoErrorKind::Other, e.to_string()))?;
-        let url = Url::parse(data).map_err(|e| IoError::new(IoErrorKind::Other, e.to_string()))?;
+    ) -> Result<String, Error> {
+        let data = str::from_utf8(&self.data).map_err(error::ErrorBadRequest)?;
+        check_url_length(data, config)?;
+        let url = Url::parse(data).map_err(error::ErrorBadRequest)?;
+        let scheme_allowed = match &config.paste.allowed_url_schemes {
+            Some(schemes) => schemes.iter().any(|v| v.eq_ignore_ascii_case(url.scheme())),
+            None => DEFAULT_ALLOWED_URL_SCHEMES.contains(&url.scheme()),
+        };
+        if !scheme_allowed {
+            return Err(error::ErrorBadRequest("this URL scheme is not allowed\n"));
+        }
         let mut file_name = self.type_.get_dir();
-        if let Some(random_url) = &config.paste.random_url {
+        if let Some(random_url) = config.paste.get_random_url(self.type_) {
             if let Some(random_text) = random_url.generate() {
                 file_name = random_text;
             }
@@ -303,14 +973,20 @@ impl Paste {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::RandomURLByType;
     use crate::random::{RandomURLConfig, RandomURLType};
     use crate::util;
+    use actix_web::http::StatusCode;
     use actix_web::web::Data;
     use awc::ClientBuilder;
     use byte_unit::Byte;
     use std::env;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
     use std::str::FromStr;
-    use std::time::Duration;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Barrier};
+    use std::thread;
 
     #[actix_rt::test]
     #[allow(deprecated)]
@@ -321,7 +997,7 @@ mod tests {
             enabled: Some(true),
             words: Some(3),
             separator: Some(String::from("_")),
-            type_: RandomURLType::PetName,
+            type_: Some(RandomURLType::PetName),
             ..RandomURLConfig::default()
         });
         let paste = Paste {
@@ -340,7 +1016,7 @@ mod tests {
 
         config.paste.random_url = Some(RandomURLConfig {
             length: Some(4),
-            type_: RandomURLType::Alphanumeric,
+            type_: Some(RandomURLType::Alphanumeric),
             suffix_mode: Some(true),
             ..RandomURLConfig::default()
         });
@@ -356,7 +1032,7 @@ mod tests {
 
         config.paste.random_url = Some(RandomURLConfig {
             length: Some(4),
-            type_: RandomURLType::Alphanumeric,
+            type_: Some(RandomURLType::Alphanumeric),
             suffix_mode: Some(true),
             ..RandomURLConfig::default()
         });
@@ -372,7 +1048,7 @@ mod tests {
 
         config.paste.random_url = Some(RandomURLConfig {
             length: Some(4),
-            type_: RandomURLType::Alphanumeric,
+            type_: Some(RandomURLType::Alphanumeric),
             suffix_mode: Some(false),
             ..RandomURLConfig::default()
         });
@@ -399,7 +1075,7 @@ mod tests {
         config.paste.default_extension = String::from("bin");
         config.paste.random_url = Some(RandomURLConfig {
             length: Some(10),
-            type_: RandomURLType::Alphanumeric,
+            type_: Some(RandomURLType::Alphanumeric),
             ..RandomURLConfig::default()
         });
         let paste = Paste {
@@ -418,7 +1094,7 @@ mod tests {
 
         config.paste.random_url = Some(RandomURLConfig {
             length: Some(4),
-            type_: RandomURLType::Alphanumeric,
+            type_: Some(RandomURLType::Alphanumeric),
             suffix_mode: Some(true),
             ..RandomURLConfig::default()
         });
@@ -438,7 +1114,7 @@ mod tests {
 
         config.paste.random_url = Some(RandomURLConfig {
             length: Some(4),
-            type_: RandomURLType::Alphanumeric,
+            type_: Some(RandomURLType::Alphanumeric),
             suffix_mode: Some(true),
             ..RandomURLConfig::default()
         });
@@ -528,7 +1204,7 @@ mod tests {
                 .finish(),
         );
         let file_name = paste
-            .store_remote_file(None, &client_data, &RwLock::new(config.clone()))
+            .store_remote_file(None, None, &client_data, &config)
             .await?;
         let file_path = PasteType::RemoteFile
             .get_path(&config.server.upload_path)
@@ -550,4 +1226,499 @@ mod tests {
 
         Ok(())
     }
+
+    #[actix_rt::test]
+    async fn test_max_url_length() -> Result<(), Error> {
+        let test_upload_dir = "test_max_url_length_upload";
+        util::create_upload_dirs(Path::new(test_upload_dir))?;
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.paste.max_url_length = Some(2048);
+
+        let paste = Paste {
+            data: b"https://example.com/page".to_vec(),
+            type_: PasteType::Url,
+        };
+        let file_name = paste.store_url(None, Some(String::from("short")), &config)?;
+        assert_eq!("short", file_name);
+
+        let url = format!("https://example.com/{}", "a".repeat(10 * 1024));
+        let paste = Paste {
+            data: url.as_bytes().to_vec(),
+            type_: PasteType::Url,
+        };
+        let error = paste
+            .store_url(None, Some(String::from("long")), &config)
+            .expect_err("long URL is stored");
+        assert_eq!(StatusCode::BAD_REQUEST, error.error_response().status());
+
+        let mut paste = Paste {
+            data: url.as_bytes().to_vec(),
+            type_: PasteType::RemoteFile,
+        };
+        let error = paste
+            .store_remote_file(None, None, &Client::default(), &config)
+            .await
+            .expect_err("long remote URL is fetched");
+        assert_eq!(StatusCode::BAD_REQUEST, error.error_response().status());
+
+        fs::remove_dir_all(test_upload_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_versioned_file_name() {
+        assert_eq!("report-2.pdf", get_versioned_file_name("report.pdf", 2));
+        assert_eq!(
+            "my.report-2.pdf",
+            get_versioned_file_name("my.report.pdf", 2)
+        );
+        assert_eq!("foo.tar-3.gz", get_versioned_file_name("foo.tar.gz", 3));
+        assert_eq!(".foo-2.txt", get_versioned_file_name(".foo.txt", 2));
+        assert_eq!(".foo-2", get_versioned_file_name(".foo", 2));
+        assert_eq!("file-2", get_versioned_file_name("file", 2));
+    }
+
+    #[test]
+    fn test_on_conflict() -> Result<(), Error> {
+        let test_upload_dir = "test_conflict_upload";
+        fs::create_dir_all(test_upload_dir)?;
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        let upload_path = config.server.upload_path.clone();
+        fs::write(upload_path.join("report.txt"), "v1")?;
+
+        let paste = Paste {
+            data: b"v2".to_vec(),
+            type_: PasteType::File,
+        };
+        config.paste.on_conflict = Some(ConflictStrategy::Error);
+        assert!(paste.store_file("report.txt", None, None, &config).is_err());
+
+        config.paste.on_conflict = Some(ConflictStrategy::Version);
+        assert_eq!(
+            "report-2.txt",
+            paste.store_file("report.txt", None, None, &config)?
+        );
+        assert_eq!("v1", fs::read_to_string(upload_path.join("report.txt"))?);
+        assert_eq!("v2", fs::read_to_string(upload_path.join("report-2.txt"))?);
+        // the same content is stored as a new version as well
+        assert_eq!(
+            "report-3.txt",
+            paste.store_file("report.txt", None, None, &config)?
+        );
+        let paste = Paste {
+            data: b"v3".to_vec(),
+            type_: PasteType::File,
+        };
+        assert_eq!(
+            "report-4.txt",
+            paste.store_file("report.txt", None, None, &config)?
+        );
+
+        config.paste.on_conflict = Some(ConflictStrategy::Overwrite);
+        assert_eq!(
+            "report.txt",
+            paste.store_file("report.txt", None, None, &config)?
+        );
+        assert_eq!("v3", fs::read_to_string(upload_path.join("report.txt"))?);
+
+        fs::remove_dir_all(test_upload_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_trust_magic_extension() -> Result<(), Error> {
+        let test_upload_dir = "test_magic_upload";
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        util::create_upload_dirs(&config.server.upload_path)?;
+
+        let paste = Paste {
+            data: b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec(),
+            type_: PasteType::File,
+        };
+        assert_eq!(
+            "payload.txt",
+            paste.store_file("payload.txt", None, None, &config)?
+        );
+
+        config.paste.trust_magic_extension = Some(true);
+        assert_eq!(
+            "image.png",
+            paste.store_file("image.txt", None, None, &config)?
+        );
+        assert_eq!(
+            "image.old.png",
+            paste.store_file("image.old.txt", None, None, &config)?
+        );
+        assert_eq!(
+            "photo.png",
+            paste.store_file("photo.png", None, None, &config)?
+        );
+        assert!(PathBuf::from(test_upload_dir).join("image.png").exists());
+        assert!(!PathBuf::from(test_upload_dir).join("image.txt").exists());
+
+        let paste = Paste {
+            data: b"plain text".to_vec(),
+            type_: PasteType::File,
+        };
+        assert_eq!(
+            "notes.md",
+            paste.store_file("notes.md", None, None, &config)?
+        );
+
+        fs::remove_dir_all(test_upload_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_blacklisted_files() -> Result<(), Error> {
+        let test_upload_dir = "test_blacklisted_files";
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        util::create_upload_dirs(&config.server.upload_path)?;
+
+        let paste = Paste {
+            data: b"<svg xmlns=\"http://www.w3.org/2000/svg\"></svg>".to_vec(),
+            type_: PasteType::File,
+        };
+        paste.store_file("image.svg", None, None, &config)?;
+        paste.store_file("image.txt", None, None, &config)?;
+        let paste = Paste {
+            data: b"https://example.com/image.svg".to_vec(),
+            type_: PasteType::Url,
+        };
+        paste.store_url(None, Some(String::from("url.svg")), &config)?;
+        assert!(get_blacklisted_files(&config).is_empty());
+
+        config.paste.mime_blacklist = vec![String::from("image/svg+xml")];
+        assert_eq!(
+            vec![PathBuf::from(test_upload_dir).join("image.svg")],
+            get_blacklisted_files(&config)
+        );
+
+        fs::remove_dir_all(test_upload_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_random_url_by_type() -> Result<(), Error> {
+        let test_upload_dir = "test_random_url_by_type_upload";
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.paste.random_url = None;
+        config.paste.random_url_by_type = Some(RandomURLByType {
+            file: Some(RandomURLConfig {
+                type_: Some(RandomURLType::Alphanumeric),
+                length: Some(8),
+                ..RandomURLConfig::default()
+            }),
+            url: Some(RandomURLConfig {
+                type_: Some(RandomURLType::PetName),
+                words: Some(2),
+                separator: Some(String::from("-")),
+                ..RandomURLConfig::default()
+            }),
+            ..RandomURLByType::default()
+        });
+        util::create_upload_dirs(&config.server.upload_path)?;
+
+        let paste = Paste {
+            data: b"test".to_vec(),
+            type_: PasteType::File,
+        };
+        let file_name = paste.store_file("file.txt", None, None, &config)?;
+        let (stem, extension) = file_name.split_once('.').unwrap_or_default();
+        assert_eq!("txt", extension);
+        assert_eq!(8, stem.len());
+        assert!(stem.chars().all(|c| c.is_ascii_alphanumeric()));
+
+        let paste = Paste {
+            data: b"https://orhun.dev/".to_vec(),
+            type_: PasteType::Url,
+        };
+        let file_name = paste.store_url(None, None, &config)?;
+        assert_eq!(2, file_name.split('-').count());
+        assert!(file_name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c == '-'));
+
+        // the global configuration is used for the other types
+        let paste = Paste {
+            data: b"test".to_vec(),
+            type_: PasteType::Oneshot,
+        };
+        assert_eq!(
+            "oneshot.txt",
+            paste.store_file("oneshot.txt", None, None, &config)?
+        );
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_file() -> Result<(), Error> {
+        let test_upload_dir = PathBuf::from("test_remove_upload");
+        util::create_upload_dirs(&test_upload_dir)?;
+        let expired_path = test_upload_dir.join("file.txt.1000");
+        fs::write(&expired_path, "expired")?;
+        fs::write(test_upload_dir.join("file.txt"), "unexpired")?;
+        store_charset(&test_upload_dir, "file.txt", Some("iso-8859-1"))?;
+        store_owner(&test_upload_dir, "file.txt", Some("owner"))?;
+
+        remove_file(&test_upload_dir, &expired_path)?;
+        assert!(!expired_path.exists());
+        assert_eq!(
+            Some(String::from("iso-8859-1")),
+            get_charset(&test_upload_dir, "file.txt")
+        );
+
+        remove_file(&test_upload_dir, &test_upload_dir.join("file.txt"))?;
+        assert_eq!(None, get_charset(&test_upload_dir, "file.txt"));
+        assert_eq!(None, get_owner(&test_upload_dir, "file.txt"));
+
+        fs::remove_dir_all(test_upload_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_versions() -> Result<(), Error> {
+        let test_upload_dir = "test_max_versions_upload";
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.paste.on_conflict = Some(ConflictStrategy::Version);
+        config.paste.max_versions = Some(3);
+        util::create_upload_dirs(&config.server.upload_path)?;
+        let upload_path = PathBuf::from(test_upload_dir);
+
+        for (version, expected) in [
+            (1, "report.txt"),
+            (2, "report-2.txt"),
+            (3, "report-3.txt"),
+            (4, "report-4.txt"),
+            (5, "report-5.txt"),
+        ] {
+            let paste = Paste {
+                data: format!("v{version}").into_bytes(),
+                type_: PasteType::File,
+            };
+            assert_eq!(
+                expected,
+                paste.store_file("report.txt", None, None, &config)?
+            );
+        }
+
+        let mut files = fs::read_dir(&upload_path)?
+            .filter_map(Result::ok)
+            .filter(|v| v.path().is_file())
+            .map(|v| v.file_name().to_string_lossy().to_string())
+            .collect::<Vec<String>>();
+        files.sort();
+        assert_eq!(vec!["report-3.txt", "report-4.txt", "report-5.txt"], files);
+        assert_eq!("v5", fs::read_to_string(upload_path.join("report-5.txt"))?);
+
+        fs::remove_dir_all(test_upload_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_mime_blacklist_by_extension() -> Result<(), Error> {
+        let test_upload_dir = "test_blacklist_upload";
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.paste.mime_blacklist = vec![String::from("image/svg+xml")];
+        util::create_upload_dirs(&config.server.upload_path)?;
+
+        let paste = Paste {
+            data: b"<svg xmlns=\"http://www.w3.org/2000/svg\"></svg>".to_vec(),
+            type_: PasteType::File,
+        };
+        let error = paste
+            .store_file("image.svg", None, None, &config)
+            .expect_err("blacklisted file is stored");
+        assert_eq!(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            error.error_response().status()
+        );
+        let error = paste
+            .store_file("image.txt", None, Some(String::from("image.svg")), &config)
+            .expect_err("blacklisted file is stored via the filename header");
+        assert_eq!(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            error.error_response().status()
+        );
+        assert!(!PathBuf::from(test_upload_dir).join("image.svg").exists());
+
+        let file_name = paste.store_file("image.txt", None, None, &config)?;
+        assert_eq!("image.txt", file_name);
+
+        fs::remove_dir_all(test_upload_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_require_extension() -> Result<(), Error> {
+        let test_upload_dir = "test_require_extension_upload";
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.paste.require_extension = Some(true);
+        util::create_upload_dirs(&config.server.upload_path)?;
+
+        let paste = Paste {
+            data: b"plain text".to_vec(),
+            type_: PasteType::File,
+        };
+        let error = paste
+            .store_file("file", None, None, &config)
+            .expect_err("file without an extension is stored");
+        assert_eq!(StatusCode::BAD_REQUEST, error.error_response().status());
+        assert!(!PathBuf::from(test_upload_dir).join("file").exists());
+
+        let file_name = paste.store_file("file.txt", None, None, &config)?;
+        assert_eq!("file.txt", file_name);
+
+        config.paste.default_extension = String::from("txt");
+        let file_name = paste.store_file("other", None, None, &config)?;
+        assert_eq!("other.txt", file_name);
+
+        fs::remove_dir_all(test_upload_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_allowed_url_schemes() -> Result<(), Error> {
+        let test_upload_dir = "test_scheme_upload";
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        util::create_upload_dirs(&config.server.upload_path)?;
+
+        let paste = Paste {
+            data: b"http://example.com/".to_vec(),
+            type_: PasteType::Url,
+        };
+        let file_name = paste.store_url(None, None, &config)?;
+        let file_path = PasteType::Url
+            .get_path(&config.server.upload_path)?
+            .join(file_name);
+        assert_eq!("http://example.com/", fs::read_to_string(file_path)?);
+
+        let paste = Paste {
+            data: b"javascript:alert(1)".to_vec(),
+            type_: PasteType::OneshotUrl,
+        };
+        let error = paste
+            .store_url(None, None, &config)
+            .expect_err("javascript URL is stored");
+        assert_eq!(StatusCode::BAD_REQUEST, error.error_response().status());
+
+        config.paste.allowed_url_schemes = Some(vec![String::from("ftp")]);
+        let paste = Paste {
+            data: b"http://example.com/".to_vec(),
+            type_: PasteType::Url,
+        };
+        assert!(paste.store_url(None, None, &config).is_err());
+
+        fs::remove_dir_all(test_upload_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_concurrent_uploads() -> Result<(), Error> {
+        let test_upload_dir = "test_concurrent_upload";
+        fs::create_dir_all(test_upload_dir)?;
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        let config = Arc::new(config);
+
+        for i in 0..10 {
+            let file_name = format!("race{i}.txt");
+            let barrier = Arc::new(Barrier::new(2));
+            let handles = (0..2)
+                .map(|j| {
+                    let config = Arc::clone(&config);
+                    let barrier = Arc::clone(&barrier);
+                    let file_name = file_name.clone();
+                    thread::spawn(move || {
+                        let paste = Paste {
+                            data: format!("upload{j}").into_bytes(),
+                            type_: PasteType::File,
+                        };
+                        barrier.wait();
+                        paste
+                            .store_file(&file_name, None, None, &config)
+                            .map_err(|e| e.error_response().status())
+                    })
+                })
+                .collect::<Vec<_>>();
+            let mut results = handles
+                .into_iter()
+                .map(|handle| handle.join().expect("thread panicked"))
+                .collect::<Vec<_>>();
+            results.sort();
+            assert_eq!(
+                vec![Ok(file_name.clone()), Err(StatusCode::CONFLICT)],
+                results
+            );
+            let content = fs::read_to_string(config.server.upload_path.join(&file_name))?;
+            assert!(content == "upload0" || content == "upload1");
+        }
+
+        fs::remove_dir_all(test_upload_dir)?;
+        Ok(())
+    }
+
+    #[actix_rt::test]
+    async fn test_store_remote_file_retry() -> Result<(), Error> {
+        let test_upload_dir = "test_remote_retry_upload";
+        fs::create_dir_all(test_upload_dir)?;
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.paste.remote_retries = Some(2);
+        config.paste.remote_retry_delay = Some(Duration::from_millis(10));
+        config.server.max_content_length = Byte::from_u64(1000);
+
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        let requests = Arc::new(AtomicUsize::new(0));
+        let requests_count = Arc::clone(&requests);
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(&stream);
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|v| v > 2) {
+                    line.clear();
+                }
+                let response: &[u8] = if requests_count.fetch_add(1, Ordering::SeqCst) == 0 {
+                    b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                } else {
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nflaky"
+                };
+                let _ = stream.write_all(response);
+            }
+        });
+
+        let mut paste = Paste {
+            data: format!("http://{address}/flaky.txt").into_bytes(),
+            type_: PasteType::RemoteFile,
+        };
+        let file_name = paste
+            .store_remote_file(None, None, &Client::default(), &config)
+            .await?;
+        assert_eq!("flaky.txt", file_name);
+        assert_eq!(2, requests.load(Ordering::SeqCst));
+        assert_eq!(
+            "flaky",
+            fs::read_to_string(
+                PasteType::RemoteFile
+                    .get_path(&config.server.upload_path)?
+                    .join(file_name)
+            )?
+        );
+
+        fs::remove_dir_all(test_upload_dir)?;
+        Ok(())
+    }
 }
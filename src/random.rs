@@ -1,5 +1,6 @@
 use petname::Generator;
 use rand::{distributions::Alphanumeric, Rng};
+use std::fmt::{self, Display, Formatter};
 
 /// Random URL configuration.
 #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
@@ -9,13 +10,21 @@ pub struct RandomURLConfig {
     pub enabled: Option<bool>,
     /// Count of words that pet name will include.
     pub words: Option<u8>,
+    /// Minimum count of words that pet name will include.
+    pub words_min: Option<u8>,
+    /// Maximum count of words that pet name will include.
+    pub words_max: Option<u8>,
     /// Separator between the words.
     pub separator: Option<String>,
     /// Length of the random string to generate.
     pub length: Option<usize>,
     /// Type of the random URL.
     #[serde(rename = "type")]
-    pub type_: RandomURLType,
+    pub type_: Option<RandomURLType>,
+    /// Type of the random URL to fall back to if [`type`] is not set.
+    ///
+    /// [`type`]: Self::type_
+    pub default_type: Option<RandomURLType>,
     /// Append a random string to the original filename.
     pub suffix_mode: Option<bool>,
 }
@@ -27,11 +36,9 @@ impl RandomURLConfig {
         if !self.enabled.unwrap_or(true) {
             return None;
         }
-        Some(match self.type_ {
-            RandomURLType::PetName => petname::Petnames::large().generate_one(
-                self.words.unwrap_or(2),
-                self.separator.as_deref().unwrap_or("-"),
-            )?,
+        Some(match self.get_type() {
+            RandomURLType::PetName => petname::Petnames::large()
+                .generate_one(self.word_count(), self.separator.as_deref().unwrap_or("-"))?,
             RandomURLType::Alphanumeric => rand::thread_rng()
                 .sample_iter(&Alphanumeric)
                 .take(self.length.unwrap_or(8))
@@ -39,21 +46,60 @@ impl RandomURLConfig {
                 .collect::<String>(),
         })
     }
+
+    /// Returns the type of the random URL.
+    ///
+    /// Resolution order is [`type`], [`default_type`] and [`RandomURLType::PetName`].
+    ///
+    /// [`type`]: Self::type_
+    /// [`default_type`]: Self::default_type
+    pub fn get_type(&self) -> RandomURLType {
+        self.type_.or(self.default_type).unwrap_or_default()
+    }
+
+    /// Returns the warnings about the incomplete configuration.
+    pub fn get_warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.type_.is_none() && self.default_type.is_none() {
+            warnings.push(format!(
+                "[paste].random_url.type is not set, falling back to \"{}\"",
+                self.get_type()
+            ));
+        }
+        warnings
+    }
+
+    /// Returns the count of words for the pet name.
+    ///
+    /// Picks a random count between [`words_min`] and [`words_max`] if a range is configured.
+    ///
+    /// [`words_min`]: Self::words_min
+    /// [`words_max`]: Self::words_max
+    fn word_count(&self) -> u8 {
+        let default = self.words.unwrap_or(2);
+        let min = self.words_min.unwrap_or(default);
+        let max = self.words_max.unwrap_or(default).max(min);
+        rand::thread_rng().gen_range(min..=max)
+    }
 }
 
 /// Type of the random URL.
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum RandomURLType {
     /// Generate a random pet name.
+    #[default]
     PetName,
     /// Generate a random alphanumeric string.
     Alphanumeric,
 }
 
-impl Default for RandomURLType {
-    fn default() -> Self {
-        Self::PetName
+impl Display for RandomURLType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PetName => write!(f, "petname"),
+            Self::Alphanumeric => write!(f, "alphanumeric"),
+        }
     }
 }
 
@@ -68,7 +114,7 @@ mod tests {
             enabled: Some(true),
             words: Some(3),
             separator: Some(String::from("~")),
-            type_: RandomURLType::PetName,
+            type_: Some(RandomURLType::PetName),
             ..RandomURLConfig::default()
         };
         let random_url = random_config
@@ -78,7 +124,7 @@ mod tests {
 
         let random_config = RandomURLConfig {
             length: Some(21),
-            type_: RandomURLType::Alphanumeric,
+            type_: Some(RandomURLType::Alphanumeric),
             ..RandomURLConfig::default()
         };
         let random_url = random_config
@@ -86,10 +132,56 @@ mod tests {
             .expect("cannot generate random URL");
         assert_eq!(21, random_url.len());
 
+        let random_config = RandomURLConfig {
+            words_min: Some(2),
+            words_max: Some(4),
+            separator: Some(String::from("~")),
+            type_: Some(RandomURLType::PetName),
+            ..RandomURLConfig::default()
+        };
+        for _ in 0..100 {
+            let random_url = random_config
+                .generate()
+                .expect("cannot generate random URL");
+            assert!((2..=4).contains(&random_url.split('~').count()));
+        }
+
         let random_config = RandomURLConfig {
             enabled: Some(false),
             ..RandomURLConfig::default()
         };
         assert!(random_config.generate().is_none());
     }
+
+    #[test]
+    fn test_minimal_config() -> Result<(), config::ConfigError> {
+        let parse = |table: &str| {
+            config::Config::builder()
+                .add_source(config::File::from_str(table, config::FileFormat::Toml))
+                .build()?
+                .try_deserialize::<RandomURLConfig>()
+        };
+
+        let random_config = parse("separator = \"_\"")?;
+        assert_eq!(RandomURLType::PetName, random_config.get_type());
+        assert_eq!(
+            vec![String::from(
+                "[paste].random_url.type is not set, falling back to \"petname\""
+            )],
+            random_config.get_warnings()
+        );
+        let random_url = random_config
+            .generate()
+            .expect("cannot generate random URL");
+        assert_eq!(2, random_url.split('_').count());
+
+        let random_config = parse("default_type = \"alphanumeric\"")?;
+        assert_eq!(RandomURLType::Alphanumeric, random_config.get_type());
+        assert!(random_config.get_warnings().is_empty());
+
+        let random_config = parse("type = \"petname\"\ndefault_type = \"alphanumeric\"")?;
+        assert_eq!(RandomURLType::PetName, random_config.get_type());
+        assert!(random_config.get_warnings().is_empty());
+        Ok(())
+    }
 }
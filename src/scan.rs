@@ -0,0 +1,235 @@
+use crate::config::ScanConfig;
+use std::io::{Error as IoError, ErrorKind as IoErrorKind, Read, Result as IoResult, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::time::Duration;
+
+/// Size of the chunks that are streamed to the scanner.
+const CHUNK_SIZE: usize = 8192;
+
+/// Default timeout for communicating with the scanner.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Result of a scan.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ScanResult {
+    /// No threats are found.
+    Clean,
+    /// A threat is found with the given signature.
+    Infected(String),
+}
+
+/// Scans the given data with ClamAV using the `INSTREAM` command.
+///
+/// - If [`address`] is an absolute path, it is used as a Unix socket.
+/// - Otherwise, a TCP connection is established.
+///
+/// If [`timeout`] is not set, [`DEFAULT_TIMEOUT`] is used for connecting, reading and writing.
+///
+/// [`address`]: crate::config::ScanConfig::address
+/// [`timeout`]: crate::config::ScanConfig::timeout
+pub fn scan_data(config: &ScanConfig, data: &[u8]) -> IoResult<ScanResult> {
+    let timeout = config.timeout.unwrap_or(DEFAULT_TIMEOUT);
+    #[cfg(unix)]
+    if Path::new(&config.address).is_absolute() {
+        let stream = std::os::unix::net::UnixStream::connect(&config.address)?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+        return instream(stream, data);
+    }
+    #[cfg(not(unix))]
+    if Path::new(&config.address).is_absolute() {
+        return Err(IoError::new(
+            IoErrorKind::Unsupported,
+            "unix sockets are not supported on this platform",
+        ));
+    }
+    let stream = connect(&config.address, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    instream(stream, data)
+}
+
+/// Connects to the first reachable socket address of the given address within the timeout.
+fn connect(address: &str, timeout: Duration) -> IoResult<TcpStream> {
+    let mut last_error = None;
+    for socket_addr in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&socket_addr, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        IoError::new(
+            IoErrorKind::InvalidInput,
+            format!("cannot resolve the scanner address: {address}"),
+        )
+    }))
+}
+
+/// Streams the data to the scanner and parses the reply.
+fn instream<S: Read + Write>(mut stream: S, data: &[u8]) -> IoResult<ScanResult> {
+    stream.write_all(b"zINSTREAM\0")?;
+    for chunk in data.chunks(CHUNK_SIZE) {
+        let size = u32::try_from(chunk.len())
+            .map_err(|e| IoError::new(IoErrorKind::InvalidInput, e.to_string()))?;
+        stream.write_all(&size.to_be_bytes())?;
+        stream.write_all(chunk)?;
+    }
+    stream.write_all(&[0; 4])?;
+    stream.flush()?;
+
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply)?;
+    let reply = String::from_utf8_lossy(&reply);
+    let reply = reply.trim_end_matches(['\0', '\n']);
+    if reply.ends_with("OK") {
+        Ok(ScanResult::Clean)
+    } else if let Some(signature) = reply.strip_suffix(" FOUND") {
+        Ok(ScanResult::Infected(
+            signature
+                .rsplit(": ")
+                .next()
+                .unwrap_or(signature)
+                .to_string(),
+        ))
+    } else {
+        Err(IoError::other(format!(
+            "unexpected reply from scanner: {reply}"
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, ScanFailurePolicy};
+    use crate::paste::{Paste, PasteType};
+    use actix_web::http::StatusCode;
+    use std::env;
+    use std::fs;
+    use std::net::TcpListener;
+    use std::path::PathBuf;
+    use std::thread;
+
+    /// EICAR anti-virus test string.
+    const EICAR: &str = r"X5O!P%@AP[4\PZX54(P^)7CC)7}$EICAR-STANDARD-ANTIVIRUS-TEST-FILE!$H+H*";
+
+    /// Handles a single `INSTREAM` session and flags the EICAR test string.
+    fn handle_stub_session(mut stream: TcpStream) -> IoResult<()> {
+        let mut command = [0; 10];
+        stream.read_exact(&mut command)?;
+        let mut data = Vec::new();
+        loop {
+            let mut size = [0; 4];
+            stream.read_exact(&mut size)?;
+            let size = u32::from_be_bytes(size) as usize;
+            if size == 0 {
+                break;
+            }
+            let mut chunk = vec![0; size];
+            stream.read_exact(&mut chunk)?;
+            data.extend(chunk);
+        }
+        let reply: &[u8] = if String::from_utf8_lossy(&data).contains(EICAR) {
+            b"stream: Eicar-Test-Signature FOUND\0"
+        } else {
+            b"stream: OK\0"
+        };
+        stream.write_all(reply)
+    }
+
+    /// Spawns a stub scanner and returns its address.
+    fn spawn_stub_scanner() -> IoResult<String> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?.to_string();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let _ = handle_stub_session(stream);
+            }
+        });
+        Ok(address)
+    }
+
+    #[test]
+    fn test_scan_data() -> IoResult<()> {
+        let config = ScanConfig {
+            enabled: true,
+            address: spawn_stub_scanner()?,
+            ..Default::default()
+        };
+        assert_eq!(
+            ScanResult::Infected(String::from("Eicar-Test-Signature")),
+            scan_data(&config, EICAR.as_bytes())?
+        );
+        assert_eq!(ScanResult::Clean, scan_data(&config, b"test")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_store_infected_file() -> Result<(), actix_web::Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?;
+        config.scan = Some(ScanConfig {
+            enabled: true,
+            address: spawn_stub_scanner()?,
+            ..Default::default()
+        });
+
+        let paste = Paste {
+            data: EICAR.as_bytes().to_vec(),
+            type_: PasteType::File,
+        };
+        let error = paste
+            .store_file("eicar.txt", None, None, &config)
+            .expect_err("infected file is stored");
+        assert_eq!(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            error.error_response().status()
+        );
+        assert!(!PathBuf::from("eicar.txt").exists());
+
+        let paste = Paste {
+            data: b"clean".to_vec(),
+            type_: PasteType::File,
+        };
+        let file_name = paste.store_file("clean.txt", None, None, &config)?;
+        assert_eq!("clean", fs::read_to_string(&file_name)?);
+        fs::remove_file(file_name)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scanner_unreachable() -> Result<(), actix_web::Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?;
+        config.scan = Some(ScanConfig {
+            enabled: true,
+            address: String::from("127.0.0.1:1"),
+            on_error: ScanFailurePolicy::Closed,
+            ..Default::default()
+        });
+
+        let paste = Paste {
+            data: b"unscanned".to_vec(),
+            type_: PasteType::File,
+        };
+        let error = paste
+            .store_file("unscanned.txt", None, None, &config)
+            .expect_err("unscanned file is stored");
+        assert_eq!(
+            StatusCode::SERVICE_UNAVAILABLE,
+            error.error_response().status()
+        );
+
+        if let Some(ref mut scan_config) = config.scan {
+            scan_config.on_error = ScanFailurePolicy::Open;
+        }
+        let file_name = paste.store_file("unscanned.txt", None, None, &config)?;
+        assert_eq!("unscanned", fs::read_to_string(&file_name)?);
+        fs::remove_file(file_name)?;
+
+        Ok(())
+    }
+}
@@ -1,37 +1,61 @@
-use crate::auth::{extract_tokens, handle_unauthorized_error, unauthorized_error};
-use crate::config::{Config, LandingPageConfig, TokenType};
-use crate::file::Directory;
-use crate::header::{self, ContentDisposition};
+use crate::archive;
+use crate::auth::{self, extract_tokens, handle_unauthorized_error, unauthorized_error};
+use crate::compression;
+use crate::config::{
+    CacheConfig, ChecksumAlgorithm, Config, DedupScope, EndpointGroup, LandingPageConfig,
+    ServerConfig, TokenType, TrailingSlash,
+};
+use crate::file::{self, Directory};
+use crate::header::{self, ClientConnection, ContentDisposition, ServerTiming};
 use crate::mime as mime_util;
-use crate::paste::{Paste, PasteType};
+use crate::openapi;
+use crate::paste::{self, Paste, PasteType, Transfer};
+use crate::transcode;
 use crate::util::{self, safe_path_join};
-use actix_files::NamedFile;
+use actix_files::{HttpRange, NamedFile};
 use actix_multipart::Multipart;
+use actix_web::body::{BodyStream, MessageBody, SizedStream};
+use actix_web::guard::GuardContext;
+use actix_web::http::header::{
+    ContentType, DispositionType, EntityTag, Header, HeaderValue, HttpDate, IfNoneMatch, ACCEPT,
+    ACCEPT_ENCODING, ACCEPT_RANGES, AGE, CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_ENCODING,
+    CONTENT_LENGTH, CONTENT_RANGE, ETAG, EXPIRES, ORIGIN, RANGE, REFERER, VARY,
+    X_CONTENT_TYPE_OPTIONS,
+};
 use actix_web::http::StatusCode;
 use actix_web::middleware::ErrorHandlers;
 use actix_web::{delete, error, get, post, web, Error, HttpRequest, HttpResponse};
+use actix_web_grants::authorities::{AuthDetails, AuthoritiesCheck};
 use actix_web_grants::GrantsMiddleware;
 use awc::Client;
 use byte_unit::{Byte, UnitType};
-use futures_util::stream::StreamExt;
+use futures_util::future;
+use futures_util::stream::{self, Stream, StreamExt};
+use glob::glob;
 use mime::TEXT_PLAIN_UTF_8;
-use serde::{Deserialize, Serialize};
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{de, Deserialize, Deserializer, Serialize};
 use std::convert::TryFrom;
 use std::env;
 use std::fs;
-use std::path::PathBuf;
-use std::sync::RwLock;
-use std::time::{Duration, UNIX_EPOCH};
-use uts2ts;
+use std::io::{Error as IoError, ErrorKind as IoErrorKind, Read, Seek, SeekFrom};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use url::Url;
 
 /// Shows the landing page.
 #[get("/")]
 #[allow(deprecated)]
-async fn index(config: web::Data<RwLock<Config>>) -> Result<HttpResponse, Error> {
-    let mut config = config
-        .read()
-        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
-        .clone();
+async fn index(
+    request: HttpRequest,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
+    let mut config = util::read_config(&config)?.clone();
+    let host = ClientConnection::from_request(&request, &config.server).host;
     let redirect = HttpResponse::Found()
         .append_header(("Location", env!("CARGO_PKG_HOMEPAGE")))
         .finish();
@@ -51,7 +75,7 @@ async fn index(config: web::Data<RwLock<Config>>) -> Result<HttpResponse, Error>
             landing_page.content_type = config.server.landing_page_content_type;
         }
     }
-    if let Some(mut landing_page) = config.landing_page {
+    if let Some(mut landing_page) = config.landing_page.map(|v| v.for_host(&host)) {
         if let Some(file) = landing_page.file {
             landing_page.text = fs::read_to_string(file).ok();
         }
@@ -75,21 +99,316 @@ async fn index(config: web::Data<RwLock<Config>>) -> Result<HttpResponse, Error>
 struct ServeOptions {
     /// If set to `true`, change the MIME type to `application/octet-stream` and force downloading
     /// the file.
+    #[serde(default)]
     download: bool,
+    /// If set, render the Markdown files as HTML (e.g. `?render`).
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    render: bool,
+    /// If set, show the text files as HTML with line anchors (e.g. `?view#L10`).
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    view: bool,
+    /// Range of the lines to show in the HTML view (e.g. `?lines=10-20`).
+    ///
+    /// Implies [`view`].
+    ///
+    /// [`view`]: Self::view
+    lines: Option<String>,
+}
+
+/// Parses the given range of lines (e.g. `10-20` or `10`).
+///
+/// Lines are numbered from 1 and the range is inclusive.
+fn parse_line_range(range: &str) -> Result<RangeInclusive<usize>, Error> {
+    let invalid_range = || error::ErrorBadRequest("invalid line range\n");
+    let (start, end) = range.split_once('-').unwrap_or((range, range));
+    let start = start.trim().parse::<usize>().map_err(|_| invalid_range())?;
+    let end = end.trim().parse::<usize>().map_err(|_| invalid_range())?;
+    if start == 0 || start > end {
+        return Err(invalid_range());
+    }
+    Ok(start..=end)
+}
+
+/// Renders the given text as an HTML page with an anchor for each line (e.g. `#L10`).
+///
+/// Only the lines in the given range are rendered, if any.
+fn render_lines(title: &str, text: &str, range: Option<RangeInclusive<usize>>) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n<pre>\n",
+        escape_html(title)
+    );
+    for (number, line) in text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        .filter(|(number, _)| match &range {
+            Some(range) => range.contains(number),
+            None => true,
+        })
+    {
+        html.push_str(&format!(
+            "<span id=\"L{number}\"><a href=\"#L{number}\">{number}</a> {}</span>\n",
+            escape_html(line)
+        ));
+    }
+    html.push_str("</pre>\n</body>\n</html>\n");
+    html
+}
+
+/// Default maximum size of the files that are rendered as HTML.
+const DEFAULT_MAX_RENDER_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Checks if the file at the given path can be rendered as HTML.
+///
+/// Returns bad request (`400`) error for the non-text files and payload too large (`413`) error
+/// for the files that are larger than [`max_render_size`].
+///
+/// [`max_render_size`]: crate::config::PasteConfig::max_render_size
+fn check_renderable_file(config: &Config, file: &str, path: &Path) -> Result<(), Error> {
+    let max_size = config
+        .paste
+        .max_render_size
+        .map(|v| v.as_u64())
+        .unwrap_or(DEFAULT_MAX_RENDER_SIZE);
+    if fs::metadata(path)?.len() > max_size {
+        return Err(error::ErrorPayloadTooLarge(
+            "file is too large to be rendered\n",
+        ));
+    }
+    let is_text = match mime_util::find_mime_type(
+        &config.paste.mime_override,
+        &config.paste.mime_by_extension,
+        file.to_string(),
+    )
+    .map_err(error::ErrorInternalServerError)?
+    {
+        Some(mime_type) => mime_type.type_() == mime::TEXT,
+        // the files without a known binary format are considered as text
+        None => util::infer_file_type(path)?.is_none(),
+    };
+    if !is_text {
+        return Err(error::ErrorBadRequest("only text files can be rendered\n"));
+    }
+    Ok(())
+}
+
+/// Deserializes a query parameter that is either empty (i.e. set) or a boolean.
+fn deserialize_flag<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    match String::deserialize(deserializer)?.as_str() {
+        "" | "true" | "1" => Ok(true),
+        "false" | "0" => Ok(false),
+        v => Err(de::Error::custom(format!("invalid flag: {v}"))),
+    }
+}
+
+/// Checks if the upload directory exists.
+///
+/// If [`recreate_upload_path`] is enabled, the missing directories are recreated.
+/// Otherwise, service unavailable (`503`) error is returned.
+///
+/// [`recreate_upload_path`]: ServerConfig::recreate_upload_path
+fn check_upload_path(server_config: &ServerConfig) -> Result<(), Error> {
+    if server_config.upload_path.is_dir() {
+        return Ok(());
+    }
+    error!(
+        "upload directory is missing: {}",
+        server_config.upload_path.display()
+    );
+    if server_config.recreate_upload_path.unwrap_or(false) {
+        match util::create_upload_dirs(&server_config.upload_path) {
+            Ok(()) => {
+                info!("upload directory is recreated");
+                return Ok(());
+            }
+            Err(e) => error!("cannot recreate the upload directory: {}", e),
+        }
+    }
+    Err(error::ErrorServiceUnavailable(
+        "upload directory is not available\n",
+    ))
+}
+
+/// Checks if the uploads are paused via the [`pause_file`].
+///
+/// Returns service unavailable (`503`) error if the file exists.
+///
+/// [`pause_file`]: ServerConfig::pause_file
+fn check_upload_paused(server_config: &ServerConfig) -> Result<(), Error> {
+    match &server_config.pause_file {
+        Some(pause_file) if pause_file.exists() => {
+            warn!("rejected an upload since {} exists", pause_file.display());
+            Err(error::ErrorServiceUnavailable("uploads are paused\n"))
+        }
+        _ => Ok(()),
+    }
 }
 
+/// Checks if the given text only contains whitespace while [`reject_blank_content`] is enabled.
+///
+/// Returns bad request (`400`) error if the content is blank. Binary content is not checked.
+///
+/// [`reject_blank_content`]: crate::config::PasteConfig::reject_blank_content
+fn check_blank_content(config: &Config, data: &[u8], host: &str) -> Result<(), Error> {
+    if config.paste.reject_blank_content.unwrap_or(false)
+        && std::str::from_utf8(data).is_ok_and(|v| v.trim().is_empty())
+    {
+        warn!("{} sent blank content", host);
+        return Err(error::ErrorBadRequest("invalid file content"));
+    }
+    Ok(())
+}
+
+/// Removes a single trailing newline (`\n` or `\r\n`) from the given text while
+/// [`strip_trailing_newline`] is enabled. Binary content is not modified.
+///
+/// [`strip_trailing_newline`]: crate::config::PasteConfig::strip_trailing_newline
+fn strip_trailing_newline(config: &Config, data: &mut Vec<u8>) {
+    if !config.paste.strip_trailing_newline.unwrap_or(false) || std::str::from_utf8(data).is_err() {
+        return;
+    }
+    if data.ends_with(b"\r\n") {
+        data.truncate(data.len() - 2);
+    } else if data.ends_with(b"\n") {
+        data.truncate(data.len() - 1);
+    }
+}
+
+/// Checks if the origin of the request is in the [`remote_allowed_origins`].
+///
+/// The `Origin` header is used if present, otherwise the origin of the `Referer` header.
+///
+/// Returns forbidden (`403`) error if the origin is missing or not allowed.
+///
+/// [`remote_allowed_origins`]: crate::config::PasteConfig::remote_allowed_origins
+fn check_remote_origin(request: &HttpRequest, config: &Config, host: &str) -> Result<(), Error> {
+    let allowed_origins = match &config.paste.remote_allowed_origins {
+        Some(allowed_origins) => allowed_origins,
+        None => return Ok(()),
+    };
+    let origin = request
+        .headers()
+        .get(ORIGIN)
+        .and_then(|v| v.to_str().ok())
+        .map(String::from)
+        .or_else(|| {
+            request
+                .headers()
+                .get(REFERER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| Url::parse(v).ok())
+                .map(|v| v.origin().ascii_serialization())
+        });
+    if origin.is_some_and(|origin| {
+        allowed_origins
+            .iter()
+            .any(|v| v.trim_end_matches('/').eq_ignore_ascii_case(&origin))
+    }) {
+        return Ok(());
+    }
+    warn!("{} sent a remote file from a disallowed origin", host);
+    Err(error::ErrorForbidden("origin is not allowed\n"))
+}
+
+/// Runs the given function on the blocking thread pool.
+///
+/// The errors are passed back as their status and message since [`Error`] is not [`Send`].
+async fn run_blocking<F, T>(f: F) -> Result<T, Error>
+where
+    F: FnOnce() -> Result<T, Error> + Send + 'static,
+    T: Send + 'static,
+{
+    web::block(move || f().map_err(|e| (e.as_response_error().status_code(), e.to_string())))
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .map_err(|(status, message)| error::InternalError::new(message, status).into())
+}
+
+/// Returns the next item of the stream.
+///
+/// Returns request timeout (`408`) error if the item doesn't arrive within the given duration.
+async fn next_with_timeout<S: Stream + Unpin>(
+    stream: &mut S,
+    timeout: Option<Duration>,
+) -> Result<Option<S::Item>, Error> {
+    match timeout {
+        Some(timeout) => actix_web::rt::time::timeout(timeout, stream.next())
+            .await
+            .map_err(|_| error::ErrorRequestTimeout("request body timed out\n")),
+        None => Ok(stream.next().await),
+    }
+}
+
+/// Default value for the maximum depth of the served paths.
+const DEFAULT_MAX_PATH_DEPTH: usize = 1;
+
 /// Serves a file from the upload directory.
-#[get("/{file}")]
+#[get("/{file:.*}")]
+#[actix_web_grants::protect("EndpointGroup::Read", ty = EndpointGroup, error = unauthorized_error)]
 async fn serve(
     request: HttpRequest,
     file: web::Path<String>,
     options: Option<web::Query<ServeOptions>>,
     config: web::Data<RwLock<Config>>,
 ) -> Result<HttpResponse, Error> {
-    let config = config
-        .read()
-        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
-    let mut path = util::glob_match_file(safe_path_join(&config.server.upload_path, &*file)?)?;
+    let start = Instant::now();
+    let mut server_timing = ServerTiming::default();
+    // the config is not locked while waiting for the file to be written
+    let config = util::read_config(&config)?.clone();
+    check_upload_path(&config.server)?;
+    let file = match config.server.get_url_path_prefix() {
+        Some(prefix) => file
+            .strip_prefix(prefix)
+            .and_then(|v| v.strip_prefix('/'))
+            .unwrap_or(file.as_str())
+            .to_string(),
+        None => file.into_inner(),
+    };
+    let trimmed_file = file.trim_end_matches('/');
+    let file = if trimmed_file.is_empty() || trimmed_file == file {
+        file
+    } else {
+        match config.server.trailing_slash.unwrap_or_default() {
+            // the trailing slash would be dropped while joining the path
+            TrailingSlash::Strict => {
+                return Err(error::ErrorNotFound("file is not found or expired :(\n"));
+            }
+            TrailingSlash::Redirect => {
+                let mut location = request.path().trim_end_matches('/').to_string();
+                if !request.query_string().is_empty() {
+                    location = format!("{location}?{}", request.query_string());
+                }
+                return Ok(HttpResponse::MovedPermanently()
+                    .append_header(("Location", location))
+                    .finish());
+            }
+            TrailingSlash::Trim => trimmed_file.to_string(),
+        }
+    };
+    let max_path_depth = config
+        .server
+        .max_path_depth
+        .unwrap_or(DEFAULT_MAX_PATH_DEPTH);
+    if file.split('/').filter(|v| !v.is_empty()).count() > max_path_depth {
+        return Err(error::ErrorBadRequest("path is too deep\n"));
+    }
+    if paste::is_metadata_path(Path::new(&file)) {
+        return Err(error::ErrorNotFound("file is not found or expired :(\n"));
+    }
+    check_read_access(&request, &config, &file)?;
+    if let Some(secret) = &config.server.signing_secret {
+        if auth::has_signature(request.query_string()) {
+            auth::verify_signature(
+                secret,
+                &file,
+                request.query_string(),
+                util::get_system_time()?,
+            )?;
+        }
+    }
+    let file_name = file.rsplit('/').next().unwrap_or_default().to_string();
+    check_blocked_extension(&config, &file_name)?;
+    let mut path = find_upload_file(&config, &file)?;
     let mut paste_type = PasteType::File;
     if !path.exists() || path.is_dir() {
         for type_ in &[PasteType::Url, PasteType::Oneshot, PasteType::OneshotUrl] {
@@ -105,126 +424,1621 @@ async fn serve(
         }
     }
     if !path.is_file() || !path.exists() {
-        return Err(error::ErrorNotFound("file is not found or expired :(\n"));
+        return Err(not_found_error(&config, &file)?);
+    }
+    if paste::is_being_written(&path) {
+        let timeout = config.server.write_wait_timeout.unwrap_or_default();
+        wait_for_write(&path, timeout).await?;
+    }
+    server_timing.add("lookup", start.elapsed());
+    let cache_control = config
+        .cache
+        .as_ref()
+        .filter(|v| v.enabled)
+        .map(|cache_config| get_cache_control(cache_config, paste_type, &path))
+        .transpose()?;
+    let expiry_date = get_expiry_date(&path);
+    let creation_date = fs::metadata(&path)
+        .and_then(|v| v.created().or_else(|_| v.modified()))
+        .ok();
+    let (download, render, view, lines) = options
+        .map(|v| {
+            let v = v.into_inner();
+            (v.download, v.render, v.view, v.lines)
+        })
+        .unwrap_or_default();
+    let line_range = lines.as_deref().map(parse_line_range).transpose()?;
+    let download = download || is_forced_download(&config, &file_name);
+    let view = (view || line_range.is_some()) && !download;
+    let is_markdown = Path::new(&file_name)
+        .extension()
+        .and_then(|v| v.to_str())
+        .is_some_and(|v| v.eq_ignore_ascii_case("md") || v.eq_ignore_ascii_case("markdown"));
+    let render = render && is_markdown && !download;
+    let is_file = matches!(
+        paste_type,
+        PasteType::File | PasteType::RemoteFile | PasteType::Oneshot
+    );
+    if (render || view) && is_file {
+        check_renderable_file(&config, &file, &path)?;
+    }
+    // the file is renamed before serving so that only one of the concurrent requests succeeds
+    if paste_type == PasteType::Oneshot || paste_type == PasteType::OneshotUrl {
+        path = consume_oneshot(&path, &file_name)?;
     }
-    match paste_type {
+    if paste_type == PasteType::File {
+        let metadata_path = safe_path_join(&config.server.upload_path, &*file)?;
+        if let Some(metadata_dir) = metadata_path.parent() {
+            path = record_transfer(&path, metadata_dir, &file_name)?;
+        }
+    }
+    let mut response = match paste_type {
+        PasteType::File | PasteType::RemoteFile | PasteType::Oneshot if render => {
+            HttpResponse::Ok()
+                .content_type(mime::TEXT_HTML_UTF_8)
+                .body(util::render_markdown(&String::from_utf8_lossy(&fs::read(
+                    &path,
+                )?)))
+        }
+        PasteType::File | PasteType::RemoteFile | PasteType::Oneshot if view => HttpResponse::Ok()
+            .content_type(mime::TEXT_HTML_UTF_8)
+            .body(render_lines(
+                &file_name,
+                &String::from_utf8_lossy(&fs::read(&path)?),
+                line_range,
+            )),
         PasteType::File | PasteType::RemoteFile | PasteType::Oneshot => {
-            let mime_type = if options.map(|v| v.download).unwrap_or(false) {
+            let mut charset = None;
+            let mime_type = if download {
                 mime::APPLICATION_OCTET_STREAM
             } else {
-                mime_util::get_mime_type(&config.paste.mime_override, file.to_string())
-                    .map_err(error::ErrorInternalServerError)?
+                let mime_type = match mime_util::find_mime_type(
+                    &config.paste.mime_override,
+                    &config.paste.mime_by_extension,
+                    file.to_string(),
+                )
+                .map_err(error::ErrorInternalServerError)?
+                {
+                    Some(mime_type) => mime_type,
+                    None => match &config.paste.default_mime {
+                        // the default is not used for the known binary files
+                        Some(default_mime) if util::infer_file_type(&path)?.is_none() => {
+                            default_mime
+                                .parse::<mime::Mime>()
+                                .map_err(error::ErrorInternalServerError)?
+                        }
+                        _ => mime::APPLICATION_OCTET_STREAM,
+                    },
+                };
+                // not the parent of `path` since the file might be in a fan-out directory
+                let charset_path =
+                    safe_path_join(paste_type.get_path(&config.server.upload_path)?, &*file)?;
+                charset = charset_path
+                    .parent()
+                    .and_then(|dir| paste::get_charset(dir, &file_name))
+                    .filter(|_| mime_type.type_() == mime::TEXT);
+                match &charset {
+                    Some(charset) => format!(
+                        "{}/{}; charset={}",
+                        mime_type.type_(),
+                        mime_type.subtype(),
+                        charset
+                    )
+                    .parse::<mime::Mime>()
+                    .map_err(error::ErrorInternalServerError)?,
+                    None => mime_type,
+                }
             };
-            let response = NamedFile::open(&path)?
-                .disable_content_disposition()
-                .set_content_type(mime_type)
-                .prefer_utf8(true)
-                .into_response(&request);
-            if paste_type.is_oneshot() {
-                fs::rename(
-                    &path,
-                    path.with_file_name(format!(
-                        "{}.{}",
-                        file,
-                        util::get_system_time()?.as_millis()
-                    )),
-                )?;
+            let size = fs::metadata(&path)?.len();
+            let ranges = request
+                .headers()
+                .get(RANGE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| HttpRange::parse(v, size).ok())
+                .filter(|v| v.len() > 1);
+            let algorithm = config.compression.as_ref().and_then(|compression_config| {
+                let accept_encoding = request
+                    .headers()
+                    .get(ACCEPT_ENCODING)
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or_default();
+                compression_config
+                    .get_algorithm(accept_encoding, size, &mime_type)
+                    .map(|algorithm| {
+                        let level = compression_config
+                            .level
+                            .unwrap_or(compression::DEFAULT_LEVEL);
+                        (algorithm, level)
+                    })
+            });
+            let mut response = match ranges {
+                Some(ranges) => get_byte_ranges_response(&request, &path, &mime_type, &ranges)?,
+                None => NamedFile::open(&path)?
+                    .disable_content_disposition()
+                    .set_content_type(mime_type)
+                    .prefer_utf8(charset.is_none())
+                    .into_response(&request),
+            };
+            response
+                .headers_mut()
+                .insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+            if let Some((algorithm, level)) =
+                algorithm.filter(|_| response.status() == StatusCode::OK)
+            {
+                let headers = response.headers_mut();
+                headers.insert(
+                    CONTENT_ENCODING,
+                    HeaderValue::from_static(algorithm.encoding()),
+                );
+                headers.insert(VARY, HeaderValue::from_static("accept-encoding"));
+                headers.remove(ACCEPT_RANGES);
+                headers.remove(CONTENT_LENGTH);
+                if let Some(etag) = headers
+                    .get(ETAG)
+                    .and_then(|v| v.to_str().ok())
+                    .filter(|v| !v.starts_with("W/"))
+                    .map(|v| format!("W/{v}"))
+                {
+                    headers.insert(ETAG, HeaderValue::from_str(&etag)?);
+                }
+                // the file is compressed while it is streamed
+                let (response_head, mut body) = response.into_parts();
+                let body = stream::poll_fn(move |cx| Pin::new(&mut body).poll_next(cx))
+                    .map(|chunk| chunk.map_err(|e| IoError::other(e.to_string())));
+                response = response_head
+                    .set_body(BodyStream::new(compression::compress_stream(
+                        body, algorithm, level,
+                    )))
+                    .map_into_boxed_body();
+            }
+            if download {
+                response.headers_mut().insert(
+                    CONTENT_DISPOSITION,
+                    header::get_content_disposition(DispositionType::Attachment, &file_name)?,
+                );
             }
-            Ok(response)
+            response
         }
-        PasteType::Url => Ok(HttpResponse::Found()
+        PasteType::Url | PasteType::OneshotUrl => HttpResponse::Found()
             .append_header(("Location", fs::read_to_string(&path)?))
-            .finish()),
-        PasteType::OneshotUrl => {
-            let resp = HttpResponse::Found()
-                .append_header(("Location", fs::read_to_string(&path)?))
-                .finish();
-            fs::rename(
-                &path,
-                path.with_file_name(format!("{}.{}", file, util::get_system_time()?.as_millis())),
+            .finish(),
+    };
+    if let Some(expiry_date) = expiry_date {
+        response.headers_mut().insert(
+            EXPIRES,
+            HeaderValue::from_str(
+                &HttpDate::from(UNIX_EPOCH + Duration::from_millis(expiry_date)).to_string(),
+            )?,
+        );
+    }
+    if let Some(age) = creation_date.and_then(|v| SystemTime::now().duration_since(v).ok()) {
+        response
+            .headers_mut()
+            .insert(AGE, HeaderValue::from(age.as_secs()));
+    }
+    if let Some(cache_control) = cache_control {
+        response
+            .headers_mut()
+            .insert(CACHE_CONTROL, HeaderValue::from_str(&cache_control)?);
+    }
+    server_timing.add("total", start.elapsed());
+    server_timing.insert_into(response.headers_mut())?;
+    Ok(response)
+}
+
+/// Interval for checking if a file is still being written.
+const WRITE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Waits until the file at the given path is written.
+///
+/// Returns conflict (`409`) error if the file is still being written after the timeout.
+async fn wait_for_write(path: &Path, timeout: Duration) -> Result<(), Error> {
+    let start = Instant::now();
+    while paste::is_being_written(path) {
+        if start.elapsed() >= timeout {
+            return Err(error::ErrorConflict("file is being written\n"));
+        }
+        actix_web::rt::time::sleep(WRITE_POLL_INTERVAL).await;
+    }
+    Ok(())
+}
+
+/// Checks if the given file can be read with the token of the request.
+///
+/// The file path is checked after it is decoded and the [`url_path_prefix`] is stripped.
+/// Returns unauthorized (`401`) error if one of the [`read_paths`] requires a token.
+/// Signed URLs are verified separately.
+///
+/// [`url_path_prefix`]: crate::config::ServerConfig::url_path_prefix
+/// [`read_paths`]: crate::config::AuthConfig::read_paths
+fn check_read_access(request: &HttpRequest, config: &Config, file: &str) -> Result<(), Error> {
+    let auth_config = config.server.auth.clone().unwrap_or_default();
+    let is_signed =
+        config.server.signing_secret.is_some() && auth::has_signature(request.query_string());
+    if auth_config.is_read_required(file)
+        && !is_signed
+        && !auth::is_authorized(request.headers(), config)
+    {
+        return Err(error::ErrorUnauthorized("unauthorized\n"));
+    }
+    Ok(())
+}
+
+/// Checks if serving the given file is blocked by its extension.
+///
+/// Returns forbidden (`403`) error if the extension is in [`serve_blocked_extensions`].
+///
+/// [`serve_blocked_extensions`]: crate::config::PasteConfig::serve_blocked_extensions
+fn check_blocked_extension(config: &Config, file_name: &str) -> Result<(), Error> {
+    let extension = Path::new(file_name)
+        .extension()
+        .and_then(|v| v.to_str())
+        .unwrap_or_default();
+    if !extension.is_empty()
+        && config
+            .paste
+            .serve_blocked_extensions
+            .iter()
+            .any(|v| v.trim_start_matches('.').eq_ignore_ascii_case(extension))
+    {
+        return Err(error::ErrorForbidden("this file type is not served\n"));
+    }
+    Ok(())
+}
+
+/// Returns the error for the files that cannot be found.
+///
+/// If [`gone_for_expired`] is set, gone (`410`) error is returned for the expired files.
+///
+/// [`gone_for_expired`]: crate::config::PasteConfig::gone_for_expired
+fn not_found_error(config: &Config, file: &str) -> Result<Error, Error> {
+    if config.paste.gone_for_expired.unwrap_or(false) {
+        for type_ in &[
+            PasteType::File,
+            PasteType::Url,
+            PasteType::Oneshot,
+            PasteType::OneshotUrl,
+        ] {
+            let path = safe_path_join(type_.get_path(&config.server.upload_path)?, file)?;
+            if util::has_expired_file(&path)? {
+                return Ok(error::ErrorGone("this file has expired\n"));
+            }
+        }
+        if util::has_expired_fanout_file(&config.server.upload_path, file)? {
+            return Ok(error::ErrorGone("this file has expired\n"));
+        }
+    }
+    Ok(error::ErrorNotFound("file is not found or expired :(\n"))
+}
+
+/// Checks if the given file should be served as an attachment regardless of the request.
+///
+/// See [`force_download`].
+///
+/// [`force_download`]: crate::config::PasteConfig::force_download
+fn is_forced_download(config: &Config, file_name: &str) -> bool {
+    if config.paste.force_download.is_empty() {
+        return false;
+    }
+    let extension = Path::new(file_name)
+        .extension()
+        .and_then(|v| v.to_str())
+        .unwrap_or_default();
+    let mime_type = mime_util::get_mime_type(
+        &config.paste.mime_override,
+        &config.paste.mime_by_extension,
+        file_name.to_string(),
+    )
+    .ok();
+    config.paste.force_download.iter().any(|v| {
+        let v = v.trim_start_matches('.');
+        (!extension.is_empty() && v.eq_ignore_ascii_case(extension))
+            || mime_type
+                .as_ref()
+                .is_some_and(|mime_type| v.eq_ignore_ascii_case(mime_type.essence_str()))
+    })
+}
+
+/// Returns the expiry date (in milliseconds) in the timestamp extension of the given path.
+fn get_expiry_date(path: &Path) -> Option<u64> {
+    path.extension()
+        .and_then(|v| v.to_str())
+        .filter(|_| util::TIMESTAMP_EXTENSION_REGEX.is_match(&path.to_string_lossy()))
+        .and_then(|v| v.parse::<u64>().ok())
+}
+
+/// Default maximum time for caching the files.
+const DEFAULT_CACHE_MAX_AGE: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// Returns the value of the `Cache-Control` header for the given file.
+///
+/// - Oneshot files and URLs are never cached.
+/// - Files with an expiry date are cached until they expire.
+/// - Other files are cached for [`max_age`] and marked as [`immutable`].
+///
+/// [`max_age`]: crate::config::CacheConfig::max_age
+/// [`immutable`]: crate::config::CacheConfig::immutable
+fn get_cache_control(
+    cache_config: &CacheConfig,
+    paste_type: PasteType,
+    path: &Path,
+) -> Result<String, Error> {
+    if paste_type != PasteType::File && paste_type != PasteType::RemoteFile {
+        return Ok(String::from("no-store"));
+    }
+    let max_age = cache_config.max_age.unwrap_or(DEFAULT_CACHE_MAX_AGE);
+    match get_expiry_date(path) {
+        Some(expiry_date) => {
+            let expires_in =
+                Duration::from_millis(expiry_date).saturating_sub(util::get_system_time()?);
+            Ok(format!(
+                "public, max-age={}",
+                expires_in.min(max_age).as_secs()
+            ))
+        }
+        None if cache_config.immutable.unwrap_or(true) => {
+            Ok(format!("public, max-age={}, immutable", max_age.as_secs()))
+        }
+        None => Ok(format!("public, max-age={}", max_age.as_secs())),
+    }
+}
+
+/// Returns the path of the given file in the upload directory.
+///
+/// If [`fanout_depth`] is set, the file is also searched in the fan-out directories.
+///
+/// [`fanout_depth`]: crate::config::PasteConfig::fanout_depth
+fn find_upload_file(config: &Config, file: &str) -> Result<PathBuf, Error> {
+    let path = util::glob_match_file(safe_path_join(&config.server.upload_path, file)?)?;
+    if config.paste.fanout_depth.is_some_and(|v| v != 0) && !path.exists() {
+        if let Some(fanout_path) = util::find_fanout_file(&config.server.upload_path, file)? {
+            return Ok(fanout_path);
+        }
+    }
+    Ok(path)
+}
+
+/// Marks the oneshot file as expired by renaming it and returns the new path.
+///
+/// Returns not found (`404`) error if the file is already consumed by another request.
+fn consume_oneshot(path: &Path, file_name: &str) -> Result<PathBuf, Error> {
+    let expired_path = path.with_file_name(format!(
+        "{}.{}",
+        file_name,
+        util::get_system_time()?.as_millis()
+    ));
+    match fs::rename(path, &expired_path) {
+        Ok(_) => Ok(expired_path),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Err(error::ErrorNotFound("file is not found or expired :(\n"))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Records the transfer of the file if it has a transfer limit and returns the path to serve.
+///
+/// The file is expired after its last allowed transfer (see [`max_transfer`]).
+///
+/// [`max_transfer`]: crate::config::PasteConfig::max_transfer
+fn record_transfer(path: &Path, metadata_dir: &Path, file_name: &str) -> Result<PathBuf, Error> {
+    match paste::record_transfer(metadata_dir, file_name, fs::metadata(path)?.len())? {
+        Some(Transfer::Exceeded) => {
+            consume_oneshot(path, file_name)?;
+            Err(error::ErrorNotFound("file is not found or expired :(\n"))
+        }
+        Some(Transfer::Last) => consume_oneshot(path, file_name),
+        Some(Transfer::Allowed) | None => Ok(path.to_path_buf()),
+    }
+}
+
+/// Maximum number of the byte ranges that are served in a `multipart/byteranges` response.
+///
+/// The whole file is served if more ranges are requested.
+const MAX_BYTE_RANGES: usize = 16;
+
+/// Size of the chunks that are read while streaming the byte ranges.
+const BYTE_RANGE_CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Returns the given ranges sorted and with the overlapping or adjacent ones merged.
+fn merge_byte_ranges(ranges: &[HttpRange]) -> Vec<HttpRange> {
+    let mut ranges = ranges.to_vec();
+    ranges.sort_by_key(|v| v.start);
+    let mut merged: Vec<HttpRange> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.start + last.length => {
+                last.length = last.length.max(range.start + range.length - last.start);
+            }
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Returns a stream that reads the given range of the file chunk by chunk.
+///
+/// The file is read on the blocking thread pool.
+fn read_byte_range(
+    file: fs::File,
+    range: HttpRange,
+) -> impl Stream<Item = Result<web::Bytes, IoError>> {
+    stream::try_unfold(
+        (Some(file), range.start, range.length),
+        |(file, offset, remaining)| async move {
+            let Some(mut file) = file.filter(|_| remaining != 0) else {
+                return Ok(None);
+            };
+            let chunk_size = remaining.min(BYTE_RANGE_CHUNK_SIZE);
+            let (file, chunk) = web::block(move || {
+                let mut chunk = vec![0; usize::try_from(chunk_size).map_err(IoError::other)?];
+                file.seek(SeekFrom::Start(offset))?;
+                file.read_exact(&mut chunk)?;
+                Ok::<_, IoError>((file, web::Bytes::from(chunk)))
+            })
+            .await
+            .map_err(IoError::other)??;
+            Ok(Some((
+                chunk,
+                (Some(file), offset + chunk_size, remaining - chunk_size),
+            )))
+        },
+    )
+}
+
+/// Returns a response that contains the given ranges of the file.
+///
+/// The overlapping ranges are merged and the parts of the `multipart/byteranges` response
+/// are streamed. The whole file is served if more than [`MAX_BYTE_RANGES`] are requested.
+fn get_byte_ranges_response(
+    request: &HttpRequest,
+    path: &Path,
+    content_type: &mime::Mime,
+    ranges: &[HttpRange],
+) -> Result<HttpResponse, Error> {
+    let file = fs::File::open(path)?;
+    let size = file.metadata()?.len();
+    if ranges.len() > MAX_BYTE_RANGES {
+        let mut response = NamedFile::from_file(file.try_clone()?, path)?
+            .disable_content_disposition()
+            .set_content_type(content_type.clone())
+            .into_response(request);
+        if response.status() != StatusCode::PARTIAL_CONTENT {
+            return Ok(response);
+        }
+        *response.status_mut() = StatusCode::OK;
+        let headers = response.headers_mut();
+        headers.remove(CONTENT_RANGE);
+        headers.remove(CONTENT_ENCODING);
+        return Ok(response
+            .set_body(SizedStream::new(
+                size,
+                read_byte_range(
+                    file,
+                    HttpRange {
+                        start: 0,
+                        length: size,
+                    },
+                ),
+            ))
+            .map_into_boxed_body());
+    }
+    let ranges = merge_byte_ranges(ranges);
+    if let [range] = ranges.as_slice() {
+        return Ok(HttpResponse::PartialContent()
+            .content_type(content_type.clone())
+            .insert_header((
+                CONTENT_RANGE,
+                format!(
+                    "bytes {}-{}/{size}",
+                    range.start,
+                    range.start + range.length - 1
+                ),
+            ))
+            .body(SizedStream::new(
+                range.length,
+                read_byte_range(file, *range),
+            )));
+    }
+    let boundary = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect::<String>();
+    let closing_boundary = format!("--{boundary}--\r\n");
+    let mut parts = Vec::with_capacity(ranges.len());
+    let mut length = closing_boundary.len() as u64;
+    for range in ranges {
+        let part_header = format!(
+            "--{boundary}\r\nContent-Type: {content_type}\r\nContent-Range: bytes {}-{}/{size}\r\n\r\n",
+            range.start,
+            range.start + range.length - 1
+        );
+        length += part_header.len() as u64 + range.length + 2;
+        parts.push((part_header, file.try_clone()?, range));
+    }
+    let body = stream::iter(parts)
+        .flat_map(|(part_header, file, range)| {
+            stream::once(future::ok(web::Bytes::from(part_header)))
+                .chain(read_byte_range(file, range))
+                .chain(stream::once(future::ok(web::Bytes::from_static(b"\r\n"))))
+        })
+        .chain(stream::once(future::ok(web::Bytes::from(closing_boundary))));
+    Ok(HttpResponse::PartialContent()
+        .content_type(format!("multipart/byteranges; boundary={boundary}"))
+        .body(SizedStream::new(length, body)))
+}
+
+/// Delete options (i.e. query parameters).
+#[derive(Debug, Deserialize)]
+struct DeleteOptions {
+    /// Treat the file name as a glob pattern.
+    #[serde(default)]
+    glob: bool,
+}
+
+/// Serves the stored bytes of a file as `application/octet-stream`.
+///
+/// Unlike [`serve`], the media type is not detected, the file is not rendered or compressed
+/// and the oneshot files/URLs are not consumed.
+#[get("/{file}/raw")]
+#[actix_web_grants::protect("EndpointGroup::Read", ty = EndpointGroup, error = unauthorized_error)]
+async fn serve_raw(
+    request: HttpRequest,
+    file: web::Path<String>,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
+    let config = util::read_config(&config)?.clone();
+    check_upload_path(&config.server)?;
+    if paste::is_metadata_path(Path::new(&*file)) {
+        return Err(error::ErrorNotFound("file is not found or expired :(\n"));
+    }
+    check_read_access(&request, &config, &file)?;
+    if let Some(secret) = &config.server.signing_secret {
+        if auth::has_signature(request.query_string()) {
+            auth::verify_signature(
+                secret,
+                &file,
+                request.query_string(),
+                util::get_system_time()?,
             )?;
-            Ok(resp)
         }
     }
+    check_blocked_extension(&config, &file)?;
+    let mut path = find_upload_file(&config, &file)?;
+    let is_file_paste = path.is_file();
+    if !is_file_paste {
+        for type_ in &[PasteType::Url, PasteType::Oneshot, PasteType::OneshotUrl] {
+            let alt_path = safe_path_join(type_.get_path(&config.server.upload_path)?, &*file)?;
+            let alt_path = util::glob_match_file(alt_path)?;
+            if alt_path.is_file() {
+                path = alt_path;
+                break;
+            }
+        }
+    }
+    if !path.is_file() {
+        return Err(not_found_error(&config, &file)?);
+    }
+    if paste::is_being_written(&path) {
+        wait_for_write(&path, config.server.write_wait_timeout.unwrap_or_default()).await?;
+    }
+    if is_file_paste {
+        let metadata_path = safe_path_join(&config.server.upload_path, &*file)?;
+        if let Some(metadata_dir) = metadata_path.parent() {
+            path = record_transfer(&path, metadata_dir, &file)?;
+        }
+    }
+    let mut response = NamedFile::open(&path)?
+        .disable_content_disposition()
+        .set_content_type(mime::APPLICATION_OCTET_STREAM)
+        .prefer_utf8(false)
+        .into_response(&request);
+    response
+        .headers_mut()
+        .insert(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    Ok(response)
+}
+
+/// Removes the files that match the given glob pattern from the upload directory.
+///
+/// Only the stored files are matched, i.e. the oneshot files, URLs and the files that are
+/// maintained by the server (e.g. the manifest) are never removed.
+///
+/// Responds with the count and the names of the removed files.
+fn delete_matching_files(pattern: &str, config: &Config) -> Result<HttpResponse, Error> {
+    if pattern.contains(['/', '\\']) {
+        return Err(error::ErrorBadRequest("invalid glob pattern\n"));
+    }
+    let pattern = glob::Pattern::new(pattern)
+        .map_err(|_| error::ErrorBadRequest("invalid glob pattern\n"))?;
+    let other_paths = [PasteType::Oneshot, PasteType::Url, PasteType::OneshotUrl]
+        .iter()
+        .map(|v| v.get_path(&config.server.upload_path))
+        .collect::<Result<Vec<PathBuf>, _>>()?;
+    let mut file_names = Vec::new();
+    for path in util::get_stored_files(&config.server.upload_path)
+        .into_iter()
+        .filter(|path| !other_paths.iter().any(|v| path.starts_with(v)))
+        .filter(|path| !is_server_file(config, path))
+    {
+        let file_name = path
+            .file_name()
+            .map(|v| v.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if !pattern.matches(&file_name) {
+            continue;
+        }
+        match paste::remove_file(&config.server.upload_path, &path) {
+            Ok(_) => info!(
+                "deleted file: {:?}",
+                util::get_log_file_name(&file_name, &config.server)
+            ),
+            Err(e) => {
+                error!("cannot delete file: {}", e);
+                return Err(error::ErrorInternalServerError("cannot delete file"));
+            }
+        }
+        file_names.push(file_name);
+    }
+    file_names.sort();
+    if file_names.is_empty() {
+        return Err(error::ErrorNotFound("file is not found or expired :(\n"));
+    }
+    update_manifest(config);
+    Ok(HttpResponse::Ok().body(format!(
+        "{} file(s) deleted\n{}",
+        file_names.len(),
+        file_names
+            .iter()
+            .map(|v| format!("{v}\n"))
+            .collect::<String>()
+    )))
+}
+
+/// Returns the checksum of the file that its deletion token is bound to.
+fn get_deletion_checksum(path: &Path) -> Result<String, Error> {
+    util::sha256_digest(fs::File::open(path)?)
 }
 
 /// Remove a file from the upload directory.
+///
+/// If `?glob=true` is set, all the files that match the given pattern are removed.
+/// If `?token=` is set, the deletion token of the file is verified instead of the delete tokens.
 #[delete("/{file}")]
-#[actix_web_grants::protect("TokenType::Delete", ty = TokenType, error = unauthorized_error)]
 async fn delete(
+    request: HttpRequest,
     file: web::Path<String>,
+    options: Option<web::Query<DeleteOptions>>,
+    auth_details: AuthDetails<EndpointGroup>,
     config: web::Data<RwLock<Config>>,
 ) -> Result<HttpResponse, Error> {
-    let config = config
-        .read()
-        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
-    let path = util::glob_match_file(safe_path_join(&config.server.upload_path, &*file)?)?;
+    let config = util::read_config(&config)?;
+    let token = auth::get_deletion_token(request.query_string());
+    // the deletion token is only valid for the given file, see below
+    if token.is_none() && !auth_details.has_authority(&EndpointGroup::Delete) {
+        return Err(error::ErrorUnauthorized("unauthorized\n"));
+    }
+    if token.is_none() && options.is_some_and(|v| v.glob) {
+        return delete_matching_files(&file, &config);
+    }
+    let path = find_upload_file(&config, &file)?;
+    if let Some(token) = token {
+        let secret = config
+            .server
+            .delete_url_secret
+            .as_ref()
+            .ok_or_else(|| error::ErrorForbidden("invalid token\n"))?;
+        let checksum = if path.is_file() {
+            get_deletion_checksum(&path)?
+        } else {
+            String::new()
+        };
+        auth::verify_deletion_token(secret, &file, &checksum, &token)?;
+    }
     if !path.is_file() || !path.exists() {
         return Err(error::ErrorNotFound("file is not found or expired :(\n"));
     }
-    match fs::remove_file(path) {
-        Ok(_) => info!("deleted file: {:?}", file.to_string()),
+    match paste::remove_file(&config.server.upload_path, &path) {
+        Ok(_) => info!(
+            "deleted file: {:?} ({})",
+            util::get_log_file_name(&file, &config.server),
+            header::get_log_identity(&request, &config.server)
+        ),
         Err(e) => {
             error!("cannot delete file: {}", e);
             return Err(error::ErrorInternalServerError("cannot delete file"));
         }
     }
+    update_manifest(&config);
     Ok(HttpResponse::Ok().body(String::from("file deleted\n")))
 }
 
-/// Expose version endpoint
-#[get("/version")]
-#[actix_web_grants::protect("TokenType::Auth", ty = TokenType, error = unauthorized_error)]
-async fn version(config: web::Data<RwLock<Config>>) -> Result<HttpResponse, Error> {
-    let config = config
-        .read()
-        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
-    if !config.server.expose_version.unwrap_or(false) {
-        warn!("server is not configured to expose version endpoint");
-        Err(error::ErrorNotFound(""))?;
-    }
+/// Purge options (i.e. query parameters).
+#[derive(Debug, Deserialize)]
+struct PurgeOptions {
+    /// Remove the files that are last modified before this duration.
+    #[serde(default, with = "humantime_serde")]
+    older_than: Option<Duration>,
+    /// Remove the files that are larger than this size.
+    larger_than: Option<Byte>,
+    /// Only list the matching files without removing them.
+    #[serde(default)]
+    dry_run: bool,
+}
 
-    let version = env!("CARGO_PKG_VERSION");
-    Ok(HttpResponse::Ok().body(version.to_owned() + "\n"))
+/// Removes the stored files that match all of the given criteria.
+///
+/// All the upload locations (including the oneshot files and URLs) are scanned.
+/// Responds with the count and the paths of the matched files relative to the upload directory.
+/// Requires one of the delete tokens, deletion URL tokens are not accepted.
+#[delete("/purge")]
+#[actix_web_grants::protect("EndpointGroup::Delete", ty = EndpointGroup, error = unauthorized_error)]
+async fn purge(
+    request: HttpRequest,
+    options: web::Query<PurgeOptions>,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
+    // deletion tokens are only valid for a single file
+    if auth::get_deletion_token(request.query_string()).is_some() {
+        return Err(error::ErrorUnauthorized("unauthorized\n"));
+    }
+    let config = util::read_config(&config)?;
+    if options.older_than.is_none() && options.larger_than.is_none() {
+        return Err(error::ErrorBadRequest("no criteria is given\n"));
+    }
+    let now = SystemTime::now();
+    let mut file_names = Vec::new();
+    for path in util::get_stored_files(&config.server.upload_path) {
+        let metadata = match fs::metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        if let Some(older_than) = options.older_than {
+            let age = metadata
+                .modified()
+                .ok()
+                .and_then(|v| now.duration_since(v).ok());
+            if age.is_none_or(|age| age <= older_than) {
+                continue;
+            }
+        }
+        if let Some(larger_than) = options.larger_than {
+            if metadata.len() <= larger_than.as_u64() {
+                continue;
+            }
+        }
+        let file_name = path
+            .strip_prefix(&config.server.upload_path)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .to_string();
+        if !options.dry_run {
+            match paste::remove_file(&config.server.upload_path, &path) {
+                Ok(_) => info!(
+                    "purged file: {:?} ({})",
+                    util::get_log_file_name(&file_name, &config.server),
+                    header::get_log_identity(&request, &config.server)
+                ),
+                Err(e) => {
+                    error!("cannot purge file: {}", e);
+                    return Err(error::ErrorInternalServerError("cannot purge file"));
+                }
+            }
+        }
+        file_names.push(file_name);
+    }
+    file_names.sort();
+    Ok(HttpResponse::Ok().body(format!(
+        "{} file(s) {}\n{}",
+        file_names.len(),
+        if options.dry_run { "matched" } else { "purged" },
+        file_names
+            .iter()
+            .map(|v| format!("{v}\n"))
+            .collect::<String>()
+    )))
 }
 
-/// Handles file upload by processing `multipart/form-data`.
-#[post("/")]
-#[actix_web_grants::protect("TokenType::Auth", ty = TokenType, error = unauthorized_error)]
-async fn upload(
+/// Extends the expiry of an existing file.
+///
+/// The new expiry time is read from the `expire` header or the request body.
+/// Shortening the expiry (including setting one for a file that does not expire) requires
+/// a delete token.
+#[post("/{file}/extend")]
+#[actix_web_grants::protect("EndpointGroup::Write", ty = EndpointGroup, error = unauthorized_error)]
+async fn extend(
     request: HttpRequest,
-    mut payload: Multipart,
-    client: web::Data<Client>,
+    file: web::Path<String>,
+    body: String,
+    auth_details: AuthDetails<EndpointGroup>,
     config: web::Data<RwLock<Config>>,
 ) -> Result<HttpResponse, Error> {
-    let connection = request.connection_info().clone();
-    let host = connection.realip_remote_addr().unwrap_or("unknown host");
-    let server_url = match config
-        .read()
-        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
-        .server
-        .url
-        .clone()
+    let config = util::read_config(&config)?;
+    check_upload_path(&config.server)?;
+    let expire_time = match request
+        .headers()
+        .get(header::EXPIRE)
+        .and_then(|v| v.to_str().ok())
     {
-        Some(v) => v,
-        None => {
-            format!("{}://{}", connection.scheme(), connection.host(),)
-        }
+        Some(expire_time) => expire_time.to_string(),
+        None => body.trim().to_string(),
     };
+    if expire_time.is_empty() {
+        return Err(error::ErrorBadRequest("expiry time is not specified\n"));
+    }
+    let expire_time = humantime::parse_duration(&expire_time)
+        .map_err(|e| error::ErrorBadRequest(format!("invalid expiry time: {e}\n")))?;
+    if let Some(max_expiry) = config.paste.max_expiry {
+        if expire_time > max_expiry {
+            return Err(error::ErrorBadRequest(format!(
+                "expiry time cannot exceed {}\n",
+                humantime::format_duration(max_expiry)
+            )));
+        }
+    }
+    let expiry_date = util::get_system_time()?
+        .checked_add(expire_time)
+        .map(|t| t.as_millis())
+        .ok_or_else(|| error::ErrorBadRequest("expiry time is too large\n"))?;
+    let path = find_upload_file(&config, &file)?;
+    if !path.is_file() || !path.exists() {
+        return Err(error::ErrorNotFound("file is not found or expired :(\n"));
+    }
+    let file_name = file.rsplit('/').next().unwrap_or_default();
+    let current_expiry_date = path
+        .file_name()
+        .and_then(|v| v.to_str())
+        .and_then(|v| v.strip_prefix(file_name))
+        .and_then(|v| v.strip_prefix('.'))
+        .and_then(|v| v.parse::<u128>().ok());
+    if current_expiry_date.is_none_or(|v| expiry_date < v)
+        && !auth_details.has_authority(&EndpointGroup::Delete)
+    {
+        warn!(
+            "{} tried to shorten the expiry of a file without a delete token",
+            header::get_log_identity(&request, &config.server)
+        );
+        return Err(error::ErrorForbidden("expiry time cannot be shortened\n"));
+    }
+    let new_path = path.with_file_name(format!("{file_name}.{expiry_date}"));
+    match fs::rename(&path, &new_path) {
+        Ok(_) => info!(
+            "extended the expiry of file: {:?}",
+            util::get_log_file_name(&file, &config.server)
+        ),
+        Err(e) => {
+            error!("cannot extend the expiry of file: {}", e);
+            return Err(error::ErrorInternalServerError("cannot extend file expiry"));
+        }
+    }
+    update_manifest(&config);
+    Ok(HttpResponse::Ok().body(String::from("file expiry extended\n")))
+}
+
+/// Imports the files that are placed into the [`IMPORT_DIR`] manually.
+///
+/// The files are moved into the upload directory and assigned the expiry from the `expire`
+/// header (or [`default_expiry`]). Their checksums are cached for detecting duplicates.
+/// The files that already exist in the upload directory are skipped.
+///
+/// Responds with the count and the names of the imported files.
+///
+/// [`IMPORT_DIR`]: crate::paste::IMPORT_DIR
+/// [`default_expiry`]: crate::config::PasteConfig::default_expiry
+#[post("/import")]
+#[actix_web_grants::protect("EndpointGroup::Delete", ty = EndpointGroup, error = unauthorized_error)]
+async fn import(
+    request: HttpRequest,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
+    let config = util::read_config(&config)?.clone();
+    check_upload_path(&config.server)?;
     let time = util::get_system_time()?;
-    let mut expiry_date = header::parse_expiry_date(request.headers(), time)?;
-    if expiry_date.is_none() {
-        expiry_date = config
-            .read()
-            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+    let expiry_date = match header::parse_expiry_date(request.headers(), time)? {
+        Some(expiry_date) => Some(expiry_date),
+        None => config
             .paste
             .default_expiry
-            .and_then(|v| time.checked_add(v).map(|t| t.as_millis()));
-    }
-    let mut urls: Vec<String> = Vec::new();
-    while let Some(item) = payload.next().await {
-        let header_filename = header::parse_header_filename(request.headers())?;
-        let mut field = item?;
+            .and_then(|v| time.checked_add(v).map(|t| t.as_millis())),
+    };
+    let max_expiry_date = config
+        .paste
+        .max_expiry
+        .and_then(|v| time.checked_add(v).map(|t| t.as_millis()));
+    let expiry_date = match (expiry_date, max_expiry_date) {
+        (Some(expiry_date), Some(max_expiry_date)) => Some(expiry_date.min(max_expiry_date)),
+        (expiry_date, _) => expiry_date,
+    };
+    let mut file_names = run_blocking(move || {
+        let import_dir = config.server.upload_path.join(paste::IMPORT_DIR);
+        let entries = match fs::read_dir(&import_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == IoErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                error!("cannot read the import directory: {}", e);
+                return Err(error::ErrorInternalServerError("cannot import files"));
+            }
+        };
+        let algorithm = config.paste.checksum_algorithm.unwrap_or_default();
+        let mut file_names = Vec::new();
+        for path in entries.filter_map(|v| v.ok()).map(|v| v.path()) {
+            if !path.is_file() {
+                continue;
+            }
+            let file_name = path
+                .file_name()
+                .map(|v| v.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let target = safe_path_join(&config.server.upload_path, &file_name)?;
+            if util::glob_match_file(target.clone())?.exists() {
+                warn!(
+                    "skipping the import of {:?}: file already exists",
+                    util::get_log_file_name(&file_name, &config.server)
+                );
+                continue;
+            }
+            let target = match expiry_date {
+                Some(expiry_date) => safe_path_join(
+                    &config.server.upload_path,
+                    format!("{file_name}.{expiry_date}"),
+                )?,
+                None => target,
+            };
+            if let Err(e) = fs::rename(&path, &target) {
+                error!("cannot import file: {}", e);
+                return Err(error::ErrorInternalServerError("cannot import file"));
+            }
+            let checksum = file::get_checksum(&target, algorithm).unwrap_or_default();
+            info!(
+                "imported file: {:?} ({})",
+                util::get_log_file_name(&file_name, &config.server),
+                checksum
+            );
+            file_names.push(file_name);
+        }
+        Ok(file_names)
+    })
+    .await?;
+    file_names.sort();
+    Ok(HttpResponse::Ok().body(format!(
+        "{} file(s) imported\n{}",
+        file_names.len(),
+        file_names
+            .iter()
+            .map(|v| format!("{v}\n"))
+            .collect::<String>()
+    )))
+}
+
+/// Build information for the version endpoint.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VersionInfo {
+    /// Version of the package.
+    pub version: String,
+    /// Git commit hash that the server is built from.
+    pub commit: String,
+    /// Date-time of the build.
+    pub build_date: String,
+}
+
+/// Expose version endpoint
+///
+/// Responds with the build information as JSON if `Accept: application/json` is set.
+#[get("/version")]
+#[actix_web_grants::protect("EndpointGroup::List", ty = EndpointGroup, error = unauthorized_error)]
+async fn version(
+    request: HttpRequest,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
+    let config = util::read_config(&config)?;
+    if !config.server.expose_version.unwrap_or(false) {
+        warn!("server is not configured to expose version endpoint");
+        Err(error::ErrorNotFound(""))?;
+    }
+
+    let version = env!("CARGO_PKG_VERSION");
+    let accepts_json = request
+        .headers()
+        .get(ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains(mime::APPLICATION_JSON.essence_str()));
+    if accepts_json {
+        return Ok(HttpResponse::Ok().json(VersionInfo {
+            version: version.to_string(),
+            commit: env!("BUILD_GIT_COMMIT").to_string(),
+            build_date: util::format_timestamp(
+                env!("BUILD_TIMESTAMP").parse().unwrap_or_default(),
+                None,
+            ),
+        }));
+    }
+    Ok(HttpResponse::Ok().body(version.to_owned() + "\n"))
+}
+
+/// Expose the effective configuration with the secrets redacted.
+#[get("/debug/config")]
+#[actix_web_grants::protect("EndpointGroup::List", ty = EndpointGroup, error = unauthorized_error)]
+async fn debug_config(config: web::Data<RwLock<Config>>) -> Result<HttpResponse, Error> {
+    let config = util::read_config(&config)?;
+    if !config.server.expose_config.unwrap_or(false) {
+        warn!("server is not configured to expose config endpoint");
+        Err(error::ErrorNotFound(""))?;
+    }
+    Ok(HttpResponse::Ok().json(config.redacted()))
+}
+
+/// Expose the OpenAPI description of the routes.
+#[get("/openapi.json")]
+#[actix_web_grants::protect("EndpointGroup::List", ty = EndpointGroup, error = unauthorized_error)]
+async fn openapi_spec(
+    request: HttpRequest,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
+    let config = util::read_config(&config)?;
+    if !config.server.expose_openapi.unwrap_or(false) {
+        warn!("server is not configured to expose OpenAPI endpoint");
+        Err(error::ErrorNotFound(""))?;
+    }
+    Ok(HttpResponse::Ok().json(openapi::get_spec(&get_server_url(&request, &config.server))))
+}
+
+/// Default contents of `/robots.txt`.
+const DEFAULT_ROBOTS_TXT: &str = "User-agent: *\nDisallow: /\n";
+
+/// Serve `/robots.txt` for keeping the uploads out of the search engines.
+#[get("/robots.txt")]
+async fn robots_txt(config: web::Data<RwLock<Config>>) -> Result<HttpResponse, Error> {
+    let config = util::read_config(&config)?;
+    Ok(HttpResponse::Ok().content_type(TEXT_PLAIN_UTF_8).body(
+        config
+            .server
+            .robots_txt
+            .clone()
+            .unwrap_or_else(|| DEFAULT_ROBOTS_TXT.to_string()),
+    ))
+}
+
+/// Default lifetime of the signed URLs.
+const DEFAULT_SIGNED_URL_EXPIRY: Duration = Duration::from_secs(60 * 60);
+
+/// Characters that are percent-encoded in the path segments of the signed URLs.
+const PATH_SEGMENT_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// URL signing options (i.e. query parameters).
+#[derive(Debug, Deserialize)]
+struct SignOptions {
+    /// Name of the file to sign.
+    file: String,
+    /// Lifetime of the signed URL.
+    #[serde(default, with = "humantime_serde")]
+    expire: Option<Duration>,
+}
+
+/// Creates a signed URL for accessing a file until it expires.
+#[get("/sign")]
+#[actix_web_grants::protect("EndpointGroup::Sign", ty = EndpointGroup, error = unauthorized_error)]
+async fn sign(
+    request: HttpRequest,
+    options: web::Query<SignOptions>,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
+    let config = util::read_config(&config)?;
+    let secret = match &config.server.signing_secret {
+        Some(secret) => secret,
+        None => {
+            warn!("server is not configured to sign URLs");
+            return Err(error::ErrorNotFound(""));
+        }
+    };
+    let file = options.file.trim_start_matches('/');
+    let expiration = util::get_system_time()?
+        .checked_add(options.expire.unwrap_or(DEFAULT_SIGNED_URL_EXPIRY))
+        .ok_or_else(|| error::ErrorBadRequest("invalid expiration\n"))?
+        .as_secs();
+    let signature = auth::sign_path(secret, file, expiration);
+    let encoded_file = file
+        .split('/')
+        .map(|segment| utf8_percent_encode(segment, PATH_SEGMENT_ENCODE_SET).to_string())
+        .collect::<Vec<String>>()
+        .join("/");
+    Ok(HttpResponse::Ok().body(format!(
+        "{}/{}?exp={}&sig={}\n",
+        get_server_url(&request, &config.server),
+        encoded_file,
+        expiration,
+        signature
+    )))
+}
+
+/// Returns the base URL of the server including the path prefix.
+fn get_server_url(request: &HttpRequest, server_config: &ServerConfig) -> String {
+    let server_url = match &server_config.url {
+        Some(v) => v.to_string(),
+        None => {
+            let connection = ClientConnection::from_request(request, server_config);
+            format!("{}://{}", connection.scheme, connection.host)
+        }
+    };
+    match server_config.get_url_path_prefix() {
+        Some(prefix) => format!("{}/{}", server_url.trim_end_matches('/'), prefix),
+        None => server_url,
+    }
+}
+
+/// Format of the upload response.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum ResponseFormat {
+    /// Each URL is followed by a single newline.
+    #[default]
+    Plain,
+    /// URLs are separated by newlines without a trailing newline.
+    UrlOnly,
+    /// Each URL is wrapped in a `curl` command for downloading the file.
+    Share,
+    /// Each URL is wrapped as a Markdown image or link.
+    Markdown,
+    /// Each URL is wrapped as an HTML image or link.
+    Html,
+    /// Each URL is wrapped as a BBCode image or link.
+    Bbcode,
+}
+
+impl ResponseFormat {
+    /// Formats the given URLs as the response body.
+    ///
+    /// The embedding formats use an image for the URLs of the images and a link for the others.
+    fn format_urls(&self, urls: &[String], config: &Config) -> String {
+        let is_image = |file_name: &str| {
+            mime_util::get_mime_type(
+                &config.paste.mime_override,
+                &config.paste.mime_by_extension,
+                file_name.to_string(),
+            )
+            .is_ok_and(|v| v.type_() == mime::IMAGE)
+        };
+        let embed = |url: &String| {
+            let file_name = url.rsplit('/').next().unwrap_or_default();
+            let image = is_image(file_name);
+            match (self, image) {
+                (Self::Markdown, true) => {
+                    format!("![{}]({url})\n", escape_markdown_text(file_name))
+                }
+                (Self::Markdown, false) => {
+                    format!("[{}]({url})\n", escape_markdown_text(file_name))
+                }
+                (Self::Html, true) => format!(
+                    "<img src=\"{}\" alt=\"{}\">\n",
+                    escape_html(url),
+                    escape_html(file_name)
+                ),
+                (Self::Html, false) => format!(
+                    "<a href=\"{}\">{}</a>\n",
+                    escape_html(url),
+                    escape_html(file_name)
+                ),
+                (_, true) => format!("[img]{url}[/img]\n"),
+                (_, false) => format!("[url]{url}[/url]\n"),
+            }
+        };
+        match self {
+            Self::Markdown | Self::Html | Self::Bbcode => urls.iter().map(embed).collect(),
+            Self::Plain => urls.iter().map(|url| format!("{url}\n")).collect(),
+            Self::UrlOnly => urls.join("\n"),
+            Self::Share => urls
+                .iter()
+                .map(|url| {
+                    let file_name = url.rsplit('/').next().unwrap_or_default();
+                    format!(
+                        "curl -o {} {}\n",
+                        quote_shell_arg(file_name),
+                        quote_shell_arg(url)
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Escapes the brackets in the given Markdown link text.
+fn escape_markdown_text(text: &str) -> String {
+    text.replace('[', "\\[").replace(']', "\\]")
+}
+
+/// Escapes the special characters in the given HTML text or attribute value.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Quotes the given argument for POSIX shells.
+fn quote_shell_arg(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+/// Upload options (i.e. query parameters).
+#[derive(Debug, Deserialize)]
+struct UploadOptions {
+    /// Format of the response.
+    #[serde(default)]
+    format: ResponseFormat,
+}
+
+/// Default maximum size of the URL fields.
+const DEFAULT_MAX_URL_SIZE: u64 = 8 * 1024;
+
+/// Settings of an upload request that are shared by the uploaded files.
+struct UploadContext {
+    /// Configuration with the settings of the authentication token.
+    config: Config,
+    /// Base URL of the uploaded files.
+    server_url: String,
+    /// Identity of the client for logging.
+    host: String,
+    /// Owner of the files (see [`auth::sign_owner`]).
+    owner: Option<String>,
+    /// Storage quota of the authentication token.
+    quota: Option<Byte>,
+    /// Time of the upload.
+    time: Duration,
+    /// Checksum from the `If-None-Match` header.
+    if_none_match: Option<String>,
+    /// Creation date from the `x-created-at` header.
+    created_at: Option<Duration>,
+}
+
+impl UploadContext {
+    /// Constructs the context of the given upload request.
+    ///
+    /// Returns an error if uploading is paused, the upload path is not valid
+    /// or the creation date is not allowed.
+    fn new(request: &HttpRequest, config: &RwLock<Config>) -> Result<Self, Error> {
+        let config = {
+            let config = util::read_config(config)?;
+            auth::get_auth_token(request.headers(), &config.server)
+                .and_then(|token| config.for_token(token))
+                .unwrap_or_else(|| config.clone())
+        };
+        let server_config = config.server.clone();
+        let host = header::get_log_identity(request, &server_config);
+        check_upload_paused(&server_config)?;
+        check_upload_path(&server_config)?;
+        let time = util::get_system_time()?;
+        let created_at = header::parse_created_at(request.headers(), time)?;
+        if created_at.is_some()
+            && !(server_config.allow_created_at.unwrap_or(false)
+                && config.get_tokens(TokenType::Auth).is_some())
+        {
+            warn!("{} sent a creation date which is not allowed", host);
+            return Err(error::ErrorForbidden("creation date is not allowed\n"));
+        }
+        let auth_token = auth::get_auth_token(request.headers(), &server_config);
+        Ok(Self {
+            server_url: get_server_url(request, &server_config),
+            owner: auth_token
+                .zip(server_config.owner_secret.as_deref())
+                .map(|(token, secret)| auth::sign_owner(secret, token)),
+            quota: auth_token
+                .and_then(|token| server_config.token_settings.as_ref()?.get(token)?.quota),
+            if_none_match: header::parse_if_none_match(request.headers())?,
+            config,
+            host,
+            time,
+            created_at,
+        })
+    }
+}
+
+/// Uploaded content with its settings.
+struct UploadedContent {
+    /// Content of the file or the URL.
+    data: Vec<u8>,
+    /// Type of the paste.
+    type_: PasteType,
+    /// Name of the uploaded file (empty for the URLs).
+    file_name: String,
+    /// File name from the `filename` header.
+    header_filename: Option<String>,
+    /// Expiry date from the `expire` header.
+    expiry_date: Option<u128>,
+    /// Charset from the `charset` header.
+    charset: Option<String>,
+    /// Transfer limit from the `max-transfer` header.
+    max_transfer: Option<Byte>,
+}
+
+/// URLs of a stored upload.
+struct UploadedUrl {
+    /// URL of the file.
+    url: String,
+    /// URL for deleting the file.
+    delete_url: Option<String>,
+    /// Whether the file is already stored with the checksum from the `If-None-Match` header.
+    existing: bool,
+}
+
+/// Stores the uploaded content and returns its URLs.
+///
+/// The same rules are applied to the files that are uploaded via `multipart/form-data`,
+/// `application/x-www-form-urlencoded` or an archive (e.g. duplicates, expiry, quota, owners).
+async fn store_upload(
+    context: &UploadContext,
+    mut content: UploadedContent,
+    client: &Client,
+    server_timing: &mut ServerTiming,
+) -> Result<UploadedUrl, Error> {
+    let config = &context.config;
+    let is_file = matches!(content.type_, PasteType::File | PasteType::Oneshot);
+    if is_file {
+        strip_trailing_newline(config, &mut content.data);
+    }
+    if content.data.is_empty() {
+        warn!("{} sent zero bytes", context.host);
+        return Err(error::ErrorBadRequest("invalid file size"));
+    }
+    if is_file {
+        check_blank_content(config, &content.data, &context.host)?;
+    }
+    let time = context.time;
+    let mut expiry_date = content.expiry_date;
+    if expiry_date.is_none() {
+        expiry_date = infer::get(&content.data)
+            .and_then(|file_type| {
+                mime_util::get_mime_expiry(&config.paste.mime_expiry, file_type.mime_type())
+            })
+            .or(config.paste.default_expiry)
+            .and_then(|v| time.checked_add(v).map(|t| t.as_millis()));
+    }
+    if content.type_ == PasteType::Oneshot || content.type_ == PasteType::OneshotUrl {
+        let max_expiry_date = config
+            .paste
+            .oneshot_max_lifetime
+            .and_then(|v| time.checked_add(v).map(|t| t.as_millis()));
+        if let Some(max_expiry_date) = max_expiry_date {
+            expiry_date = Some(expiry_date.map_or(max_expiry_date, |v| v.min(max_expiry_date)));
+        }
+    }
+    let check_duplicate = content.type_ != PasteType::Oneshot
+        && content.type_ != PasteType::RemoteFile
+        && content.type_ != PasteType::OneshotUrl
+        && expiry_date.is_none()
+        && !config.paste.duplicate_files.unwrap_or(true);
+    let check_if_none_match = content.type_ == PasteType::File && context.if_none_match.is_some();
+    if check_duplicate || check_if_none_match {
+        let hash_start = Instant::now();
+        let checksum_algorithm = config.paste.checksum_algorithm.unwrap_or_default();
+        let checksum = util::digest(&*content.data, checksum_algorithm)?;
+        let matches_header = context.if_none_match.as_deref() == Some(checksum.as_str());
+        let existing_file = if check_duplicate || matches_header {
+            let directory = Directory::from_path(&config.server.upload_path, checksum_algorithm)?;
+            match config.paste.dedup_scope.unwrap_or_default() {
+                DedupScope::Global => directory.get_file(&checksum),
+                DedupScope::PerToken => directory.get_owned_file(
+                    &checksum,
+                    &config.server.upload_path,
+                    context.owner.as_deref(),
+                ),
+            }
+        } else {
+            None
+        };
+        server_timing.add("hash", hash_start.elapsed());
+        if let Some(file) = existing_file {
+            return Ok(UploadedUrl {
+                url: format!(
+                    "{}/{}",
+                    context.server_url,
+                    file.path
+                        .file_name()
+                        .map(|v| v.to_string_lossy())
+                        .unwrap_or_default()
+                ),
+                delete_url: None,
+                existing: check_if_none_match && matches_header,
+            });
+        }
+    }
+    if let (Some(quota), Some(owner), true) = (context.quota, &context.owner, is_file) {
+        let usage = paste::get_owned_size(&config.server.upload_path, owner);
+        if usage + content.data.len() as u64 > quota.as_u64() {
+            warn!("{} exceeded the storage quota", context.host);
+            return Err(error::ErrorPayloadTooLarge("storage quota exceeded\n"));
+        }
+    }
+    let transcode_config = config.transcode.clone().filter(|v| v.enabled);
+    if let (Some(transcode_config), true) = (transcode_config, is_file) {
+        let transcode_start = Instant::now();
+        let format = transcode_config.format;
+        let data = content.data;
+        // the images are transcoded on the blocking thread pool
+        let (data, transcoded) = web::block(move || {
+            let transcoded = transcode::transcode(&data, &transcode_config);
+            (data, transcoded)
+        })
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+        content.data = match transcoded {
+            Ok(Some(transcoded)) => {
+                content.file_name = format.rename(&content.file_name);
+                content.header_filename = content.header_filename.map(|v| format.rename(&v));
+                transcoded
+            }
+            Ok(None) => data,
+            Err(e) => {
+                warn!("cannot transcode the image, storing the original: {e}");
+                data
+            }
+        };
+        server_timing.add("transcode", transcode_start.elapsed());
+    }
+    let mut paste = Paste {
+        data: content.data,
+        type_: content.type_,
+    };
+    let store_start = Instant::now();
+    let mut file_name = match paste.type_ {
+        PasteType::File | PasteType::Oneshot => {
+            let config = config.clone();
+            let (upload_file_name, header_filename) = (content.file_name, content.header_filename);
+            // the files might be scanned, store them on the blocking thread pool
+            let (stored_paste, file_name) = run_blocking(move || {
+                let file_name =
+                    paste.store_file(&upload_file_name, expiry_date, header_filename, &config)?;
+                Ok((paste, file_name))
+            })
+            .await?;
+            paste = stored_paste;
+            file_name
+        }
+        PasteType::RemoteFile => {
+            paste
+                .store_remote_file(expiry_date, content.header_filename, client, config)
+                .await?
+        }
+        PasteType::Url | PasteType::OneshotUrl => {
+            paste.store_url(expiry_date, content.header_filename, config)?
+        }
+    };
+    server_timing.add("store", store_start.elapsed());
+    if paste.type_ == PasteType::File || paste.type_ == PasteType::Oneshot {
+        paste::store_charset(
+            &paste.type_.get_path(&config.server.upload_path)?,
+            &file_name,
+            content.charset.as_deref(),
+        )?;
+    }
+    if matches!(paste.type_, PasteType::File | PasteType::RemoteFile) {
+        paste::store_transfer_limit(
+            &paste.type_.get_path(&config.server.upload_path)?,
+            &file_name,
+            content
+                .max_transfer
+                .or(config.paste.max_transfer)
+                .map(|v| v.as_u64()),
+        )?;
+    }
+    let dedup_per_token = config.paste.dedup_scope.unwrap_or_default() == DedupScope::PerToken;
+    // the owners are also used for calculating the usage of the quotas
+    if (paste.type_ == PasteType::File && dedup_per_token)
+        || (context.quota.is_some() && matches!(paste.type_, PasteType::File | PasteType::Oneshot))
+    {
+        paste::store_owner(
+            &paste.type_.get_path(&config.server.upload_path)?,
+            &file_name,
+            context.owner.as_deref(),
+        )?;
+    }
+    if let (Some(created_at), PasteType::File) = (context.created_at, paste.type_) {
+        let path = find_upload_file(config, &file_name)?;
+        fs::File::options()
+            .write(true)
+            .open(path)?
+            .set_modified(UNIX_EPOCH + created_at)?;
+    }
+    info!(
+        "{} ({}) is uploaded from {}",
+        util::get_log_file_name(&file_name, &config.server),
+        Byte::from_u128(paste.data.len() as u128)
+            .unwrap_or_default()
+            .get_appropriate_unit(UnitType::Decimal),
+        context.host
+    );
+    // the tokens are signed with the stored file name
+    let delete_token = match (&config.server.delete_url_secret, paste.type_) {
+        (Some(secret), PasteType::File | PasteType::RemoteFile) => {
+            let checksum = get_deletion_checksum(&find_upload_file(config, &file_name)?)?;
+            Some(auth::sign_deletion(secret, &file_name, &checksum))
+        }
+        _ => None,
+    };
+    if let Some(handle_spaces_config) = config.server.handle_spaces {
+        file_name = handle_spaces_config.process_filename(&file_name);
+    }
+    Ok(UploadedUrl {
+        url: format!("{}/{}", context.server_url, file_name),
+        delete_url: delete_token
+            .map(|token| format!("{}/{file_name}?token={token}", context.server_url)),
+        existing: false,
+    })
+}
+
+/// Reads the request body up to [`max_content_length`].
+///
+/// [`max_content_length`]: crate::config::ServerConfig::max_content_length
+async fn read_payload(
+    payload: &mut web::Payload,
+    server_config: &ServerConfig,
+) -> Result<Vec<u8>, Error> {
+    let mut body = Vec::<u8>::new();
+    while let Some(chunk) = next_with_timeout(payload, server_config.body_read_timeout).await? {
+        body.append(&mut chunk?.to_vec());
+        if body.len() as u64 > server_config.max_content_length.as_u64() {
+            return Err(error::ErrorPayloadTooLarge("upload limit exceeded"));
+        }
+    }
+    Ok(body)
+}
+
+/// Handles file upload by processing `multipart/form-data`.
+///
+/// Responds with the URLs of the uploaded files, each followed by a single newline.
+/// If `?format=url-only` is set, the trailing newline is omitted.
+/// If `?format=share` is set, each URL is returned as a `curl` command for downloading the file.
+/// If `?format=markdown`, `?format=html` or `?format=bbcode` is set, each URL is returned as an
+/// embeddable image (for images) or link.
+#[post("/")]
+#[actix_web_grants::protect("EndpointGroup::Write", ty = EndpointGroup, error = unauthorized_error)]
+async fn upload(
+    request: HttpRequest,
+    mut payload: Multipart,
+    options: Option<web::Query<UploadOptions>>,
+    client: web::Data<Client>,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
+    let start = Instant::now();
+    let mut server_timing = ServerTiming::default();
+    let context = UploadContext::new(&request, &config)?;
+    let server_config = context.config.server.clone();
+    let host = &context.host;
+    let time = context.time;
+    let header_expiry_date = header::parse_expiry_date(request.headers(), time)?;
+    let mut existing = false;
+    let mut urls: Vec<String> = Vec::new();
+    let mut delete_urls: Vec<String> = Vec::new();
+    let body_read_timeout = server_config.body_read_timeout;
+    while let Some(item) = next_with_timeout(&mut payload, body_read_timeout).await? {
+        let mut field = item?;
+        let header_filename = match header::parse_header_filename(field.headers())? {
+            Some(filename) => Some(filename),
+            None => header::parse_header_filename(request.headers())?,
+        };
+        let field_expiry_date = header::parse_expiry_date(field.headers(), time)?;
+        let charset = match header::parse_charset(field.headers())? {
+            Some(charset) => Some(charset),
+            None => header::parse_charset(request.headers())?,
+        };
+        let max_transfer = match header::parse_max_transfer(field.headers())? {
+            Some(max_transfer) => Some(max_transfer),
+            None => header::parse_max_transfer(request.headers())?,
+        };
+        let content_digest = match header::parse_content_digest(field.headers())? {
+            Some(content_digest) => Some(content_digest),
+            None => header::parse_content_digest(request.headers())?,
+        };
         let content = ContentDisposition::from(
             field
                 .content_disposition()
@@ -234,92 +2048,199 @@ async fn upload(
                 .clone(),
         );
         if let Ok(paste_type) = PasteType::try_from(&content) {
+            // URL fields are small, do not buffer more than needed
+            let max_size = server_config
+                .max_url_size
+                .map(|v| v.as_u64())
+                .unwrap_or(DEFAULT_MAX_URL_SIZE);
             let mut bytes = Vec::<u8>::new();
-            while let Some(chunk) = field.next().await {
+            while let Some(chunk) = next_with_timeout(&mut field, body_read_timeout).await? {
                 bytes.append(&mut chunk?.to_vec());
+                if paste_type.is_url() && bytes.len() as u64 > max_size {
+                    warn!("{} sent an URL larger than {} bytes", host, max_size);
+                    return Err(error::ErrorBadRequest("URL is too long\n"));
+                }
             }
-            if bytes.is_empty() {
-                warn!("{} sent zero bytes", host);
-                return Err(error::ErrorBadRequest("invalid file size"));
-            }
-            if paste_type != PasteType::Oneshot
-                && paste_type != PasteType::RemoteFile
-                && paste_type != PasteType::OneshotUrl
-                && expiry_date.is_none()
-                && !config
-                    .read()
-                    .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
-                    .paste
-                    .duplicate_files
-                    .unwrap_or(true)
+            if let (Some(content_digest), PasteType::File | PasteType::Oneshot) =
+                (&content_digest, paste_type)
             {
-                let bytes_checksum = util::sha256_digest(&*bytes)?;
-                let config = config
-                    .read()
-                    .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
-                if let Some(file) = Directory::try_from(config.server.upload_path.as_path())?
-                    .get_file(bytes_checksum)
-                {
-                    urls.push(format!(
-                        "{}/{}\n",
-                        server_url,
-                        file.path
-                            .file_name()
-                            .map(|v| v.to_string_lossy())
-                            .unwrap_or_default()
-                    ));
-                    continue;
+                if !content_digest.verify(&bytes)? {
+                    warn!("{} sent content that does not match the digest", host);
+                    return Err(error::ErrorBadRequest("content digest mismatch\n"));
                 }
             }
-            let mut paste = Paste {
-                data: bytes.to_vec(),
+            if paste_type == PasteType::RemoteFile && !bytes.is_empty() {
+                check_remote_origin(&request, &context.config, host)?;
+            }
+            let uploaded_content = UploadedContent {
+                data: bytes,
                 type_: paste_type,
+                // the URLs do not have a file name
+                file_name: match paste_type {
+                    PasteType::File | PasteType::Oneshot => content.get_file_name()?.to_string(),
+                    _ => String::new(),
+                },
+                header_filename,
+                expiry_date: field_expiry_date.or(header_expiry_date),
+                charset,
+                max_transfer,
             };
-            let mut file_name = match paste.type_ {
-                PasteType::File | PasteType::Oneshot => {
-                    let config = config
-                        .read()
-                        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
-                    paste.store_file(
-                        content.get_file_name()?,
-                        expiry_date,
-                        header_filename,
-                        &config,
-                    )?
-                }
-                PasteType::RemoteFile => {
-                    paste
-                        .store_remote_file(expiry_date, &client, &config)
-                        .await?
-                }
-                PasteType::Url | PasteType::OneshotUrl => {
-                    let config = config
-                        .read()
-                        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
-                    paste.store_url(expiry_date, header_filename, &config)?
-                }
-            };
-            info!(
-                "{} ({}) is uploaded from {}",
-                file_name,
-                Byte::from_u128(paste.data.len() as u128)
-                    .unwrap_or_default()
-                    .get_appropriate_unit(UnitType::Decimal),
-                host
-            );
-            let config = config
-                .read()
-                .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
-            if let Some(handle_spaces_config) = config.server.handle_spaces {
-                file_name = handle_spaces_config.process_filename(&file_name);
-            }
-            urls.push(format!("{}/{}\n", server_url, file_name));
+            let uploaded_url =
+                store_upload(&context, uploaded_content, &client, &mut server_timing).await?;
+            existing |= uploaded_url.existing;
+            delete_urls.extend(uploaded_url.delete_url);
+            urls.push(uploaded_url.url);
         } else {
             warn!("{} sent an invalid form field", host);
             return Err(error::ErrorBadRequest("invalid form field"));
         }
     }
-    Ok(HttpResponse::Ok().body(urls.join("")))
+    let format = options.map(|v| v.format).unwrap_or_default();
+    let mut response = HttpResponse::Ok();
+    if existing {
+        response.insert_header((header::EXISTING, "true"));
+    }
+    for delete_url in delete_urls {
+        response.append_header((header::DELETE_URL, delete_url));
+    }
+    let body = format.format_urls(&urls, &context.config);
+    update_manifest(&context.config);
+    let mut response = response.body(body);
+    server_timing.add("total", start.elapsed());
+    server_timing.insert_into(response.headers_mut())?;
+    Ok(response)
+}
+
+/// Returns `true` if the request body is `application/x-www-form-urlencoded`.
+fn is_form_urlencoded(ctx: &GuardContext) -> bool {
+    ctx.header::<ContentType>()
+        .is_some_and(|v| v.0.essence_str() == mime::APPLICATION_WWW_FORM_URLENCODED.essence_str())
+}
+
+/// Handles text upload by processing `application/x-www-form-urlencoded`.
+///
+/// - `content` field is stored as a text file.
+/// - `name` and `extension` fields are used for the file name (defaults to `paste`).
+///
+/// Responds with the URL of the uploaded file followed by a single newline.
+#[post("/", guard = "is_form_urlencoded")]
+#[actix_web_grants::protect("EndpointGroup::Write", ty = EndpointGroup, error = unauthorized_error)]
+async fn upload_form(
+    request: HttpRequest,
+    mut payload: web::Payload,
+    client: web::Data<Client>,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
+    let mut server_timing = ServerTiming::default();
+    let context = UploadContext::new(&request, &config)?;
+    let server_config = context.config.server.clone();
+    let body = read_payload(&mut payload, &server_config).await?;
+    let (mut content, mut name, mut extension) = (None, None, None);
+    for (key, value) in url::form_urlencoded::parse(&body) {
+        match key.as_ref() {
+            "content" => content = Some(value.into_owned()),
+            "name" => name = Some(value.into_owned()).filter(|v| !v.is_empty()),
+            "extension" => extension = Some(value.into_owned()).filter(|v| !v.is_empty()),
+            _ => {}
+        }
+    }
+    if let Some(extension) = extension
+        .as_deref()
+        .filter(|v| !v.chars().all(|c| c.is_ascii_alphanumeric() || c == '.'))
+    {
+        warn!("{} sent an invalid extension: {}", context.host, extension);
+        return Err(error::ErrorBadRequest("invalid extension\n"));
+    }
+    let file_name = match (name, extension) {
+        (Some(name), Some(extension)) => format!("{name}.{extension}"),
+        (Some(name), None) => name,
+        (None, Some(extension)) => format!("paste.{extension}"),
+        (None, None) => String::from("paste"),
+    };
+    let uploaded_content = UploadedContent {
+        data: content.unwrap_or_default().into_bytes(),
+        type_: PasteType::File,
+        file_name,
+        header_filename: None,
+        expiry_date: header::parse_expiry_date(request.headers(), context.time)?,
+        charset: header::parse_charset(request.headers())?,
+        max_transfer: header::parse_max_transfer(request.headers())?,
+    };
+    let uploaded_url =
+        store_upload(&context, uploaded_content, &client, &mut server_timing).await?;
+    update_manifest(&context.config);
+    let mut response = HttpResponse::Ok();
+    if let Some(delete_url) = uploaded_url.delete_url {
+        response.insert_header((header::DELETE_URL, delete_url));
+    }
+    Ok(response.body(format!("{}\n", uploaded_url.url)))
+}
+
+/// Handles the upload of multiple files in a tar, gzip compressed tar or zip archive.
+///
+/// Each file in the archive is stored as a separate paste. See [`archive::extract`] for the limits.
+///
+/// Responds with the URLs of the uploaded files, one per line.
+#[post("/archive")]
+#[actix_web_grants::protect("EndpointGroup::Write", ty = EndpointGroup, error = unauthorized_error)]
+async fn upload_archive(
+    request: HttpRequest,
+    mut payload: web::Payload,
+    client: web::Data<Client>,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
+    let archive_config = match util::read_config(&config)?
+        .archive
+        .clone()
+        .filter(|v| v.enabled)
+    {
+        Some(archive_config) => archive_config,
+        None => {
+            warn!("server is not configured to extract archives");
+            return Err(error::ErrorNotFound(""));
+        }
+    };
+    let mut server_timing = ServerTiming::default();
+    let context = UploadContext::new(&request, &config)?;
+    let server_config = context.config.server.clone();
+    let body = read_payload(&mut payload, &server_config).await?;
+    let max_entries = archive_config
+        .max_entries
+        .unwrap_or(archive::DEFAULT_MAX_ENTRIES);
+    let max_size = archive_config
+        .max_size
+        .unwrap_or(server_config.max_content_length)
+        .as_u64();
+    // the archives are extracted on the blocking thread pool
+    let entries = run_blocking(move || archive::extract(&body, max_entries, max_size))
+        .await
+        .inspect_err(|_| warn!("{} sent an invalid archive", context.host))?;
+    if entries.iter().any(|entry| entry.data.is_empty()) {
+        warn!("{} sent zero bytes", context.host);
+        return Err(error::ErrorBadRequest("invalid file size"));
+    }
+    let expiry_date = header::parse_expiry_date(request.headers(), context.time)?;
+    let mut urls = String::new();
+    let mut response = HttpResponse::Ok();
+    for entry in entries {
+        let uploaded_content = UploadedContent {
+            data: entry.data,
+            type_: PasteType::File,
+            file_name: entry.file_name,
+            header_filename: None,
+            expiry_date,
+            charset: header::parse_charset(request.headers())?,
+            max_transfer: header::parse_max_transfer(request.headers())?,
+        };
+        let uploaded_url =
+            store_upload(&context, uploaded_content, &client, &mut server_timing).await?;
+        if let Some(delete_url) = uploaded_url.delete_url {
+            response.append_header((header::DELETE_URL, delete_url));
+        }
+        urls.push_str(&format!("{}\n", uploaded_url.url));
+    }
+    update_manifest(&context.config);
+    Ok(response.body(urls))
 }
 
 /// File entry item for list endpoint.
@@ -330,90 +2251,419 @@ pub struct ListItem {
     /// Size of the file in bytes.
     pub file_size: u64,
     /// ISO8601 formatted date-time of the moment the file was created (uploaded).
+    ///
+    /// Formatted in [`display_timezone`] if it is set.
+    ///
+    /// [`display_timezone`]: crate::config::ServerConfig::display_timezone
     pub creation_date_utc: Option<String>,
+    /// UNIX timestamp (in seconds) of the moment the file was created (uploaded).
+    #[serde(default)]
+    pub creation_date_epoch: Option<i64>,
     /// ISO8601 formatted date-time string of the expiration timestamp if one exists for this file.
+    ///
+    /// Formatted in [`display_timezone`] if it is set.
+    ///
+    /// [`display_timezone`]: crate::config::ServerConfig::display_timezone
     pub expires_at_utc: Option<String>,
+    /// UNIX timestamp (in seconds) of the expiration if one exists for this file.
+    #[serde(default)]
+    pub expires_at_epoch: Option<i64>,
 }
 
-/// Returns the list of files.
-#[get("/list")]
-#[actix_web_grants::protect("TokenType::Auth", ty = TokenType, error = unauthorized_error)]
-async fn list(config: web::Data<RwLock<Config>>) -> Result<HttpResponse, Error> {
-    let config = config
-        .read()
-        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
-        .clone();
-    if !config.server.expose_list.unwrap_or(false) {
-        warn!("server is not configured to expose list endpoint");
-        Err(error::ErrorNotFound(""))?;
+/// Header row of the CSV list.
+const LIST_CSV_HEADER: &str =
+    "file_name,file_size,creation_date_utc,creation_date_epoch,expires_at_utc,expires_at_epoch\n";
+
+impl ListItem {
+    /// Returns the item as a CSV record that ends with a newline.
+    fn to_csv_record(&self) -> String {
+        let fields = [
+            escape_csv_field(&self.file_name.to_string_lossy()),
+            self.file_size.to_string(),
+            escape_csv_field(self.creation_date_utc.as_deref().unwrap_or_default()),
+            self.creation_date_epoch
+                .map(|v| v.to_string())
+                .unwrap_or_default(),
+            escape_csv_field(self.expires_at_utc.as_deref().unwrap_or_default()),
+            self.expires_at_epoch
+                .map(|v| v.to_string())
+                .unwrap_or_default(),
+        ];
+        format!("{}\n", fields.join(","))
     }
-    let entries: Vec<ListItem> = fs::read_dir(config.server.upload_path)?
-        .filter_map(|entry| {
-            entry.ok().and_then(|e| {
-                let metadata = match e.metadata() {
-                    Ok(metadata) => {
-                        if metadata.is_dir() {
-                            return None;
-                        }
-                        metadata
-                    }
-                    Err(e) => {
-                        error!("failed to read metadata: {e}");
+}
+
+/// Quotes the given CSV field if it contains a delimiter, quote or line break (RFC 4180).
+fn escape_csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Format of the list response.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ListFormat {
+    /// JSON array.
+    #[default]
+    Json,
+    /// Comma-separated values with a header row.
+    Csv,
+    /// JSON objects separated by newlines.
+    Ndjson,
+}
+
+impl ListFormat {
+    /// Returns the format that is requested via the `Accept` header.
+    fn from_accept(request: &HttpRequest) -> Self {
+        let accept = request
+            .headers()
+            .get(ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        if accept.contains("text/csv") {
+            Self::Csv
+        } else if accept.contains("application/x-ndjson") {
+            Self::Ndjson
+        } else {
+            Self::default()
+        }
+    }
+
+    /// Returns the content type of the response.
+    fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    /// Returns the bytes that precede the entries.
+    fn prefix(&self) -> &'static [u8] {
+        match self {
+            Self::Json => b"[",
+            Self::Csv => LIST_CSV_HEADER.as_bytes(),
+            Self::Ndjson => b"",
+        }
+    }
+
+    /// Returns the bytes that follow the entries.
+    fn suffix(&self) -> &'static [u8] {
+        match self {
+            Self::Json => b"]",
+            Self::Csv | Self::Ndjson => b"",
+        }
+    }
+
+    /// Formats the entry at the given position.
+    fn format_item(&self, position: usize, item: &ListItem) -> Result<web::Bytes, Error> {
+        let mut chunk = Vec::new();
+        match self {
+            Self::Json => {
+                if position != 0 {
+                    chunk.push(b',');
+                }
+                serde_json::to_writer(&mut chunk, item).map_err(error::ErrorInternalServerError)?;
+            }
+            Self::Csv => chunk.extend(item.to_csv_record().into_bytes()),
+            Self::Ndjson => {
+                serde_json::to_writer(&mut chunk, item).map_err(error::ErrorInternalServerError)?;
+                chunk.push(b'\n');
+            }
+        }
+        Ok(web::Bytes::from(chunk))
+    }
+}
+
+/// Name of the manifest file in the upload directory.
+///
+/// See [`write_manifest`](crate::config::ServerConfig::write_manifest).
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Name of the temporary file that the manifest is written to before being renamed.
+const MANIFEST_TEMP_FILE: &str = ".manifest.json.tmp";
+
+/// Lock for preventing the concurrent writes of the manifest.
+static MANIFEST_LOCK: Mutex<()> = Mutex::new(());
+
+/// Returns `true` if the file at the given path is maintained by the server,
+/// i.e. the manifest or the [`pause_file`].
+///
+/// [`pause_file`]: ServerConfig::pause_file
+fn is_server_file(config: &Config, path: &Path) -> bool {
+    path.parent() == Some(config.server.upload_path.as_path())
+        && path
+            .file_name()
+            .is_some_and(|v| v == MANIFEST_FILE || v == MANIFEST_TEMP_FILE)
+        || config.server.pause_file.as_deref() == Some(path)
+}
+
+/// Returns the unexpired files in the upload directory (including the fan-out directories).
+fn get_list_items(config: &Config) -> Result<impl Iterator<Item = ListItem>, Error> {
+    let display_timezone = config.server.display_timezone;
+    let fanout_paths = match config.paste.fanout_depth.filter(|v| *v != 0) {
+        Some(depth) => Some(
+            glob(
+                &util::get_fanout_pattern(&config.server.upload_path, depth)
+                    .join("*")
+                    .to_string_lossy(),
+            )
+            .map_err(error::ErrorInternalServerError)?,
+        ),
+        None => None,
+    };
+    let write_manifest = config.server.write_manifest.unwrap_or(false);
+    let entries = fs::read_dir(&config.server.upload_path)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .chain(fanout_paths.into_iter().flatten().filter_map(Result::ok))
+        .filter(move |path| {
+            !write_manifest
+                || !path
+                    .file_name()
+                    .is_some_and(|v| v == MANIFEST_FILE || v == MANIFEST_TEMP_FILE)
+        })
+        .filter_map(move |path| {
+            let metadata = match fs::symlink_metadata(&path) {
+                Ok(metadata) => {
+                    if metadata.is_dir() {
                         return None;
                     }
-                };
-                let mut file_name = PathBuf::from(e.file_name());
-
-                let creation_date_utc = metadata.created().ok().map(|v| {
+                    metadata
+                }
+                Err(e) => {
+                    error!("failed to read metadata: {e}");
+                    return None;
+                }
+            };
+            let mut file_name = PathBuf::from(path.file_name()?);
+
+            // the modification time is earlier if the creation date is preserved while uploading
+            let creation_date_epoch = [metadata.created().ok(), metadata.modified().ok()]
+                .into_iter()
+                .flatten()
+                .min()
+                .map(|v| {
                     let millis = v
                         .duration_since(UNIX_EPOCH)
                         .expect("Time since UNIX epoch should be valid.")
                         .as_millis();
-                    uts2ts::uts2ts(
-                        i64::try_from(millis).expect("UNIX time should be smaller than i64::MAX")
-                            / 1000,
-                    )
-                    .as_string()
+                    i64::try_from(millis).expect("UNIX time should be smaller than i64::MAX") / 1000
                 });
 
-                let expires_at_utc = if let Some(expiration) = file_name
-                    .extension()
-                    .and_then(|ext| ext.to_str())
-                    .and_then(|v| v.parse::<i64>().ok())
+            let expires_at_epoch = if let Some(expiration) = file_name
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .and_then(|v| v.parse::<i64>().ok())
+            {
+                file_name.set_extension("");
+                if util::get_system_time().ok()?
+                    > Duration::from_millis(expiration.try_into().ok()?)
                 {
-                    file_name.set_extension("");
-                    if util::get_system_time().ok()?
-                        > Duration::from_millis(expiration.try_into().ok()?)
-                    {
-                        return None;
-                    }
-                    Some(uts2ts::uts2ts(expiration / 1000).as_string())
-                } else {
-                    None
-                };
-                Some(ListItem {
-                    file_name,
-                    file_size: metadata.len(),
-                    creation_date_utc,
-                    expires_at_utc,
-                })
+                    return None;
+                }
+                Some(expiration / 1000)
+            } else {
+                None
+            };
+            Some(ListItem {
+                file_name,
+                file_size: metadata.len(),
+                creation_date_utc: creation_date_epoch
+                    .map(|v| util::format_timestamp(v, display_timezone)),
+                creation_date_epoch,
+                expires_at_utc: expires_at_epoch
+                    .map(|v| util::format_timestamp(v, display_timezone)),
+                expires_at_epoch,
             })
-        })
-        .collect();
-    Ok(HttpResponse::Ok().json(entries))
+        });
+    Ok(entries)
+}
+
+/// Returns the entity tag of the list in the given format.
+///
+/// The tag is computed from the names, sizes and dates of the files so that the entries
+/// are not serialized for the clients that already have the latest list.
+fn get_list_etag(items: &[ListItem], format: ListFormat) -> Result<EntityTag, Error> {
+    let mut fingerprint = String::from(format.content_type());
+    for item in items {
+        fingerprint.push_str(&format!(
+            "\n{}\0{}\0{:?}\0{:?}",
+            item.file_name.display(),
+            item.file_size,
+            item.creation_date_epoch,
+            item.expires_at_epoch
+        ));
+    }
+    Ok(EntityTag::new_strong(util::digest(
+        fingerprint.as_bytes(),
+        ChecksumAlgorithm::Sha256,
+    )?))
+}
+
+/// Writes the list of the files into the manifest file if [`write_manifest`] is enabled.
+///
+/// The manifest is replaced atomically and the errors are only logged.
+///
+/// [`write_manifest`]: crate::config::ServerConfig::write_manifest
+fn update_manifest(config: &Config) {
+    if !config.server.write_manifest.unwrap_or(false) {
+        return;
+    }
+    let _lock = MANIFEST_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let result = get_list_items(config).and_then(|items| {
+        let manifest = serde_json::to_vec_pretty(&items.collect::<Vec<ListItem>>())
+            .map_err(error::ErrorInternalServerError)?;
+        let temp_path = config.server.upload_path.join(MANIFEST_TEMP_FILE);
+        fs::write(&temp_path, manifest)?;
+        fs::rename(&temp_path, config.server.upload_path.join(MANIFEST_FILE))?;
+        Ok(())
+    });
+    if let Err(e) = result {
+        error!("cannot write manifest: {}", e);
+    }
+}
+
+/// List options (i.e. query parameters).
+#[derive(Debug, Deserialize)]
+struct ListOptions {
+    /// Format of the response.
+    format: Option<ListFormat>,
+}
+
+/// Returns the list of files.
+///
+/// The entries are serialized while they are streamed.
+/// The format is selected via `?format=json|csv|ndjson` or the `Accept` header (JSON by default).
+#[get("/list")]
+#[actix_web_grants::protect("EndpointGroup::List", ty = EndpointGroup, error = unauthorized_error)]
+async fn list(
+    request: HttpRequest,
+    options: Option<web::Query<ListOptions>>,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
+    let config = util::read_config(&config)?.clone();
+    if !config.server.expose_list.unwrap_or(false) {
+        warn!("server is not configured to expose list endpoint");
+        Err(error::ErrorNotFound(""))?;
+    }
+    let format = options
+        .and_then(|v| v.format)
+        .unwrap_or_else(|| ListFormat::from_accept(&request));
+    // the directory is read once so that the tag always matches the entries
+    let items = get_list_items(&config)?.collect::<Vec<ListItem>>();
+    let etag = get_list_etag(&items, format)?;
+    let not_modified = match IfNoneMatch::parse(&request) {
+        Ok(IfNoneMatch::Any) => true,
+        Ok(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&etag)),
+        Err(_) => false,
+    };
+    if not_modified {
+        return Ok(HttpResponse::NotModified()
+            .insert_header((ETAG, etag.to_string()))
+            .finish());
+    }
+    let entries = items
+        .into_iter()
+        .enumerate()
+        .map(move |(i, entry)| format.format_item(i, &entry));
+    let body = stream::once(future::ok(web::Bytes::from_static(format.prefix())))
+        .chain(stream::iter(entries))
+        .chain(stream::once(future::ok(web::Bytes::from_static(
+            format.suffix(),
+        ))));
+    let algorithm = if config.server.compress_list.unwrap_or(false) {
+        let accept_encoding = request
+            .headers()
+            .get(ACCEPT_ENCODING)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        compression::negotiate(accept_encoding, compression::DEFAULT_ALGORITHMS)
+    } else {
+        None
+    };
+    let mut response = HttpResponse::Ok();
+    response.content_type(format.content_type());
+    match algorithm {
+        Some(algorithm) => Ok(response
+            .insert_header((
+                ETAG,
+                EntityTag::new_weak(etag.tag().to_string()).to_string(),
+            ))
+            .insert_header((CONTENT_ENCODING, algorithm.encoding()))
+            .insert_header((VARY, "accept-encoding"))
+            .streaming(compression::compress_stream(
+                body,
+                algorithm,
+                compression::DEFAULT_LEVEL,
+            ))),
+        None => Ok(response
+            .insert_header((ETAG, etag.to_string()))
+            .streaming(body)),
+    }
+}
+
+/// Groups of the server routes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteGroup {
+    /// All of the routes.
+    All,
+    /// Routes for uploading and serving the files.
+    Public,
+    /// Administrative routes (`/list`, `/debug/config`, `/import` and `/purge`).
+    Admin,
 }
 
+/// Paths of the administrative routes (see [`RouteGroup::Admin`]).
+const ADMIN_ROUTES: [&str; 4] = ["/list", "/debug/config", "/import", "/purge"];
+
 /// Configures the server routes.
+///
+/// The shared [`Config`] and the HTTP [`Client`] are expected as application data.
+/// See [`AppState`](crate::AppState) for registering them along with the middleware.
 pub fn configure_routes(cfg: &mut web::ServiceConfig) {
-    cfg.service(
-        web::scope("")
+    configure_route_group(cfg, RouteGroup::All);
+}
+
+/// Configures the server routes of the given group.
+///
+/// See [`configure_routes`] for the expected application data.
+pub fn configure_route_group(cfg: &mut web::ServiceConfig, group: RouteGroup) {
+    let mut scope = web::scope("");
+    if group != RouteGroup::Public {
+        scope = scope
+            .service(list)
+            .service(debug_config)
+            .service(import)
+            .service(purge);
+    } else {
+        // the administrative routes should not be handled as files
+        for path in ADMIN_ROUTES {
+            scope = scope.route(path, web::to(HttpResponse::NotFound));
+        }
+    }
+    if group != RouteGroup::Admin {
+        scope = scope
             .service(index)
             .service(version)
-            .service(list)
+            .service(openapi_spec)
+            .service(robots_txt)
+            .service(sign)
+            .service(serve_raw)
             .service(serve)
+            .service(upload_archive)
+            .service(upload_form)
             .service(upload)
             .service(delete)
-            .route("", web::head().to(HttpResponse::MethodNotAllowed))
+            .service(extend)
+            .route("", web::head().to(HttpResponse::MethodNotAllowed));
+    }
+    cfg.service(
+        scope
             .wrap(GrantsMiddleware::with_extractor(extract_tokens))
             .wrap(
                 ErrorHandlers::new().handler(StatusCode::UNAUTHORIZED, handle_unauthorized_error),
@@ -424,39 +2674,52 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::LandingPageConfig;
+    use crate::auth::AuthFailureTracker;
+    use crate::config::{
+        ArchiveConfig, AuthConfig, AuthFailureLimitConfig, ChecksumAlgorithm, CompressionConfig,
+        LandingPageConfig, ScanConfig, SpaceHandlingConfig, TokenSettings, TranscodeConfig,
+        TranscodeFormat,
+    };
     use crate::middleware::ContentLengthLimiter;
+    use crate::mime::MimeExpiryMatcher;
     use crate::random::{RandomURLConfig, RandomURLType};
     use actix_web::body::MessageBody;
     use actix_web::body::{BodySize, BoxBody};
+    use actix_web::dev::ServiceResponse;
     use actix_web::error::Error;
-    use actix_web::http::header::AUTHORIZATION;
+    use actix_web::http::header::{AUTHORIZATION, IF_NONE_MATCH};
     use actix_web::http::{header, StatusCode};
     use actix_web::test::{self, TestRequest};
     use actix_web::web::Data;
     use actix_web::App;
     use awc::ClientBuilder;
     use glob::glob;
+    use regex::Regex;
+    use std::collections::HashMap;
     use std::fs::File;
-    use std::io::Write;
+    use std::io::{Read, Write};
     use std::path::PathBuf;
     use std::str;
     use std::thread;
     use std::time::Duration;
 
     fn get_multipart_request(data: &str, name: &str, filename: &str) -> TestRequest {
-        let multipart_data = format!(
+        get_multipart_request_with_bytes(data.as_bytes(), name, filename)
+    }
+
+    fn get_multipart_request_with_bytes(data: &[u8], name: &str, filename: &str) -> TestRequest {
+        let mut multipart_data = format!(
             "\r\n\
              --multipart_bound\r\n\
              Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\
-             Content-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\n\r\n\
-             {}\r\n\
-             --multipart_bound--\r\n",
+             Content-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\n\r\n",
             name,
             filename,
-            data.bytes().len(),
-            data,
-        );
+            data.len(),
+        )
+        .into_bytes();
+        multipart_data.extend_from_slice(data);
+        multipart_data.extend_from_slice(b"\r\n--multipart_bound--\r\n");
         TestRequest::post()
             .insert_header((
                 header::CONTENT_TYPE,
@@ -464,15 +2727,37 @@ mod tests {
             ))
             .insert_header((
                 header::CONTENT_LENGTH,
-                header::HeaderValue::from_str(&data.bytes().len().to_string())
+                header::HeaderValue::from_str(&data.len().to_string())
                     .expect("cannot create header value"),
             ))
             .set_payload(multipart_data)
     }
 
+    fn get_multipart_request_with_files(files: &[(&str, &str)]) -> TestRequest {
+        let mut multipart_data = String::from("\r\n");
+        for (data, filename) in files {
+            multipart_data.push_str(&format!(
+                "--multipart_bound\r\n\
+                 Content-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n\
+                 Content-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\n\r\n\
+                 {}\r\n",
+                filename,
+                data.len(),
+                data,
+            ));
+        }
+        multipart_data.push_str("--multipart_bound--\r\n");
+        TestRequest::post()
+            .insert_header((
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("multipart/mixed; boundary=\"multipart_bound\""),
+            ))
+            .set_payload(multipart_data)
+    }
+
     async fn assert_body(body: BoxBody, expected: &str) -> Result<(), Error> {
         if let BodySize::Sized(size) = body.size() {
-            assert_eq!(size, expected.as_bytes().len() as u64);
+            assert_eq!(size, expected.len() as u64);
             let body_bytes = actix_web::body::to_bytes(body).await?;
             let body_text = str::from_utf8(&body_bytes)?;
             assert_eq!(expected, body_text);
@@ -498,87 +2783,3835 @@ mod tests {
         assert_eq!(StatusCode::FOUND, response.status());
     }
 
-    #[actix_web::test]
-    async fn test_index_with_landing_page() -> Result<(), Error> {
-        let config = Config {
-            landing_page: Some(LandingPageConfig {
-                text: Some(String::from("landing page")),
-                ..Default::default()
-            }),
-            ..Default::default()
-        };
+    #[actix_web::test]
+    async fn test_index_with_landing_page() -> Result<(), Error> {
+        let config = Config {
+            landing_page: Some(LandingPageConfig {
+                text: Some(String::from("landing page")),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .service(index),
+        )
+        .await;
+        let request = TestRequest::default()
+            .insert_header(("content-type", "text/plain"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "landing page").await?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_index_with_landing_page_per_host() -> Result<(), Error> {
+        let config = Config {
+            landing_page: Some(LandingPageConfig {
+                text: Some(String::from("default page")),
+                hosts: [
+                    (
+                        String::from("a.example.com"),
+                        LandingPageConfig {
+                            text: Some(String::from("page a")),
+                            ..Default::default()
+                        },
+                    ),
+                    (
+                        String::from("b.example.com"),
+                        LandingPageConfig {
+                            text: Some(String::from("<b>page b</b>")),
+                            content_type: Some(String::from("text/html; charset=utf-8")),
+                            ..Default::default()
+                        },
+                    ),
+                ]
+                .into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .service(index),
+        )
+        .await;
+
+        let request = TestRequest::default()
+            .insert_header((header::HOST, "a.example.com"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "page a").await?;
+
+        let request = TestRequest::default()
+            .insert_header((header::HOST, "b.example.com:8000"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some("text/html; charset=utf-8"),
+            response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+        );
+        assert_body(response.into_body(), "<b>page b</b>").await?;
+
+        let request = TestRequest::default()
+            .insert_header((header::HOST, "c.example.com"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "default page").await?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_index_with_landing_page_file() -> Result<(), Error> {
+        let filename = "landing_page.txt";
+        let config = Config {
+            landing_page: Some(LandingPageConfig {
+                file: Some(filename.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut file = File::create(filename)?;
+        file.write_all("landing page from file".as_bytes())?;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .service(index),
+        )
+        .await;
+        let request = TestRequest::default()
+            .insert_header(("content-type", "text/plain"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "landing page from file").await?;
+        fs::remove_file(filename)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_index_with_landing_page_file_not_found() -> Result<(), Error> {
+        let filename = "landing_page.txt";
+        let config = Config {
+            landing_page: Some(LandingPageConfig {
+                text: Some(String::from("landing page")),
+                file: Some(filename.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .service(index),
+        )
+        .await;
+        let request = TestRequest::default()
+            .insert_header(("content-type", "text/plain"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::FOUND, response.status());
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_robots_txt() -> Result<(), Error> {
+        let test_upload_dir = "test_robots_upload";
+        fs::create_dir(test_upload_dir)?;
+        fs::write(PathBuf::from(test_upload_dir).join("robots.txt"), "upload")?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.auth_tokens = Some(["test".to_string()].into());
+        let config = Data::new(RwLock::new(config));
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::clone(&config))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::get().uri("/robots.txt").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "User-agent: *\nDisallow: /\n").await?;
+
+        config
+            .write()
+            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+            .server
+            .robots_txt = Some(String::from("User-agent: *\nAllow: /\n"));
+        let request = TestRequest::get().uri("/robots.txt").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "User-agent: *\nAllow: /\n").await?;
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_version_without_auth() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.auth_tokens = Some(["test".to_string()].into());
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::default()
+            .insert_header(("content-type", "text/plain"))
+            .uri("/version")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+        assert_body(response.into_body(), "unauthorized\n").await?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_version_without_config() -> Result<(), Error> {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(Config::default())))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::default()
+            .insert_header(("content-type", "text/plain"))
+            .uri("/version")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+        assert_body(response.into_body(), "").await?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_version() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.expose_version = Some(true);
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::default()
+            .insert_header(("content-type", "text/plain"))
+            .uri("/version")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(
+            response.into_body(),
+            &(env!("CARGO_PKG_VERSION").to_owned() + "\n"),
+        )
+        .await?;
+
+        let request = TestRequest::default()
+            .insert_header((header::ACCEPT, "application/json"))
+            .uri("/version")
+            .to_request();
+        let result: VersionInfo = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(env!("CARGO_PKG_VERSION"), result.version);
+        assert_eq!(env!("BUILD_GIT_COMMIT"), result.commit);
+        assert!(!result.commit.is_empty());
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_debug_config() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.auth_tokens = Some(["super_secret_token".to_string()].into());
+        config.server.delete_tokens = Some(["super_secret_delete_token".to_string()].into());
+        config.server.upload_path = PathBuf::from("secret_upload_path");
+        config.server.pause_file = Some(PathBuf::from("secret_pause_file"));
+        config.scan = Some(ScanConfig {
+            address: String::from("/run/secret_clamd.ctl"),
+            ..Default::default()
+        });
+        config.server.expose_config = Some(true);
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::default()
+            .insert_header((
+                AUTHORIZATION,
+                header::HeaderValue::from_static("super_secret_token"),
+            ))
+            .uri("/debug/config")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let body_bytes = actix_web::body::to_bytes(response.into_body()).await?;
+        let body_text = str::from_utf8(&body_bytes)?;
+        assert!(body_text.contains(r#""auth_tokens":["***"]"#));
+        assert!(body_text.contains(r#""delete_tokens":["***"]"#));
+        assert!(body_text.contains(r#""upload_path":"***""#));
+        assert!(body_text.contains(r#""pause_file":"***""#));
+        assert!(body_text.contains(r#""address":"***""#));
+        assert!(!body_text.contains("super_secret"));
+        for path in ["secret_upload_path", "secret_pause_file", "secret_clamd"] {
+            assert!(!body_text.contains(path));
+        }
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_debug_config_not_exposed() -> Result<(), Error> {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(Config::default())))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::default().uri("/debug/config").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_openapi_spec() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.expose_openapi = Some(true);
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::default().uri("/openapi.json").to_request();
+        let spec: serde_json::Value = test::call_and_read_body_json(&app, request).await;
+        assert_eq!("http://localhost:8080", spec["servers"][0]["url"]);
+        assert!(spec["paths"]["/"]["post"]["requestBody"]["content"]
+            .get("multipart/form-data")
+            .is_some());
+        assert!(spec["paths"]["/list"]["get"]["responses"]
+            .get("200")
+            .is_some());
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(Config::default())))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+        let request = TestRequest::default().uri("/openapi.json").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_list() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.expose_list = Some(true);
+
+        let test_upload_dir = "test_upload";
+        fs::create_dir(test_upload_dir)?;
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let filename = "test_file.txt";
+        let timestamp = util::get_system_time()?.as_secs().to_string();
+        test::call_service(
+            &app,
+            get_multipart_request(&timestamp, "file", filename).to_request(),
+        )
+        .await;
+
+        let request = TestRequest::default()
+            .insert_header(("content-type", "text/plain"))
+            .uri("/list")
+            .to_request();
+        let result: Vec<ListItem> = test::call_and_read_body_json(&app, request).await;
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(
+            result.first().expect("json object").file_name,
+            PathBuf::from(filename)
+        );
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_list_etag() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.expose_list = Some(true);
+
+        let test_upload_dir = "test_list_etag_upload";
+        fs::create_dir(test_upload_dir)?;
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let get_etag = |response: &ServiceResponse| {
+            response
+                .headers()
+                .get(header::ETAG)
+                .and_then(|v| v.to_str().ok())
+                .map(String::from)
+        };
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("a", "file", "a.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let request = TestRequest::get().uri("/list").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let etag = get_etag(&response).expect("ETag is not set");
+
+        let request = TestRequest::get()
+            .uri("/list")
+            .insert_header((IF_NONE_MATCH, etag.as_str()))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::NOT_MODIFIED, response.status());
+        assert_eq!(Some(etag.clone()), get_etag(&response));
+
+        // the tag depends on the format
+        let request = TestRequest::get()
+            .uri("/list?format=csv")
+            .insert_header((IF_NONE_MATCH, etag.as_str()))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("b", "file", "b.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let request = TestRequest::get()
+            .uri("/list")
+            .insert_header((IF_NONE_MATCH, etag.as_str()))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let new_etag = get_etag(&response).expect("ETag is not set");
+        assert_ne!(etag, new_etag);
+        let result: Vec<ListItem> = test::read_body_json(response).await;
+        assert_eq!(2, result.len());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_list_compressed() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.expose_list = Some(true);
+        config.server.compress_list = Some(true);
+
+        let test_upload_dir = "test_list_compressed_upload";
+        fs::create_dir(test_upload_dir)?;
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("a", "file", "a.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let request = TestRequest::get()
+            .uri("/list")
+            .insert_header((header::ACCEPT_ENCODING, "gzip"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some("gzip"),
+            response
+                .headers()
+                .get(header::CONTENT_ENCODING)
+                .and_then(|v| v.to_str().ok())
+        );
+        let body = test::read_body(response).await;
+        let mut decompressed = String::new();
+        flate2::read::GzDecoder::new(&body[..]).read_to_string(&mut decompressed)?;
+        let result: Vec<ListItem> = serde_json::from_str(&decompressed)?;
+        assert_eq!(1, result.len());
+
+        let request = TestRequest::get().uri("/list").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+        let result: Vec<ListItem> = test::read_body_json(response).await;
+        assert_eq!(1, result.len());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_manifest() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.expose_list = Some(true);
+        config.server.write_manifest = Some(true);
+        config.server.delete_tokens = Some(["may_the_force_be_with_you".to_string()].into());
+
+        let test_upload_dir = "test_manifest_upload";
+        fs::create_dir(test_upload_dir)?;
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let read_manifest = || -> Result<Vec<PathBuf>, Error> {
+            let manifest = fs::read(PathBuf::from(test_upload_dir).join(MANIFEST_FILE))?;
+            let mut file_names = serde_json::from_slice::<Vec<ListItem>>(&manifest)?
+                .into_iter()
+                .map(|v| v.file_name)
+                .collect::<Vec<PathBuf>>();
+            file_names.sort();
+            Ok(file_names)
+        };
+
+        for file_name in ["a.txt", "b.txt"] {
+            let response = test::call_service(
+                &app,
+                get_multipart_request(file_name, "file", file_name).to_request(),
+            )
+            .await;
+            assert_eq!(StatusCode::OK, response.status());
+        }
+        assert_eq!(
+            vec![PathBuf::from("a.txt"), PathBuf::from("b.txt")],
+            read_manifest()?
+        );
+        assert!(!PathBuf::from(test_upload_dir)
+            .join(MANIFEST_TEMP_FILE)
+            .exists());
+
+        let request = TestRequest::default().uri("/list").to_request();
+        let result: Vec<ListItem> = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(2, result.len());
+
+        let request = TestRequest::delete()
+            .insert_header((
+                AUTHORIZATION,
+                header::HeaderValue::from_static("may_the_force_be_with_you"),
+            ))
+            .uri("/a.txt")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(vec![PathBuf::from("b.txt")], read_manifest()?);
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_created_at() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.expose_list = Some(true);
+        config.server.auth_tokens = Some(["test_token".to_string()].into());
+
+        let test_upload_dir = "test_created_at_upload";
+        fs::create_dir(test_upload_dir)?;
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let config = Data::new(RwLock::new(config));
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::clone(&config))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("old", "file", "old.txt")
+                .insert_header((AUTHORIZATION, "test_token"))
+                .insert_header((crate::header::CREATED_AT, "2020-01-01T00:00:00Z"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::FORBIDDEN, response.status());
+
+        config
+            .write()
+            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+            .server
+            .allow_created_at = Some(true);
+        let response = test::call_service(
+            &app,
+            get_multipart_request("old", "file", "old.txt")
+                .insert_header((AUTHORIZATION, "test_token"))
+                .insert_header((crate::header::CREATED_AT, "2020-01-01T00:00:00Z"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let request = TestRequest::default()
+            .uri("/list")
+            .insert_header((AUTHORIZATION, "test_token"))
+            .to_request();
+        let result: Vec<ListItem> = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(1, result.len());
+        assert_eq!(Some(1577836800), result[0].creation_date_epoch);
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_list_stream() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.expose_list = Some(true);
+
+        let test_upload_dir = "test_list_stream_upload";
+        fs::create_dir(test_upload_dir)?;
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::default().uri("/list").to_request();
+        let body = test::call_and_read_body(&app, request).await;
+        assert_eq!("[]", str::from_utf8(&body)?);
+
+        let file_names = ["a.txt", "b.txt", "c.txt"];
+        for (i, file_name) in file_names.iter().enumerate() {
+            test::call_service(
+                &app,
+                get_multipart_request(&"x".repeat(i + 1), "file", file_name).to_request(),
+            )
+            .await;
+        }
+
+        let request = TestRequest::default().uri("/list").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some("application/json"),
+            response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+        );
+        let body = test::read_body(response).await;
+        let mut result: Vec<ListItem> =
+            serde_json::from_slice(&body).map_err(error::ErrorInternalServerError)?;
+        result.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+        assert_eq!(
+            file_names.iter().map(PathBuf::from).collect::<Vec<_>>(),
+            result
+                .iter()
+                .map(|v| v.file_name.clone())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![1, 2, 3],
+            result.iter().map(|v| v.file_size).collect::<Vec<_>>()
+        );
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_list_formats() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.expose_list = Some(true);
+
+        let test_upload_dir = "test_list_formats_upload";
+        fs::create_dir(test_upload_dir)?;
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        for file_name in ["a,b.txt", "c.txt"] {
+            let response = test::call_service(
+                &app,
+                get_multipart_request("test", "file", file_name).to_request(),
+            )
+            .await;
+            assert_eq!(StatusCode::OK, response.status());
+        }
+
+        let request = TestRequest::default().uri("/list?format=csv").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some("text/csv; charset=utf-8"),
+            response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+        );
+        let body = test::read_body(response).await;
+        let mut lines = str::from_utf8(&body)?.lines().collect::<Vec<&str>>();
+        assert_eq!(3, lines.len());
+        assert_eq!(LIST_CSV_HEADER.trim_end(), lines.remove(0));
+        lines.sort();
+        assert!(lines[0].starts_with("\"a,b.txt\",4,"));
+        assert!(lines[1].starts_with("c.txt,4,"));
+        assert_eq!(6, lines[1].split(',').count());
+
+        let request = TestRequest::default()
+            .uri("/list")
+            .insert_header((header::ACCEPT, "application/x-ndjson"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some("application/x-ndjson"),
+            response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+        );
+        let body = test::read_body(response).await;
+        let mut file_names = str::from_utf8(&body)?
+            .lines()
+            .map(|line| {
+                serde_json::from_str::<ListItem>(line)
+                    .map(|v| v.file_name)
+                    .map_err(error::ErrorInternalServerError)
+            })
+            .collect::<Result<Vec<PathBuf>, Error>>()?;
+        file_names.sort();
+        assert_eq!(
+            vec![PathBuf::from("a,b.txt"), PathBuf::from("c.txt")],
+            file_names
+        );
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_list_display_timezone() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.expose_list = Some(true);
+        config.server.display_timezone = Some(chrono_tz::Tz::Asia__Kolkata);
+
+        let test_upload_dir = "test_list_timezone_upload";
+        fs::create_dir(test_upload_dir)?;
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        test::call_service(
+            &app,
+            get_multipart_request("test", "file", "test_file.txt")
+                .insert_header((
+                    header::HeaderName::from_static("expire"),
+                    header::HeaderValue::from_static("1h"),
+                ))
+                .to_request(),
+        )
+        .await;
+
+        let request = TestRequest::default()
+            .insert_header(("content-type", "text/plain"))
+            .uri("/list")
+            .to_request();
+        let result: Vec<ListItem> = test::call_and_read_body_json(&app, request).await;
+        let item = result.first().expect("json object");
+
+        let expires_at_epoch = item.expires_at_epoch.expect("expiration timestamp");
+        let expected_epoch = i64::try_from(util::get_system_time()?.as_secs())
+            .map_err(error::ErrorInternalServerError)?
+            + 3600;
+        assert!((expected_epoch - expires_at_epoch).abs() <= 5);
+        assert_eq!(
+            Some(util::format_timestamp(
+                expires_at_epoch,
+                Some(chrono_tz::Tz::Asia__Kolkata)
+            )),
+            item.expires_at_utc
+        );
+        assert!(item
+            .expires_at_utc
+            .as_ref()
+            .is_some_and(|v| v.ends_with("+0530")));
+        assert_ne!(
+            Some(util::format_timestamp(expires_at_epoch, None)),
+            item.expires_at_utc
+        );
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_signed_url() -> Result<(), Error> {
+        let test_upload_dir = "test_signed_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.auth_tokens = Some(["test".to_string()].into());
+        config.server.signing_secret = Some(String::from("secret"));
+        config.server.auth = Some(AuthConfig {
+            read: true,
+            ..Default::default()
+        });
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let file_name = "test_file.txt";
+        let response = test::call_service(
+            &app,
+            get_multipart_request("signed", "file", file_name)
+                .insert_header((
+                    header::AUTHORIZATION,
+                    header::HeaderValue::from_static("test"),
+                ))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let request = TestRequest::get()
+            .uri(&format!("/{file_name}"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+        let request = TestRequest::get()
+            .uri(&format!("/sign?file={file_name}"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+        let request = TestRequest::get()
+            .insert_header((
+                header::AUTHORIZATION,
+                header::HeaderValue::from_static("test"),
+            ))
+            .uri(&format!("/sign?file={file_name}&expire=1h"))
+            .to_request();
+        let body = test::call_and_read_body(&app, request).await;
+        let signed_url = str::from_utf8(&body)?.trim_end().to_string();
+        let signed_path = signed_url
+            .strip_prefix("http://localhost:8080")
+            .expect("invalid signed URL");
+
+        let request = TestRequest::get().uri(signed_path).to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "signed").await?;
+
+        let expiration = util::get_system_time()?.as_secs() - 1;
+        let signature = auth::sign_path("secret", file_name, expiration);
+        let request = TestRequest::get()
+            .uri(&format!("/{file_name}?exp={expiration}&sig={signature}"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::FORBIDDEN, response.status());
+
+        let tampered_path = signed_path.replace("sig=", "sig=00");
+        let request = TestRequest::get().uri(&tampered_path).to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::FORBIDDEN, response.status());
+
+        let file_name = "test file.txt";
+        let response = test::call_service(
+            &app,
+            get_multipart_request("encoded", "file", file_name)
+                .insert_header((
+                    header::AUTHORIZATION,
+                    header::HeaderValue::from_static("test"),
+                ))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let request = TestRequest::get()
+            .insert_header((
+                header::AUTHORIZATION,
+                header::HeaderValue::from_static("test"),
+            ))
+            .uri("/sign?file=test%20file.txt")
+            .to_request();
+        let body = test::call_and_read_body(&app, request).await;
+        let signed_url = str::from_utf8(&body)?.trim_end().to_string();
+        let signed_path = signed_url
+            .strip_prefix("http://localhost:8080")
+            .expect("invalid signed URL");
+        assert!(signed_path.starts_with("/test%20file.txt?"));
+
+        let request = TestRequest::get().uri(signed_path).to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "encoded").await?;
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_list_expired() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.expose_list = Some(true);
+
+        let test_upload_dir = "test_upload";
+        fs::create_dir(test_upload_dir)?;
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let filename = "test_file.txt";
+        let timestamp = util::get_system_time()?.as_secs().to_string();
+        test::call_service(
+            &app,
+            get_multipart_request(&timestamp, "file", filename)
+                .insert_header((
+                    header::HeaderName::from_static("expire"),
+                    header::HeaderValue::from_static("50ms"),
+                ))
+                .to_request(),
+        )
+        .await;
+
+        thread::sleep(Duration::from_millis(500));
+
+        let request = TestRequest::default()
+            .insert_header(("content-type", "text/plain"))
+            .uri("/list")
+            .to_request();
+        let result: Vec<ListItem> = test::call_and_read_body_json(&app, request).await;
+
+        assert!(result.is_empty());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_extend() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.expose_list = Some(true);
+        config.paste.max_expiry = Some(Duration::from_secs(24 * 60 * 60));
+        config.server.delete_tokens = Some(["delete".to_string()].into());
+
+        let test_upload_dir = "test_extend_upload";
+        fs::create_dir(test_upload_dir)?;
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let get_expiry = |items: &[ListItem], file_name: &str| {
+            items
+                .iter()
+                .find(|v| v.file_name == Path::new(file_name))
+                .and_then(|v| v.expires_at_epoch)
+        };
+        let list_request = || {
+            TestRequest::default()
+                .insert_header(("content-type", "text/plain"))
+                .uri("/list")
+                .to_request()
+        };
+
+        let expiring_file = "expiring.txt";
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", expiring_file)
+                .insert_header((
+                    header::HeaderName::from_static("expire"),
+                    header::HeaderValue::from_static("1h"),
+                ))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let permanent_file = "permanent.txt";
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", permanent_file).to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let now = i64::try_from(util::get_system_time()?.as_secs())
+            .expect("UNIX time should be smaller than i64::MAX");
+        let result: Vec<ListItem> = test::call_and_read_body_json(&app, list_request()).await;
+        let expiry = get_expiry(&result, expiring_file).expect("file should expire");
+        assert!((now + 3600 - expiry).abs() <= 1);
+
+        let request = TestRequest::post()
+            .uri(&format!("/{expiring_file}/extend"))
+            .insert_header((
+                header::HeaderName::from_static("expire"),
+                header::HeaderValue::from_static("2h"),
+            ))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "file expiry extended\n").await?;
+
+        let request = TestRequest::post()
+            .uri(&format!("/{expiring_file}/extend"))
+            .set_payload("1h")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::FORBIDDEN, response.status());
+        assert_body(response.into_body(), "expiry time cannot be shortened\n").await?;
+
+        let request = TestRequest::post()
+            .uri(&format!("/{permanent_file}/extend"))
+            .set_payload("3h")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::FORBIDDEN, response.status());
+
+        let request = TestRequest::post()
+            .uri(&format!("/{permanent_file}/extend"))
+            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("delete")))
+            .set_payload("3h")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let result: Vec<ListItem> = test::call_and_read_body_json(&app, list_request()).await;
+        assert_eq!(2, result.len());
+        let expiry = get_expiry(&result, expiring_file).expect("file should expire");
+        assert!((now + 2 * 3600 - expiry).abs() <= 1);
+        let expiry = get_expiry(&result, permanent_file).expect("file should expire");
+        assert!((now + 3 * 3600 - expiry).abs() <= 1);
+
+        let request = TestRequest::post()
+            .uri(&format!("/{expiring_file}/extend"))
+            .set_payload("2d")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        assert_body(response.into_body(), "expiry time cannot exceed 1day\n").await?;
+
+        let request = TestRequest::post()
+            .uri("/missing.txt/extend")
+            .set_payload("1h")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_import() -> Result<(), Error> {
+        let test_upload_dir = "test_import_upload";
+        let import_dir = PathBuf::from(test_upload_dir).join(paste::IMPORT_DIR);
+        fs::create_dir_all(&import_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.expose_list = Some(true);
+        config.server.delete_tokens = Some(["admin".to_string()].into());
+        config.paste.duplicate_files = Some(false);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        fs::write(import_dir.join("migrated.txt"), "test")?;
+        let request = TestRequest::post().uri("/import").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+        assert!(import_dir.join("migrated.txt").exists());
+
+        let request = TestRequest::post()
+            .uri("/import")
+            .insert_header((
+                header::AUTHORIZATION,
+                header::HeaderValue::from_static("admin"),
+            ))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "1 file(s) imported\nmigrated.txt\n").await?;
+        assert!(!import_dir.join("migrated.txt").exists());
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", "duplicate.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "http://localhost:8080/migrated.txt\n").await?;
+        assert!(!PathBuf::from(test_upload_dir)
+            .join("duplicate.txt")
+            .exists());
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("uploaded", "file", "uploaded.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        fs::write(import_dir.join("imported.txt"), "imported")?;
+        fs::write(import_dir.join("migrated.txt"), "conflict")?;
+        let request = TestRequest::post()
+            .uri("/import")
+            .insert_header((
+                header::AUTHORIZATION,
+                header::HeaderValue::from_static("admin"),
+            ))
+            .insert_header((
+                header::HeaderName::from_static("expire"),
+                header::HeaderValue::from_static("1h"),
+            ))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "1 file(s) imported\nimported.txt\n").await?;
+        assert!(import_dir.join("migrated.txt").exists());
+
+        let request = TestRequest::default().uri("/list").to_request();
+        let mut result: Vec<ListItem> = test::call_and_read_body_json(&app, request).await;
+        result.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+        assert_eq!(
+            vec![
+                (PathBuf::from("imported.txt"), true),
+                (PathBuf::from("migrated.txt"), false),
+                (PathBuf::from("uploaded.txt"), false),
+            ],
+            result
+                .into_iter()
+                .map(|v| (v.file_name, v.expires_at_epoch.is_some()))
+                .collect::<Vec<_>>()
+        );
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_auth() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.auth_tokens = Some(["test".to_string()].into());
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let response =
+            test::call_service(&app, get_multipart_request("", "", "").to_request()).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+        assert_body(response.into_body(), "unauthorized\n").await?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_auth_failure_limit() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.auth_tokens = Some(["test".to_string()].into());
+        config.server.expose_version = Some(true);
+        config.server.auth_failure_limit = Some(AuthFailureLimitConfig {
+            max_attempts: 3,
+            window: Duration::from_secs(60),
+            block_duration: Duration::from_secs(60),
+            delay: Some(Duration::from_millis(50)),
+        });
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .app_data(Data::new(AuthFailureTracker::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let version_request = |peer_addr: &str, token: &'static str| {
+            TestRequest::get()
+                .uri("/version")
+                .peer_addr(peer_addr.parse().expect("invalid address"))
+                .insert_header((AUTHORIZATION, header::HeaderValue::from_static(token)))
+                .to_request()
+        };
+
+        let response = test::call_service(&app, version_request("10.0.0.1:1234", "invalid")).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+        let start = Instant::now();
+        let response = test::call_service(&app, version_request("10.0.0.1:1234", "invalid")).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        let response = test::call_service(&app, version_request("10.0.0.1:1234", "invalid")).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+        let response = test::call_service(&app, version_request("10.0.0.1:1234", "test")).await;
+        assert_eq!(StatusCode::TOO_MANY_REQUESTS, response.status());
+
+        let start = Instant::now();
+        let response = test::call_service(&app, version_request("10.0.0.2:1234", "test")).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(start.elapsed() < Duration::from_millis(50));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_auth_write_only() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?;
+        config.server.auth_tokens = Some(["test".to_string()].into());
+        config.server.auth = Some(AuthConfig {
+            read: false,
+            write: true,
+            list: false,
+            ..Default::default()
+        });
+        config.server.expose_version = Some(true);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let file_name = "test_file.txt";
+        let timestamp = util::get_system_time()?.as_secs().to_string();
+        let response = test::call_service(
+            &app,
+            get_multipart_request(&timestamp, "file", file_name).to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request(&timestamp, "file", file_name)
+                .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let serve_request = TestRequest::get()
+            .uri(&format!("/{file_name}"))
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), &timestamp).await?;
+
+        let request = TestRequest::get().uri("/version").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        fs::remove_file(file_name)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_auth_read_paths() -> Result<(), Error> {
+        let test_upload_dir = "test_read_paths_upload";
+        for dir in ["private", "public"] {
+            fs::create_dir_all(PathBuf::from(test_upload_dir).join(dir))?;
+            fs::write(
+                PathBuf::from(test_upload_dir).join(dir).join("file.txt"),
+                dir,
+            )?;
+        }
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.max_path_depth = Some(2);
+        config.server.auth_tokens = Some(["test".to_string()].into());
+        config.server.auth = Some(AuthConfig {
+            read: false,
+            read_paths: [(String::from("private"), true)].into(),
+            ..Default::default()
+        });
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::get().uri("/public/file.txt").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "public").await?;
+
+        for uri in [
+            "/private/file.txt",
+            "/%70rivate/file.txt",
+            "//private/file.txt",
+        ] {
+            let request = TestRequest::get().uri(uri).to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+        }
+
+        let request = TestRequest::get()
+            .uri("/private/file.txt")
+            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "private").await?;
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_payload_limit() -> Result<(), Error> {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(Config::default())))
+                .app_data(Data::new(Client::default()))
+                .wrap(ContentLengthLimiter::new(Byte::from_u64(1)))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", "test").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+        assert_body(
+            response.into_body().boxed(),
+            "upload limit exceeded, limit is 1 B",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_payload_limit_without_content_length() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .wrap(ContentLengthLimiter::new(Byte::from_u64(300)))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let file_name = "test_file.txt";
+        let mut request = get_multipart_request(&"x".repeat(400), "file", file_name).to_request();
+        request.headers_mut().remove(header::CONTENT_LENGTH);
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+        assert_body(
+            response.into_body().boxed(),
+            "upload limit exceeded, limit is 300 B",
+        )
+        .await?;
+        assert!(!PathBuf::from(file_name).exists());
+
+        let mut request = get_multipart_request("x", "file", file_name).to_request();
+        request.headers_mut().remove(header::CONTENT_LENGTH);
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        fs::remove_file(file_name)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_delete_file() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.delete_tokens = Some(["test".to_string()].into());
+        config.server.upload_path = env::current_dir()?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let file_name = "test_file.txt";
+        let timestamp = util::get_system_time()?.as_secs().to_string();
+        test::call_service(
+            &app,
+            get_multipart_request(&timestamp, "file", file_name).to_request(),
+        )
+        .await;
+
+        let request = TestRequest::delete()
+            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+            .uri(&format!("/{file_name}"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "file deleted\n").await?;
+
+        let path = PathBuf::from(file_name);
+        assert!(!path.exists());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_log_redact_filenames() -> Result<(), Error> {
+        /// Writer for capturing the logs.
+        #[derive(Clone, Default)]
+        struct LogWriter(std::sync::Arc<Mutex<Vec<u8>>>);
+
+        impl std::io::Write for LogWriter {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0
+                    .lock()
+                    .map_err(|_| std::io::Error::other("cannot acquire logs"))?
+                    .extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let test_upload_dir = "test_redact_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.delete_tokens = Some(["test".to_string()].into());
+        config.server.log_redact_filenames = Some(true);
+
+        let writer = LogWriter::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::INFO)
+            .with_writer({
+                let writer = writer.clone();
+                move || writer.clone()
+            })
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let file_name = "passwords.txt";
+        let response = test::call_service(
+            &app,
+            get_multipart_request("secret", "file", file_name).to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(
+            response.into_body(),
+            &format!("http://localhost:8080/{file_name}\n"),
+        )
+        .await?;
+
+        let request = TestRequest::delete()
+            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+            .uri(&format!("/{file_name}"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let logs = String::from_utf8(
+            writer
+                .0
+                .lock()
+                .map_err(|_| error::ErrorInternalServerError("cannot acquire logs"))?
+                .clone(),
+        )
+        .map_err(error::ErrorInternalServerError)?;
+        assert!(logs.contains("is uploaded from"));
+        assert!(logs.contains("deleted file"));
+        assert!(!logs.contains("passwords"));
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_delete_url() -> Result<(), Error> {
+        let test_upload_dir = "test_delete_url_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.delete_url_secret = Some(String::from("secret"));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request_with_files(&[("a", "a.txt"), ("b", "b.txt")]).to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        let delete_urls = response
+            .headers()
+            .get_all(crate::header::DELETE_URL)
+            .filter_map(|v| v.to_str().ok())
+            .map(String::from)
+            .collect::<Vec<String>>();
+        assert_eq!(2, delete_urls.len());
+        let token = delete_urls[0]
+            .strip_prefix("http://localhost:8080/a.txt?token=")
+            .expect("invalid delete URL");
+        assert!(delete_urls[1].starts_with("http://localhost:8080/b.txt?token="));
+
+        let request = TestRequest::delete()
+            .uri(&format!("/b.txt?token={token}"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::FORBIDDEN, response.status());
+        assert!(PathBuf::from(test_upload_dir).join("b.txt").exists());
+
+        let request = TestRequest::delete().uri("/a.txt").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+        let request = TestRequest::delete()
+            .uri(&format!("/a.txt?token={token}"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "file deleted\n").await?;
+        assert!(!PathBuf::from(test_upload_dir).join("a.txt").exists());
+
+        // the token is not valid for another file with the same name
+        let response = test::call_service(
+            &app,
+            get_multipart_request("other", "file", "a.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        let request = TestRequest::delete()
+            .uri(&format!("/a.txt?token={token}"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::FORBIDDEN, response.status());
+        assert!(PathBuf::from(test_upload_dir).join("a.txt").exists());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_delete_matching_files() -> Result<(), Error> {
+        let test_upload_dir = "test_delete_matching_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.delete_tokens = Some(["test".to_string()].into());
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.pause_file = Some(PathBuf::from(test_upload_dir).join("paused.txt"));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        for file_name in [
+            "logs-1.txt",
+            "logs-2.txt",
+            "logs.txt",
+            "other-1.txt",
+            "paused.txt",
+            MANIFEST_FILE,
+        ] {
+            fs::write(PathBuf::from(test_upload_dir).join(file_name), "test")?;
+        }
+
+        let request = TestRequest::delete()
+            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+            .uri("/logs-*.txt?glob=true")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(
+            response.into_body(),
+            "2 file(s) deleted\nlogs-1.txt\nlogs-2.txt\n",
+        )
+        .await?;
+        assert!(!PathBuf::from(test_upload_dir).join("logs-1.txt").exists());
+        assert!(!PathBuf::from(test_upload_dir).join("logs-2.txt").exists());
+        assert!(PathBuf::from(test_upload_dir).join("logs.txt").exists());
+        assert!(PathBuf::from(test_upload_dir).join("other-1.txt").exists());
+
+        let request = TestRequest::delete()
+            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+            .uri("/logs-*.txt?glob=true")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+        let request = TestRequest::delete()
+            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+            .uri("/..%5C*?glob=true")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+        let request = TestRequest::delete()
+            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+            .uri("/other-*.txt")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+        assert!(PathBuf::from(test_upload_dir).join("other-1.txt").exists());
+
+        let request = TestRequest::delete()
+            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+            .uri("/*?glob=true")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(
+            response.into_body(),
+            "2 file(s) deleted\nlogs.txt\nother-1.txt\n",
+        )
+        .await?;
+        assert!(PathBuf::from(test_upload_dir).join("paused.txt").exists());
+        assert!(PathBuf::from(test_upload_dir).join(MANIFEST_FILE).exists());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_purge_files() -> Result<(), Error> {
+        let test_upload_dir = "test_purge_upload";
+        fs::create_dir_all(PathBuf::from(test_upload_dir).join("oneshot"))?;
+
+        let mut config = Config::default();
+        config.server.delete_tokens = Some(["test".to_string()].into());
+        config.server.delete_url_secret = Some(String::from("secret"));
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let old_time = SystemTime::now() - Duration::from_secs(2 * 60 * 60);
+        for (file_name, size, modified) in [
+            ("old-small.txt", 10, Some(old_time)),
+            ("old-large.txt", 2000, Some(old_time)),
+            ("new-large.txt", 2000, None),
+            ("oneshot/old.txt", 10, Some(old_time)),
+        ] {
+            let path = PathBuf::from(test_upload_dir).join(file_name);
+            fs::write(&path, "x".repeat(size))?;
+            if let Some(modified) = modified {
+                fs::File::options()
+                    .write(true)
+                    .open(&path)?
+                    .set_modified(modified)?;
+            }
+        }
+
+        // deletion URL tokens are not accepted for purging
+        let request = TestRequest::delete()
+            .uri("/purge?larger_than=1B&token=x")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+        let request = TestRequest::delete()
+            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+            .uri("/purge?larger_than=1B&token=x")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+        for (query, status, expected) in [
+            ("", StatusCode::BAD_REQUEST, "no criteria is given\n"),
+            (
+                "?older_than=1h&dry_run=true",
+                StatusCode::OK,
+                "3 file(s) matched\nold-large.txt\nold-small.txt\noneshot/old.txt\n",
+            ),
+            (
+                "?older_than=1h&larger_than=1KB",
+                StatusCode::OK,
+                "1 file(s) purged\nold-large.txt\n",
+            ),
+            (
+                "?larger_than=1KB",
+                StatusCode::OK,
+                "1 file(s) purged\nnew-large.txt\n",
+            ),
+        ] {
+            let request = TestRequest::delete()
+                .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+                .uri(&format!("/purge{query}"))
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(status, response.status());
+            assert_body(response.into_body(), expected).await?;
+        }
+
+        assert!(PathBuf::from(test_upload_dir)
+            .join("old-small.txt")
+            .exists());
+        assert!(PathBuf::from(test_upload_dir)
+            .join("oneshot/old.txt")
+            .exists());
+        assert!(!PathBuf::from(test_upload_dir)
+            .join("old-large.txt")
+            .exists());
+        assert!(!PathBuf::from(test_upload_dir)
+            .join("new-large.txt")
+            .exists());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_delete_file_without_token_in_config() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let file_name = "test_file.txt";
+        let request = TestRequest::delete()
+            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+            .uri(&format!("/{file_name}"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+        assert_body(response.into_body(), "").await?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_file() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let file_name = "test_file.txt";
+        let timestamp = util::get_system_time()?.as_secs().to_string();
+        let response = test::call_service(
+            &app,
+            get_multipart_request(&timestamp, "file", file_name).to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(
+            response.into_body(),
+            &format!("http://localhost:8080/{file_name}\n"),
+        )
+        .await?;
+
+        let serve_request = TestRequest::get()
+            .uri(&format!("/{file_name}"))
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), &timestamp).await?;
+
+        fs::remove_file(file_name)?;
+        let serve_request = TestRequest::get()
+            .uri(&format!("/{file_name}"))
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_paused() -> Result<(), Error> {
+        let test_upload_dir = "test_paused_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let pause_file = PathBuf::from(test_upload_dir).join(".paused");
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.pause_file = Some(pause_file.clone());
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        fs::write(&pause_file, "")?;
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", "paused.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, response.status());
+        assert_body(response.into_body(), "uploads are paused\n").await?;
+        assert!(!PathBuf::from(test_upload_dir).join("paused.txt").exists());
+
+        fs::remove_file(&pause_file)?;
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", "paused.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "http://localhost:8080/paused.txt\n").await?;
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_blank_content() -> Result<(), Error> {
+        let test_upload_dir = "test_blank_content_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.paste.reject_blank_content = Some(true);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        for data in [" ", "\n", " \t\r\n "] {
+            let response = test::call_service(
+                &app,
+                get_multipart_request(data, "file", "blank.txt").to_request(),
+            )
+            .await;
+            assert_eq!(StatusCode::BAD_REQUEST, response.status());
+            assert_body(response.into_body(), "invalid file content").await?;
+        }
+        assert!(!PathBuf::from(test_upload_dir).join("blank.txt").exists());
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request(" test\n", "file", "blank.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "http://localhost:8080/blank.txt\n").await?;
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_content_digest() -> Result<(), Error> {
+        let test_upload_dir = "test_content_digest_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        for (name, value) in [
+            (
+                crate::header::DIGEST,
+                "sha-256=n4bQgYhMfWWaL+qgxVrQFaO/TxsrC4Is0V1sFbDwCgg=",
+            ),
+            (crate::header::CONTENT_MD5, "CY9rzUYh03PK3k6DJie09g=="),
+        ] {
+            let response = test::call_service(
+                &app,
+                get_multipart_request("tset", "file", "corrupted.txt")
+                    .insert_header((name, value))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(StatusCode::BAD_REQUEST, response.status());
+            assert_body(response.into_body(), "content digest mismatch\n").await?;
+            assert!(!PathBuf::from(test_upload_dir)
+                .join("corrupted.txt")
+                .exists());
+
+            let response = test::call_service(
+                &app,
+                get_multipart_request("test", "file", &format!("{name}.txt"))
+                    .insert_header((name, value))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_eq!(
+                "test",
+                fs::read_to_string(PathBuf::from(test_upload_dir).join(format!("{name}.txt")))?
+            );
+        }
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_strip_trailing_newline() -> Result<(), Error> {
+        let test_upload_dir = "test_strip_newline_upload";
+
+        for (strip_trailing_newline, expected) in [(true, "foo"), (false, "foo\n")] {
+            fs::create_dir(test_upload_dir)?;
+
+            let mut config = Config::default();
+            config.server.upload_path = PathBuf::from(test_upload_dir);
+            config.paste.strip_trailing_newline = Some(strip_trailing_newline);
+
+            let app = test::init_service(
+                App::new()
+                    .app_data(Data::new(RwLock::new(config)))
+                    .app_data(Data::new(Client::default()))
+                    .configure(configure_routes),
+            )
+            .await;
+
+            let response = test::call_service(
+                &app,
+                get_multipart_request("foo\n", "file", "foo.txt").to_request(),
+            )
+            .await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_eq!(
+                expected,
+                fs::read_to_string(PathBuf::from(test_upload_dir).join("foo.txt"))?
+            );
+
+            let response = test::call_service(
+                &app,
+                get_multipart_request_with_bytes(b"\x00\xff\n", "file", "foo.bin").to_request(),
+            )
+            .await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_eq!(
+                b"\x00\xff\n".to_vec(),
+                fs::read(PathBuf::from(test_upload_dir).join("foo.bin"))?
+            );
+
+            fs::remove_dir_all(test_upload_dir)?;
+        }
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_canonical_extension() -> Result<(), Error> {
+        let test_upload_dir = "test_canonical_extension_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.paste.canonical_extensions = Some(true);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        for (file_name, expected) in [("a.JPEG", "a.jpg"), ("b.Jpe", "b.jpg"), ("c.TXT", "c.txt")] {
+            let response = test::call_service(
+                &app,
+                get_multipart_request("test", "file", file_name).to_request(),
+            )
+            .await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_body(
+                response.into_body(),
+                &format!("http://localhost:8080/{expected}\n"),
+            )
+            .await?;
+        }
+
+        let response =
+            test::call_service(&app, TestRequest::get().uri("/a.jpg").to_request()).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            "image/jpeg",
+            response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+        );
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_large_url() -> Result<(), Error> {
+        let test_upload_dir = "test_large_url_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let url = format!("https://example.com/{}", "a".repeat(4 * 1024 * 1024));
+        for field in ["remote", "url", "oneshot_url"] {
+            let start = Instant::now();
+            let response =
+                test::call_service(&app, get_multipart_request(&url, field, "").to_request()).await;
+            assert_eq!(StatusCode::BAD_REQUEST, response.status());
+            assert_body(response.into_body(), "URL is too long\n").await?;
+            assert!(start.elapsed() < Duration::from_secs(5));
+        }
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_space_collision() -> Result<(), Error> {
+        let test_upload_dir = "test_space_collision_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.handle_spaces = Some(SpaceHandlingConfig::Replace);
+        config.server.avoid_space_collisions = Some(true);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        for (data, file_name, expected) in [
+            ("first", "a b.txt", "a_b.txt"),
+            ("second", "a_b.txt", "a_b-2.txt"),
+            ("third", "a b.txt", "a_b-3.txt"),
+        ] {
+            let response = test::call_service(
+                &app,
+                get_multipart_request(data, "file", file_name).to_request(),
+            )
+            .await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_body(
+                response.into_body(),
+                &format!("http://localhost:8080/{expected}\n"),
+            )
+            .await?;
+            assert_eq!(
+                data,
+                fs::read_to_string(PathBuf::from(test_upload_dir).join(expected))?
+            );
+        }
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_archive() -> Result<(), Error> {
+        let test_upload_dir = "test_archive_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.max_content_length = Byte::from_u64(10000);
+        config.archive = Some(ArchiveConfig {
+            enabled: true,
+            ..Default::default()
+        });
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let create_tar = |files: &[(&str, &str)]| -> Result<Vec<u8>, Error> {
+            let mut builder = tar::Builder::new(Vec::new());
+            for (path, data) in files {
+                let mut header = tar::Header::new_old();
+                header.as_old_mut().name[..path.len()].copy_from_slice(path.as_bytes());
+                header.set_entry_type(tar::EntryType::Regular);
+                header.set_size(data.len() as u64);
+                header.set_mode(0o644);
+                header.set_cksum();
+                builder.append(&header, data.as_bytes())?;
+            }
+            Ok(builder.into_inner()?)
+        };
+
+        let request = TestRequest::post()
+            .uri("/archive")
+            .set_payload(create_tar(&[("a.txt", "a"), ("../../b.txt", "b")])?)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        assert_eq!(0, fs::read_dir(test_upload_dir)?.count());
+
+        let request = TestRequest::post()
+            .uri("/archive")
+            .set_payload(create_tar(&[("a.txt", "a"), ("dir/b.txt", "b")])?)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(
+            response.into_body(),
+            "http://localhost:8080/a.txt\nhttp://localhost:8080/b.txt\n",
+        )
+        .await?;
+
+        for (file_name, content) in [("a.txt", "a"), ("b.txt", "b")] {
+            let serve_request = TestRequest::get()
+                .uri(&format!("/{file_name}"))
+                .to_request();
+            let response = test::call_service(&app, serve_request).await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_body(response.into_body(), content).await?;
+        }
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_form() -> Result<(), Error> {
+        let test_upload_dir = "test_form_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.max_content_length = Byte::from_u64(1000);
+        config.paste.default_extension = String::from("txt");
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        for (payload, file_name) in [
+            ("content=hello%20world&extension=md", "paste.md"),
+            ("content=hello+world&name=notes", "notes.txt"),
+        ] {
+            let request = TestRequest::post()
+                .uri("/")
+                .insert_header((
+                    header::CONTENT_TYPE,
+                    header::HeaderValue::from_static("application/x-www-form-urlencoded"),
+                ))
+                .set_payload(payload)
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_body(
+                response.into_body(),
+                &format!("http://localhost:8080/{file_name}\n"),
+            )
+            .await?;
+
+            let serve_request = TestRequest::get()
+                .uri(&format!("/{file_name}"))
+                .to_request();
+            let response = test::call_service(&app, serve_request).await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_body(response.into_body(), "hello world").await?;
+        }
+
+        let request = TestRequest::post()
+            .uri("/")
+            .insert_header((
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/x-www-form-urlencoded"),
+            ))
+            .set_payload("content=test&extension=..%2Fmd")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_serve_nested_file() -> Result<(), Error> {
+        let mut config = Config::default();
+        let test_upload_dir = "test_nested_file_upload";
+        fs::create_dir_all(PathBuf::from(test_upload_dir).join("sub"))?;
+        fs::write(
+            PathBuf::from(test_upload_dir)
+                .join("sub")
+                .join("test_file.txt"),
+            "nested",
+        )?;
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.max_path_depth = Some(2);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let serve_request = TestRequest::get().uri("/sub/test_file.txt").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "nested").await?;
+
+        let serve_request = TestRequest::get()
+            .uri("/sub/deeper/test_file.txt")
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        assert_body(response.into_body(), "path is too deep\n").await?;
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_response_format() -> Result<(), Error> {
+        let test_upload_dir = "test_response_format_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        for (query, file_name, expected) in [
+            ("", "a.txt", "http://localhost:8080/a.txt\n"),
+            ("?format=plain", "b.txt", "http://localhost:8080/b.txt\n"),
+            ("?format=url-only", "c.txt", "http://localhost:8080/c.txt"),
+            (
+                "?format=share",
+                "it's.txt",
+                "curl -o 'it'\\''s.txt' 'http://localhost:8080/it'\\''s.txt'\n",
+            ),
+            (
+                "?format=markdown",
+                "image.png",
+                "![image.png](http://localhost:8080/image.png)\n",
+            ),
+            (
+                "?format=markdown",
+                "j.txt",
+                "[j.txt](http://localhost:8080/j.txt)\n",
+            ),
+            (
+                "?format=html",
+                "image.jpg",
+                "<img src=\"http://localhost:8080/image.jpg\" alt=\"image.jpg\">\n",
+            ),
+            (
+                "?format=html",
+                "k.txt",
+                "<a href=\"http://localhost:8080/k.txt\">k.txt</a>\n",
+            ),
+            (
+                "?format=bbcode",
+                "image.gif",
+                "[img]http://localhost:8080/image.gif[/img]\n",
+            ),
+            (
+                "?format=bbcode",
+                "l.txt",
+                "[url]http://localhost:8080/l.txt[/url]\n",
+            ),
+        ] {
+            let response = test::call_service(
+                &app,
+                get_multipart_request("test", "file", file_name)
+                    .uri(&format!("/{query}"))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_body(response.into_body(), expected).await?;
+        }
+
+        for (query, files, expected) in [
+            (
+                "?format=plain",
+                [("test", "d.txt"), ("test", "e.txt")],
+                "http://localhost:8080/d.txt\nhttp://localhost:8080/e.txt\n",
+            ),
+            (
+                "?format=url-only",
+                [("test", "f.txt"), ("test", "g.txt")],
+                "http://localhost:8080/f.txt\nhttp://localhost:8080/g.txt",
+            ),
+            (
+                "?format=share",
+                [("test", "h.txt"), ("test", "i.txt")],
+                "curl -o 'h.txt' 'http://localhost:8080/h.txt'\n\
+                 curl -o 'i.txt' 'http://localhost:8080/i.txt'\n",
+            ),
+        ] {
+            let response = test::call_service(
+                &app,
+                get_multipart_request_with_files(&files)
+                    .uri(&format!("/{query}"))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_body(response.into_body(), expected).await?;
+        }
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_per_field_headers() -> Result<(), Error> {
+        let test_upload_dir = "test_field_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let multipart_data = "\r\n\
+             --multipart_bound\r\n\
+             Content-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+             Content-Type: text/plain; charset=utf-8\r\nexpire: 1h\r\n\r\n\
+             a\r\n\
+             --multipart_bound\r\n\
+             Content-Disposition: form-data; name=\"file\"; filename=\"b.txt\"\r\n\
+             Content-Type: text/plain; charset=utf-8\r\nexpire: 2h\r\n\r\n\
+             b\r\n\
+             --multipart_bound\r\n\
+             Content-Disposition: form-data; name=\"file\"; filename=\"c.txt\"\r\n\
+             Content-Type: text/plain; charset=utf-8\r\nfilename: renamed.txt\r\n\r\n\
+             c\r\n\
+             --multipart_bound--\r\n";
+        let request = TestRequest::post()
+            .insert_header((
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("multipart/mixed; boundary=\"multipart_bound\""),
+            ))
+            .insert_header((
+                header::HeaderName::from_static("expire"),
+                header::HeaderValue::from_static("3h"),
+            ))
+            .set_payload(multipart_data)
+            .to_request();
+        let time = util::get_system_time()?;
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        for (file_name, expiry) in [("a.txt", 1), ("b.txt", 2), ("renamed.txt", 3)] {
+            let path = util::glob_match_file(PathBuf::from(test_upload_dir).join(file_name))?;
+            let expiration = path
+                .extension()
+                .and_then(|v| v.to_str())
+                .and_then(|v| v.parse::<u128>().ok())
+                .expect("file does not have an expiration");
+            let expected = time.as_millis() + expiry * 60 * 60 * 1000;
+            assert!(expiration >= expected && expiration < expected + 5000);
+        }
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_missing_upload_path() -> Result<(), Error> {
+        let test_upload_dir = "test_missing_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        let config = Data::new(RwLock::new(config));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(config.clone())
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let file_name = "test_file.txt";
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", file_name).to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        let serve_request = TestRequest::get()
+            .uri(&format!("/{file_name}"))
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, response.status());
+        assert_body(response.into_body(), "upload directory is not available\n").await?;
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", file_name).to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, response.status());
+        assert!(!PathBuf::from(test_upload_dir).exists());
+
+        config
+            .write()
+            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+            .server
+            .recreate_upload_path = Some(true);
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", file_name).to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(PathBuf::from(test_upload_dir).join(file_name).exists());
+        assert!(PasteType::Url
+            .get_path(&PathBuf::from(test_upload_dir))?
+            .exists());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_with_charset() -> Result<(), Error> {
+        let test_upload_dir = "test_charset_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.max_path_depth = Some(2);
+        config.server.delete_tokens = Some(["test".to_string()].into());
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let latin1_data = [b'c', b'a', b'f', 0xe9];
+        let response = test::call_service(
+            &app,
+            get_multipart_request_with_bytes(&latin1_data, "file", "latin1.txt")
+                .insert_header((
+                    header::HeaderName::from_static(crate::header::CHARSET),
+                    header::HeaderValue::from_static("iso-8859-1"),
+                ))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", "utf8.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        for (file_name, content_type) in [
+            ("latin1.txt", "text/plain; charset=iso-8859-1"),
+            ("utf8.txt", "text/plain; charset=utf-8"),
+        ] {
+            let serve_request = TestRequest::get()
+                .uri(&format!("/{file_name}"))
+                .to_request();
+            let response = test::call_service(&app, serve_request).await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_eq!(
+                Some(content_type),
+                response
+                    .headers()
+                    .get(header::CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+            );
+            if file_name == "latin1.txt" {
+                let body = test::read_body(response).await;
+                assert_eq!(latin1_data.as_slice(), &body[..]);
+            }
+        }
+
+        let charset_path = PathBuf::from(test_upload_dir)
+            .join(paste::CHARSET_DIR)
+            .join("latin1.txt");
+        assert!(charset_path.exists());
+        let serve_request = TestRequest::get()
+            .uri(&format!("/{}/latin1.txt", paste::CHARSET_DIR))
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+        let delete_request = TestRequest::delete()
+            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+            .uri("/latin1.txt")
+            .to_request();
+        let response = test::call_service(&app, delete_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(!charset_path.exists());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_serve_rendered_markdown() -> Result<(), Error> {
+        let test_upload_dir = "test_markdown_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.paste.max_render_size = Some(Byte::from_u64(64));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let markdown = "# title\n\n<script>alert(1)</script>\n";
+        fs::write(PathBuf::from(test_upload_dir).join("note.md"), markdown)?;
+        fs::write(PathBuf::from(test_upload_dir).join("note.txt"), markdown)?;
+
+        let serve_request = TestRequest::get().uri("/note.md?render").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some("text/html; charset=utf-8"),
+            response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+        );
+        let body = test::read_body(response).await;
+        let body = str::from_utf8(&body)?;
+        assert!(body.contains("<h1>title</h1>"));
+        assert!(!body.contains("<script>"));
+
+        let serve_request = TestRequest::get().uri("/note.md").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), markdown).await?;
+
+        let serve_request = TestRequest::get().uri("/note.txt?render").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), markdown).await?;
+
+        fs::write(
+            PathBuf::from(test_upload_dir).join("large.md"),
+            markdown.repeat(2),
+        )?;
+        let serve_request = TestRequest::get().uri("/large.md?render").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+        let serve_request = TestRequest::get().uri("/large.md").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_serve_line_view() -> Result<(), Error> {
+        let test_upload_dir = "test_line_view_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.paste.max_render_size = Some(Byte::from_u64(1024));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let text = (1..=30)
+            .map(|i| format!("line <{i}>\n"))
+            .collect::<String>();
+        fs::write(PathBuf::from(test_upload_dir).join("code.rs"), &text)?;
+
+        let serve_request = TestRequest::get().uri("/code.rs?view").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some("text/html; charset=utf-8"),
+            response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+        );
+        let body = test::read_body(response).await;
+        let body = str::from_utf8(&body)?;
+        assert!(body.contains(r##"<span id="L1"><a href="#L1">1</a> line &lt;1&gt;</span>"##));
+        assert!(body.contains(r#"id="L30""#));
+        assert!(!body.contains(r#"id="L31""#));
+
+        let serve_request = TestRequest::get().uri("/code.rs?lines=10-20").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let body = test::read_body(response).await;
+        let body = str::from_utf8(&body)?;
+        assert_eq!(11, body.matches("<span id=").count());
+        assert!(body.contains(r#"id="L10""#));
+        assert!(body.contains(r#"id="L20""#));
+        assert!(!body.contains(r#"id="L9""#));
+        assert!(!body.contains(r#"id="L21""#));
+
+        for range in ["0-5", "20-10", "a"] {
+            let serve_request = TestRequest::get()
+                .uri(&format!("/code.rs?lines={range}"))
+                .to_request();
+            let response = test::call_service(&app, serve_request).await;
+            assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        }
+
+        let serve_request = TestRequest::get().uri("/code.rs").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), &text).await?;
+
+        fs::write(
+            PathBuf::from(test_upload_dir).join("large.rs"),
+            text.repeat(10),
+        )?;
+        let serve_request = TestRequest::get().uri("/large.rs?view").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+
+        fs::write(
+            PathBuf::from(test_upload_dir).join("image.png"),
+            b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR",
+        )?;
+        fs::write(
+            PathBuf::from(test_upload_dir).join("image"),
+            b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR",
+        )?;
+        for uri in ["/image.png?view", "/image?lines=1"] {
+            let serve_request = TestRequest::get().uri(uri).to_request();
+            let response = test::call_service(&app, serve_request).await;
+            assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        }
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_serve_trailing_slash() -> Result<(), Error> {
+        let test_upload_dir = "test_trailing_slash_upload";
+        fs::create_dir(test_upload_dir)?;
+        fs::write(PathBuf::from(test_upload_dir).join("slash.txt"), "slash")?;
+
+        for (trailing_slash, status) in [
+            (TrailingSlash::Strict, StatusCode::NOT_FOUND),
+            (TrailingSlash::Redirect, StatusCode::MOVED_PERMANENTLY),
+            (TrailingSlash::Trim, StatusCode::OK),
+        ] {
+            let mut config = Config::default();
+            config.server.upload_path = PathBuf::from(test_upload_dir);
+            config.server.trailing_slash = Some(trailing_slash);
+
+            let app = test::init_service(
+                App::new()
+                    .app_data(Data::new(RwLock::new(config)))
+                    .app_data(Data::new(Client::default()))
+                    .configure(configure_routes),
+            )
+            .await;
+
+            let serve_request = TestRequest::get().uri("/slash.txt").to_request();
+            let response = test::call_service(&app, serve_request).await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_body(response.into_body(), "slash").await?;
+
+            let serve_request = TestRequest::get()
+                .uri("/slash.txt/?download=true")
+                .to_request();
+            let response = test::call_service(&app, serve_request).await;
+            assert_eq!(status, response.status());
+            match trailing_slash {
+                TrailingSlash::Strict => {}
+                TrailingSlash::Redirect => assert_eq!(
+                    Some("/slash.txt?download=true"),
+                    response
+                        .headers()
+                        .get(header::LOCATION)
+                        .and_then(|v| v.to_str().ok())
+                ),
+                TrailingSlash::Trim => assert_body(response.into_body(), "slash").await?,
+            }
+        }
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_serve_raw() -> Result<(), Error> {
+        let test_upload_dir = "test_raw_upload";
+        let oneshot_upload_path = PasteType::Oneshot.get_path(&PathBuf::from(test_upload_dir))?;
+        fs::create_dir_all(&oneshot_upload_path)?;
+        fs::write(PathBuf::from(test_upload_dir).join("raw.txt"), "raw text")?;
+        fs::write(oneshot_upload_path.join("once.txt"), "oneshot")?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        for (uri, body) in [
+            ("/raw.txt/raw", "raw text"),
+            ("/once.txt/raw", "oneshot"),
+            ("/once.txt/raw", "oneshot"),
+        ] {
+            let serve_request = TestRequest::get().uri(uri).to_request();
+            let response = test::call_service(&app, serve_request).await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_eq!(
+                Some("application/octet-stream"),
+                response
+                    .headers()
+                    .get(header::CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+            );
+            assert_eq!(
+                Some("nosniff"),
+                response
+                    .headers()
+                    .get(header::X_CONTENT_TYPE_OPTIONS)
+                    .and_then(|v| v.to_str().ok())
+            );
+            assert!(response
+                .headers()
+                .get(header::CONTENT_DISPOSITION)
+                .is_none());
+            assert_body(response.into_body(), body).await?;
+        }
+
+        // the oneshot file is still available
+        let serve_request = TestRequest::get().uri("/once.txt").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "oneshot").await?;
+
+        let serve_request = TestRequest::get().uri("/missing.txt/raw").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_transcode() -> Result<(), Error> {
+        let test_upload_dir = "test_transcode_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.transcode = Some(TranscodeConfig {
+            enabled: true,
+            format: TranscodeFormat::Webp,
+            quality: None,
+        });
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let mut png = Vec::new();
+        image::DynamicImage::ImageRgb8(image::RgbImage::new(4, 4))
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .map_err(error::ErrorInternalServerError)?;
+        for (data, file_name, stored_file_name) in [
+            (png.as_slice(), "image.png", "image.webp"),
+            (&png[..png.len() / 2], "broken.png", "broken.png"),
+            (b"text".as_slice(), "text.txt", "text.txt"),
+        ] {
+            let response = test::call_service(
+                &app,
+                get_multipart_request_with_bytes(data, "file", file_name).to_request(),
+            )
+            .await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_body(
+                response.into_body(),
+                &format!("http://localhost:8080/{stored_file_name}\n"),
+            )
+            .await?;
+            assert!(PathBuf::from(test_upload_dir)
+                .join(stored_file_name)
+                .is_file());
+        }
+        let webp = fs::read(PathBuf::from(test_upload_dir).join("image.webp"))?;
+        assert_eq!(
+            Some(image::ImageFormat::WebP),
+            image::guess_format(&webp).ok()
+        );
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_max_transfer() -> Result<(), Error> {
+        let test_upload_dir = "test_max_transfer_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let file_name = "transfer.txt";
+        let response = test::call_service(
+            &app,
+            get_multipart_request("0123456789", "file", file_name)
+                .insert_header((crate::header::MAX_TRANSFER, "25"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        for _ in 0..2 {
+            let serve_request = TestRequest::get()
+                .uri(&format!("/{file_name}"))
+                .to_request();
+            let response = test::call_service(&app, serve_request).await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_body(response.into_body(), "0123456789").await?;
+        }
+
+        let serve_request = TestRequest::get()
+            .uri(&format!("/{file_name}"))
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+        let serve_request = TestRequest::get()
+            .uri(&format!("/{file_name}/raw"))
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("0123456789", "file", file_name)
+                .insert_header((crate::header::MAX_TRANSFER, "0"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_serve_blocked_extensions() -> Result<(), Error> {
+        let test_upload_dir = "test_blocked_extensions_upload";
+        fs::create_dir(test_upload_dir)?;
+        fs::write(PathBuf::from(test_upload_dir).join("index.php"), "<?php")?;
+        fs::write(PathBuf::from(test_upload_dir).join("index.txt"), "text")?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.paste.serve_blocked_extensions = vec![String::from(".PHP"), String::from("cgi")];
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        for uri in ["/index.php", "/index.php/raw"] {
+            let serve_request = TestRequest::get().uri(uri).to_request();
+            let response = test::call_service(&app, serve_request).await;
+            assert_eq!(StatusCode::FORBIDDEN, response.status());
+        }
+
+        let serve_request = TestRequest::get().uri("/index.txt").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "text").await?;
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_serve_download() -> Result<(), Error> {
+        let test_upload_dir = "test_download_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let file_name = "çay.txt";
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", file_name).to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let serve_request = TestRequest::get()
+            .uri("/%C3%A7ay.txt?download=true")
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some(r#"attachment; filename="_ay.txt"; filename*=UTF-8''%C3%A7ay.txt"#),
+            response
+                .headers()
+                .get(header::CONTENT_DISPOSITION)
+                .and_then(|v| v.to_str().ok())
+        );
+
+        let serve_request = TestRequest::get().uri("/%C3%A7ay.txt").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(response
+            .headers()
+            .get(header::CONTENT_DISPOSITION)
+            .is_none());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_serve_force_download() -> Result<(), Error> {
+        let test_upload_dir = "test_force_download_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.paste.force_download = vec![String::from(".HTML"), String::from("image/svg+xml")];
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        for file_name in ["page.html", "image.svg", "image.png"] {
+            let response = test::call_service(
+                &app,
+                get_multipart_request("test", "file", file_name).to_request(),
+            )
+            .await;
+            assert_eq!(StatusCode::OK, response.status());
+        }
+
+        for (file_name, forced) in [
+            ("page.html", true),
+            ("image.svg", true),
+            ("image.png", false),
+        ] {
+            let serve_request = TestRequest::get()
+                .uri(&format!("/{file_name}"))
+                .to_request();
+            let response = test::call_service(&app, serve_request).await;
+            assert_eq!(StatusCode::OK, response.status());
+            let content_type = response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .map(String::from);
+            let content_disposition = response
+                .headers()
+                .get(header::CONTENT_DISPOSITION)
+                .and_then(|v| v.to_str().ok())
+                .map(String::from);
+            if forced {
+                assert_eq!(Some("application/octet-stream"), content_type.as_deref());
+                assert_eq!(
+                    Some(format!(r#"attachment; filename="{file_name}""#)),
+                    content_disposition
+                );
+            } else {
+                assert_eq!(Some("image/png"), content_type.as_deref());
+                assert!(content_disposition.is_none());
+            }
+        }
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_serve_default_mime() -> Result<(), Error> {
+        let test_upload_dir = "test_default_mime_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.paste.default_mime = Some(String::from("text/plain"));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        for file_name in ["notes", "image.png"] {
+            let response = test::call_service(
+                &app,
+                get_multipart_request("test", "file", file_name).to_request(),
+            )
+            .await;
+            assert_eq!(StatusCode::OK, response.status());
+        }
+
+        for (file_name, mime_type) in [("notes", "text/plain"), ("image.png", "image/png")] {
+            let serve_request = TestRequest::get()
+                .uri(&format!("/{file_name}"))
+                .to_request();
+            let response = test::call_service(&app, serve_request).await;
+            assert_eq!(StatusCode::OK, response.status());
+            let content_type = response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<mime::Mime>().ok());
+            assert_eq!(
+                Some(mime_type),
+                content_type.as_ref().map(|v| v.essence_str())
+            );
+            assert!(response
+                .headers()
+                .get(header::CONTENT_DISPOSITION)
+                .is_none());
+            assert_body(response.into_body(), "test").await?;
+        }
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_serve_expires() -> Result<(), Error> {
+        let test_upload_dir = "test_expires_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", "expiring.txt")
+                .insert_header((
+                    header::HeaderName::from_static("expire"),
+                    header::HeaderValue::from_static("1h"),
+                ))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", "permanent.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let serve_request = TestRequest::get().uri("/expiring.txt").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let expires = response
+            .headers()
+            .get(header::EXPIRES)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<HttpDate>().ok())
+            .map(SystemTime::from)
+            .expect("invalid Expires header");
+        let expires_in = expires
+            .duration_since(SystemTime::now())
+            .expect("Expires header is in the past");
+        assert!(expires_in <= Duration::from_secs(3600));
+        assert!(expires_in >= Duration::from_secs(3590));
+        let age = response
+            .headers()
+            .get(header::AGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok())
+            .expect("invalid Age header");
+        assert!(age <= 10);
+
+        let serve_request = TestRequest::get().uri("/permanent.txt").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(response.headers().get(header::EXPIRES).is_none());
+        assert!(response.headers().get(header::AGE).is_some());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_serve_during_write() -> Result<(), Error> {
+        let test_upload_dir = "test_serve_write_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        let config = Data::new(RwLock::new(config));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::clone(&config))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let path = PathBuf::from(test_upload_dir).join("file.txt");
+        let write_guard = paste::WriteGuard::new(&path);
+        fs::write(&path, "parti")?;
+
+        let serve_request = TestRequest::get().uri("/file.txt").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::CONFLICT, response.status());
+        assert_body(response.into_body(), "file is being written\n").await?;
+
+        config
+            .write()
+            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+            .server
+            .write_wait_timeout = Some(Duration::from_secs(5));
+        let write_path = path.clone();
+        let writer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            let result = fs::write(write_path, "partial content");
+            drop(write_guard);
+            result
+        });
+
+        let serve_request = TestRequest::get().uri("/file.txt").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "partial content").await?;
+        writer
+            .join()
+            .map_err(|_| error::ErrorInternalServerError("writer panicked"))??;
+        assert!(!paste::is_being_written(&path));
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_serve_cache_control() -> Result<(), Error> {
+        let test_upload_dir = "test_cache_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.cache = Some(CacheConfig {
+            enabled: true,
+            ..Default::default()
+        });
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config.clone())))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let oneshot_upload_path = PasteType::Oneshot
+            .get_path(&config.server.upload_path)
+            .expect("Bad upload path");
+        fs::create_dir_all(&oneshot_upload_path)?;
+        fs::write(oneshot_upload_path.join("oneshot.txt"), "test")?;
+        fs::write(PathBuf::from(test_upload_dir).join("file.txt"), "test")?;
+        let expiry_date = util::get_system_time()?.as_millis() + 60 * 60 * 1000;
+        fs::write(
+            PathBuf::from(test_upload_dir).join(format!("expiring.txt.{expiry_date}")),
+            "test",
+        )?;
+
+        let get_cache_control = |response: &ServiceResponse| {
+            response
+                .headers()
+                .get(header::CACHE_CONTROL)
+                .and_then(|v| v.to_str().ok())
+                .map(String::from)
+        };
+
+        let serve_request = TestRequest::get().uri("/file.txt").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some(String::from("public, max-age=31536000, immutable")),
+            get_cache_control(&response)
+        );
+
+        let serve_request = TestRequest::get().uri("/expiring.txt").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let max_age = get_cache_control(&response)
+            .and_then(|v| v.strip_prefix("public, max-age=").map(String::from))
+            .and_then(|v| v.parse::<u64>().ok())
+            .expect("invalid cache-control header");
+        assert!(max_age <= 60 * 60 && max_age > 60 * 59);
+
+        let serve_request = TestRequest::get().uri("/oneshot.txt").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(Some(String::from("no-store")), get_cache_control(&response));
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_serve_ranges() -> Result<(), Error> {
+        let test_upload_dir = "test_range_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        fs::write(
+            PathBuf::from(test_upload_dir).join("range.txt"),
+            "0123456789",
+        )?;
+
+        let serve_request = TestRequest::get().uri("/range.txt").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some("bytes"),
+            response
+                .headers()
+                .get(header::ACCEPT_RANGES)
+                .and_then(|v| v.to_str().ok())
+        );
+
+        let serve_request = TestRequest::get()
+            .uri("/range.txt")
+            .insert_header((header::RANGE, "bytes=0-1"))
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::PARTIAL_CONTENT, response.status());
+        assert_eq!(
+            Some("bytes 0-1/10"),
+            response
+                .headers()
+                .get(header::CONTENT_RANGE)
+                .and_then(|v| v.to_str().ok())
+        );
+        let body = test::read_body(response).await;
+        assert_eq!(web::Bytes::from_static(b"01"), body);
+
+        let serve_request = TestRequest::get()
+            .uri("/range.txt")
+            .insert_header((header::RANGE, "bytes=0-1,4-5"))
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::PARTIAL_CONTENT, response.status());
+        let boundary = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("multipart/byteranges; boundary="))
+            .map(String::from)
+            .expect("response is not multipart/byteranges");
+        let body = test::read_body(response).await;
+        assert_eq!(
+            format!(
+                "--{boundary}\r\n\
+                 Content-Type: text/plain\r\n\
+                 Content-Range: bytes 0-1/10\r\n\r\n\
+                 01\r\n\
+                 --{boundary}\r\n\
+                 Content-Type: text/plain\r\n\
+                 Content-Range: bytes 4-5/10\r\n\r\n\
+                 45\r\n\
+                 --{boundary}--\r\n"
+            ),
+            str::from_utf8(&body)?
+        );
+
+        let serve_request = TestRequest::get()
+            .uri("/range.txt")
+            .insert_header((header::RANGE, "bytes=6-8,0-1,1-3,4-4"))
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::PARTIAL_CONTENT, response.status());
+        let boundary = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("multipart/byteranges; boundary="))
+            .map(String::from)
+            .expect("response is not multipart/byteranges");
+        let body = test::read_body(response).await;
+        assert_eq!(
+            format!(
+                "--{boundary}\r\n\
+                 Content-Type: text/plain\r\n\
+                 Content-Range: bytes 0-4/10\r\n\r\n\
+                 01234\r\n\
+                 --{boundary}\r\n\
+                 Content-Type: text/plain\r\n\
+                 Content-Range: bytes 6-8/10\r\n\r\n\
+                 678\r\n\
+                 --{boundary}--\r\n"
+            ),
+            str::from_utf8(&body)?
+        );
+
+        let serve_request = TestRequest::get()
+            .uri("/range.txt")
+            .insert_header((header::RANGE, "bytes=2-3,0-1"))
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::PARTIAL_CONTENT, response.status());
+        assert_eq!(
+            Some("bytes 0-3/10"),
+            response
+                .headers()
+                .get(header::CONTENT_RANGE)
+                .and_then(|v| v.to_str().ok())
+        );
+        let body = test::read_body(response).await;
+        assert_eq!(web::Bytes::from_static(b"0123"), body);
+
+        let ranges = (0..MAX_BYTE_RANGES + 1)
+            .map(|_| "0-0")
+            .collect::<Vec<_>>()
+            .join(",");
+        let serve_request = TestRequest::get()
+            .uri("/range.txt")
+            .insert_header((header::RANGE, format!("bytes={ranges}")))
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(response.headers().get(header::CONTENT_RANGE).is_none());
+        let body = test::read_body(response).await;
+        assert_eq!(web::Bytes::from_static(b"0123456789"), body);
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_serve_compressed() -> Result<(), Error> {
+        let test_upload_dir = "test_compression_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.compression = Some(CompressionConfig {
+            enabled: true,
+            min_size: Some(Byte::from_u64(100)),
+            max_size: Some(Byte::from_u64(2000)),
+            ..Default::default()
+        });
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let large_data = "rustypaste ".repeat(100);
+        fs::write(PathBuf::from(test_upload_dir).join("small.txt"), "test")?;
+        fs::write(
+            PathBuf::from(test_upload_dir).join("large.txt"),
+            &large_data,
+        )?;
+
+        let serve_request = TestRequest::get()
+            .uri("/small.txt")
+            .insert_header((header::ACCEPT_ENCODING, "gzip"))
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+        let body = test::read_body(response).await;
+        assert_eq!(web::Bytes::from_static(b"test"), body);
+
+        let serve_request = TestRequest::get()
+            .uri("/large.txt")
+            .insert_header((header::ACCEPT_ENCODING, "gzip, deflate"))
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some("gzip"),
+            response
+                .headers()
+                .get(header::CONTENT_ENCODING)
+                .and_then(|v| v.to_str().ok())
+        );
+        let body = test::read_body(response).await;
+        assert!(body.len() < large_data.len());
+        let mut decompressed = String::new();
+        flate2::read::GzDecoder::new(&body[..]).read_to_string(&mut decompressed)?;
+        assert_eq!(large_data, decompressed);
+
+        let serve_request = TestRequest::get().uri("/large.txt").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+
+        fs::write(
+            PathBuf::from(test_upload_dir).join("image.png"),
+            &large_data,
+        )?;
+        fs::write(
+            PathBuf::from(test_upload_dir).join("huge.txt"),
+            large_data.repeat(2),
+        )?;
+        for uri in ["/image.png", "/huge.txt"] {
+            let serve_request = TestRequest::get()
+                .uri(uri)
+                .insert_header((header::ACCEPT_ENCODING, "gzip"))
+                .to_request();
+            let response = test::call_service(&app, serve_request).await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+        }
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_next_with_timeout() -> Result<(), Error> {
+        let mut stream = stream::iter(vec![1]);
+        assert_eq!(
+            Some(1),
+            next_with_timeout(&mut stream, Some(Duration::from_millis(10))).await?
+        );
+        assert_eq!(None, next_with_timeout(&mut stream, None).await?);
+
+        let mut stream = stream::pending::<web::Bytes>();
+        let error = next_with_timeout(&mut stream, Some(Duration::from_millis(10)))
+            .await
+            .expect_err("stalling stream should time out");
+        assert_eq!(
+            StatusCode::REQUEST_TIMEOUT,
+            error.as_response_error().status_code()
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_server_timing() -> Result<(), Error> {
+        let test_upload_dir = "test_server_timing_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.paste.duplicate_files = Some(false);
+
         let app = test::init_service(
             App::new()
                 .app_data(Data::new(RwLock::new(config)))
-                .service(index),
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", "test_file.txt").to_request(),
         )
         .await;
-        let request = TestRequest::default()
-            .insert_header(("content-type", "text/plain"))
-            .to_request();
-        let response = test::call_service(&app, request).await;
         assert_eq!(StatusCode::OK, response.status());
-        assert_body(response.into_body(), "landing page").await?;
+        let server_timing = response
+            .headers()
+            .get(crate::header::SERVER_TIMING)
+            .and_then(|v| v.to_str().ok())
+            .expect("server timing header is missing");
+        let metrics = server_timing
+            .split(", ")
+            .map(|metric| {
+                let (name, duration) = metric.split_once(";dur=").expect("invalid metric");
+                assert!(duration.parse::<f64>().is_ok());
+                name
+            })
+            .collect::<Vec<&str>>();
+        assert_eq!(vec!["hash", "store", "total"], metrics);
+
+        let serve_request = TestRequest::get().uri("/test_file.txt").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(response
+            .headers()
+            .get(crate::header::SERVER_TIMING)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("lookup;dur=")));
+
+        fs::remove_dir_all(test_upload_dir)?;
+
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_index_with_landing_page_file() -> Result<(), Error> {
-        let filename = "landing_page.txt";
-        let config = Config {
-            landing_page: Some(LandingPageConfig {
-                file: Some(filename.to_string()),
-                ..Default::default()
-            }),
-            ..Default::default()
-        };
-        let mut file = File::create(filename)?;
-        file.write_all("landing page from file".as_bytes())?;
+    async fn test_upload_file_with_forwarded_header() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?;
+        config.server.trusted_proxies = Some(vec!["127.0.0.1"
+            .parse()
+            .map_err(error::ErrorInternalServerError)?]);
+
         let app = test::init_service(
             App::new()
                 .app_data(Data::new(RwLock::new(config)))
-                .service(index),
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let file_name = "test_file.txt";
+        let forwarded = "for=192.0.2.60;proto=https;host=paste.example.com";
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", file_name)
+                .peer_addr(
+                    "127.0.0.1:12345"
+                        .parse()
+                        .map_err(error::ErrorInternalServerError)?,
+                )
+                .insert_header((header::FORWARDED, forwarded))
+                .to_request(),
         )
         .await;
-        let request = TestRequest::default()
-            .insert_header(("content-type", "text/plain"))
-            .to_request();
-        let response = test::call_service(&app, request).await;
         assert_eq!(StatusCode::OK, response.status());
-        assert_body(response.into_body(), "landing page from file").await?;
-        fs::remove_file(filename)?;
-        Ok(())
-    }
+        assert_body(
+            response.into_body(),
+            &format!("https://paste.example.com/{file_name}\n"),
+        )
+        .await?;
+        fs::remove_file(file_name)?;
 
-    #[actix_web::test]
-    async fn test_index_with_landing_page_file_not_found() -> Result<(), Error> {
-        let filename = "landing_page.txt";
-        let config = Config {
-            landing_page: Some(LandingPageConfig {
-                text: Some(String::from("landing page")),
-                file: Some(filename.to_string()),
-                ..Default::default()
-            }),
-            ..Default::default()
-        };
-        let app = test::init_service(
-            App::new()
-                .app_data(Data::new(RwLock::new(config)))
-                .service(index),
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", file_name)
+                .peer_addr(
+                    "192.0.2.1:12345"
+                        .parse()
+                        .map_err(error::ErrorInternalServerError)?,
+                )
+                .insert_header((header::FORWARDED, forwarded))
+                .to_request(),
         )
         .await;
-        let request = TestRequest::default()
-            .insert_header(("content-type", "text/plain"))
-            .to_request();
-        let response = test::call_service(&app, request).await;
-        assert_eq!(StatusCode::FOUND, response.status());
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(
+            response.into_body(),
+            &format!("http://localhost:8080/{file_name}\n"),
+        )
+        .await?;
+        fs::remove_file(file_name)?;
+
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_version_without_auth() -> Result<(), Error> {
+    async fn test_upload_file_with_url_path_prefix() -> Result<(), Error> {
         let mut config = Config::default();
-        config.server.auth_tokens = Some(["test".to_string()].into());
+        config.server.upload_path = env::current_dir()?;
+        config.server.url_path_prefix = Some(String::from("/paste/"));
+
         let app = test::init_service(
             App::new()
                 .app_data(Data::new(RwLock::new(config)))
@@ -587,40 +6620,87 @@ mod tests {
         )
         .await;
 
-        let request = TestRequest::default()
-            .insert_header(("content-type", "text/plain"))
-            .uri("/version")
-            .to_request();
-        let response = test::call_service(&app, request).await;
-        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
-        assert_body(response.into_body(), "unauthorized\n").await?;
+        let file_name = "test_file.txt";
+        let timestamp = util::get_system_time()?.as_secs().to_string();
+        let response = test::call_service(
+            &app,
+            get_multipart_request(&timestamp, "file", file_name).to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(
+            response.into_body(),
+            &format!("http://localhost:8080/paste/{file_name}\n"),
+        )
+        .await?;
+
+        for uri in [format!("/paste/{file_name}"), format!("/{file_name}")] {
+            let serve_request = TestRequest::get().uri(&uri).to_request();
+            let response = test::call_service(&app, serve_request).await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_body(response.into_body(), &timestamp).await?;
+        }
+
+        fs::remove_file(file_name)?;
+
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_version_without_config() -> Result<(), Error> {
+    async fn test_upload_file_override_filename() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?;
+
         let app = test::init_service(
             App::new()
-                .app_data(Data::new(RwLock::new(Config::default())))
+                .app_data(Data::new(RwLock::new(config)))
                 .app_data(Data::new(Client::default()))
                 .configure(configure_routes),
         )
         .await;
 
-        let request = TestRequest::default()
-            .insert_header(("content-type", "text/plain"))
-            .uri("/version")
+        let file_name = "test_file.txt";
+        let header_filename = "fn_from_header.txt";
+        let timestamp = util::get_system_time()?.as_secs().to_string();
+        let response = test::call_service(
+            &app,
+            get_multipart_request(&timestamp, "file", file_name)
+                .insert_header((
+                    header::HeaderName::from_static("filename"),
+                    header::HeaderValue::from_static("fn_from_header.txt"),
+                ))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(
+            response.into_body(),
+            &format!("http://localhost:8080/{header_filename}\n"),
+        )
+        .await?;
+
+        let serve_request = TestRequest::get()
+            .uri(&format!("/{header_filename}"))
             .to_request();
-        let response = test::call_service(&app, request).await;
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), &timestamp).await?;
+
+        fs::remove_file(header_filename)?;
+        let serve_request = TestRequest::get()
+            .uri(&format!("/{header_filename}"))
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
         assert_eq!(StatusCode::NOT_FOUND, response.status());
-        assert_body(response.into_body(), "").await?;
+
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_version() -> Result<(), Error> {
+    async fn test_upload_same_filename() -> Result<(), Error> {
         let mut config = Config::default();
-        config.server.expose_version = Some(true);
+        config.server.upload_path = env::current_dir()?;
+
         let app = test::init_service(
             App::new()
                 .app_data(Data::new(RwLock::new(config)))
@@ -629,28 +6709,59 @@ mod tests {
         )
         .await;
 
-        let request = TestRequest::default()
-            .insert_header(("content-type", "text/plain"))
-            .uri("/version")
-            .to_request();
-        let response = test::call_service(&app, request).await;
+        let file_name = "test_file.txt";
+        let header_filename = "fn_from_header.txt";
+        let timestamp = util::get_system_time()?.as_secs().to_string();
+        let response = test::call_service(
+            &app,
+            get_multipart_request(&timestamp, "file", file_name)
+                .insert_header((
+                    header::HeaderName::from_static("filename"),
+                    header::HeaderValue::from_static("fn_from_header.txt"),
+                ))
+                .to_request(),
+        )
+        .await;
         assert_eq!(StatusCode::OK, response.status());
         assert_body(
             response.into_body(),
-            &(env!("CARGO_PKG_VERSION").to_owned() + "\n"),
+            &format!("http://localhost:8080/{header_filename}\n"),
         )
         .await?;
+
+        let timestamp = util::get_system_time()?.as_secs().to_string();
+        let response = test::call_service(
+            &app,
+            get_multipart_request(&timestamp, "file", file_name)
+                .insert_header((
+                    header::HeaderName::from_static("filename"),
+                    header::HeaderValue::from_static("fn_from_header.txt"),
+                ))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::CONFLICT, response.status());
+        assert_body(response.into_body(), "file already exists\n").await?;
+
+        fs::remove_file(header_filename)?;
+
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_list() -> Result<(), Error> {
-        let mut config = Config::default();
-        config.server.expose_list = Some(true);
-
+    #[allow(deprecated)]
+    async fn test_upload_duplicate_file() -> Result<(), Error> {
         let test_upload_dir = "test_upload";
         fs::create_dir(test_upload_dir)?;
-        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(&test_upload_dir);
+        config.paste.duplicate_files = Some(false);
+        config.paste.random_url = Some(RandomURLConfig {
+            enabled: Some(true),
+            type_: Some(RandomURLType::Alphanumeric),
+            ..Default::default()
+        });
 
         let app = test::init_service(
             App::new()
@@ -660,25 +6771,25 @@ mod tests {
         )
         .await;
 
-        let filename = "test_file.txt";
-        let timestamp = util::get_system_time()?.as_secs().to_string();
-        test::call_service(
+        let response = test::call_service(
             &app,
-            get_multipart_request(&timestamp, "file", filename).to_request(),
+            get_multipart_request("test", "file", "x").to_request(),
         )
         .await;
+        assert_eq!(StatusCode::OK, response.status());
+        let body = response.into_body();
+        let first_body_bytes = actix_web::body::to_bytes(body).await?;
 
-        let request = TestRequest::default()
-            .insert_header(("content-type", "text/plain"))
-            .uri("/list")
-            .to_request();
-        let result: Vec<ListItem> = test::call_and_read_body_json(&app, request).await;
-
-        assert_eq!(result.len(), 1);
-        assert_eq!(
-            result.first().expect("json object").file_name,
-            PathBuf::from(filename)
-        );
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", "x").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        let body = response.into_body();
+        let second_body_bytes = actix_web::body::to_bytes(body).await?;
+
+        assert_eq!(first_body_bytes, second_body_bytes);
 
         fs::remove_dir_all(test_upload_dir)?;
 
@@ -686,13 +6797,15 @@ mod tests {
     }
 
     #[actix_web::test]
-    async fn test_list_expired() -> Result<(), Error> {
-        let mut config = Config::default();
-        config.server.expose_list = Some(true);
-
-        let test_upload_dir = "test_upload";
+    async fn test_upload_fanout() -> Result<(), Error> {
+        let test_upload_dir = "test_fanout_upload";
         fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
         config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.expose_list = Some(true);
+        config.paste.duplicate_files = Some(false);
+        config.paste.fanout_depth = Some(2);
 
         let app = test::init_service(
             App::new()
@@ -702,28 +6815,109 @@ mod tests {
         )
         .await;
 
-        let filename = "test_file.txt";
-        let timestamp = util::get_system_time()?.as_secs().to_string();
-        test::call_service(
+        let response = test::call_service(
             &app,
-            get_multipart_request(&timestamp, "file", filename)
-                .insert_header((
-                    header::HeaderName::from_static("expire"),
-                    header::HeaderValue::from_static("50ms"),
-                ))
-                .to_request(),
+            get_multipart_request("test", "file", "fanout.txt").to_request(),
         )
         .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "http://localhost:8080/fanout.txt\n").await?;
+
+        // SHA256 of "test" is 9f86d081...
+        let fanout_path = PathBuf::from(test_upload_dir)
+            .join("9f")
+            .join("86")
+            .join("fanout.txt");
+        assert!(fanout_path.is_file());
+        assert!(!PathBuf::from(test_upload_dir).join("fanout.txt").exists());
+        assert_eq!(
+            Some(PathBuf::from("9f/86")),
+            paste::get_fanout_dir(Path::new(test_upload_dir), "fanout.txt")
+        );
 
-        thread::sleep(Duration::from_millis(500));
+        let serve_request = TestRequest::get().uri("/fanout.txt").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "test").await?;
 
-        let request = TestRequest::default()
-            .insert_header(("content-type", "text/plain"))
-            .uri("/list")
-            .to_request();
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", "duplicate.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "http://localhost:8080/fanout.txt\n").await?;
+
+        let request = TestRequest::default().uri("/list").to_request();
         let result: Vec<ListItem> = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(1, result.len());
+        assert_eq!(PathBuf::from("fanout.txt"), result[0].file_name);
 
-        assert!(result.is_empty());
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_duplicate_file_per_token() -> Result<(), Error> {
+        let test_upload_dir = "test_dedup_scope_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        for (dedup_scope, expected) in [
+            (
+                DedupScope::PerToken,
+                ["a.txt", "b.txt", "a.txt", "d.txt", "d.txt"],
+            ),
+            (
+                DedupScope::Global,
+                ["a.txt", "a.txt", "a.txt", "a.txt", "a.txt"],
+            ),
+        ] {
+            let mut config = Config::default();
+            config.server.upload_path = PathBuf::from(test_upload_dir);
+            config.server.auth_tokens = Some(["alice".to_string(), "bob".to_string()].into());
+            config.server.auth = Some(AuthConfig {
+                write: false,
+                ..AuthConfig::default()
+            });
+            config.server.owner_secret = Some(String::from("secret"));
+            config.paste.duplicate_files = Some(false);
+            config.paste.dedup_scope = Some(dedup_scope);
+
+            let app = test::init_service(
+                App::new()
+                    .app_data(Data::new(RwLock::new(config)))
+                    .app_data(Data::new(Client::default()))
+                    .configure(configure_routes),
+            )
+            .await;
+
+            for ((token, file_name), expected) in [
+                (Some("alice"), "a.txt"),
+                (Some("bob"), "b.txt"),
+                (Some("alice"), "c.txt"),
+                (None, "d.txt"),
+                (None, "e.txt"),
+            ]
+            .into_iter()
+            .zip(expected)
+            {
+                let mut request = get_multipart_request("same", "file", file_name);
+                if let Some(token) = token {
+                    request = request.insert_header((AUTHORIZATION, token));
+                }
+                let response = test::call_service(&app, request.to_request()).await;
+                assert_eq!(StatusCode::OK, response.status());
+                assert_body(
+                    response.into_body(),
+                    &format!("http://localhost:8080/{expected}\n"),
+                )
+                .await?;
+            }
+
+            fs::remove_dir_all(test_upload_dir)?;
+            fs::create_dir(test_upload_dir)?;
+        }
 
         fs::remove_dir_all(test_upload_dir)?;
 
@@ -731,9 +6925,14 @@ mod tests {
     }
 
     #[actix_web::test]
-    async fn test_auth() -> Result<(), Error> {
+    async fn test_upload_duplicate_file_blake3() -> Result<(), Error> {
+        let test_upload_dir = "test_duplicate_blake3_upload";
+        fs::create_dir(test_upload_dir)?;
+
         let mut config = Config::default();
-        config.server.auth_tokens = Some(["test".to_string()].into());
+        config.server.upload_path = PathBuf::from(&test_upload_dir);
+        config.paste.duplicate_files = Some(false);
+        config.paste.checksum_algorithm = Some(ChecksumAlgorithm::Blake3);
 
         let app = test::init_service(
             App::new()
@@ -743,41 +6942,100 @@ mod tests {
         )
         .await;
 
-        let response =
-            test::call_service(&app, get_multipart_request("", "", "").to_request()).await;
-        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
-        assert_body(response.into_body(), "unauthorized\n").await?;
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", "first.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "http://localhost:8080/first.txt\n").await?;
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", "second.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "http://localhost:8080/first.txt\n").await?;
+        assert!(!PathBuf::from(test_upload_dir).join("second.txt").exists());
+
+        let checksum = util::digest("test".as_bytes(), ChecksumAlgorithm::Blake3)?;
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", "third.txt")
+                .insert_header((IF_NONE_MATCH, checksum))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(response.headers().get(crate::header::EXISTING).is_some());
+        assert_body(response.into_body(), "http://localhost:8080/first.txt\n").await?;
+
+        fs::remove_dir_all(test_upload_dir)?;
 
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_payload_limit() -> Result<(), Error> {
+    async fn test_upload_warm_up_checksums() -> Result<(), Error> {
+        let test_upload_dir = "test_warm_up_upload";
+        fs::create_dir(test_upload_dir)?;
+        for i in 0..10 {
+            fs::write(
+                PathBuf::from(test_upload_dir).join(format!("{i}.txt")),
+                format!("existing {i}"),
+            )?;
+        }
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.expose_version = Some(true);
+        config.paste.duplicate_files = Some(false);
+
+        let warm_up = crate::file::warm_up_checksums(
+            PathBuf::from(test_upload_dir),
+            ChecksumAlgorithm::default(),
+        );
         let app = test::init_service(
             App::new()
-                .app_data(Data::new(RwLock::new(Config::default())))
+                .app_data(Data::new(RwLock::new(config)))
                 .app_data(Data::new(Client::default()))
-                .wrap(ContentLengthLimiter::new(Byte::from_u64(1)))
                 .configure(configure_routes),
         )
         .await;
 
+        let request = TestRequest::get().uri("/version").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        warm_up
+            .join()
+            .map_err(|_| error::ErrorInternalServerError("warm-up failed"))?;
+
         let response = test::call_service(
             &app,
-            get_multipart_request("test", "file", "test").to_request(),
+            get_multipart_request("existing 5", "file", "duplicate.txt").to_request(),
         )
         .await;
-        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
-        assert_body(response.into_body().boxed(), "upload limit exceeded").await?;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "http://localhost:8080/5.txt\n").await?;
+        assert!(!PathBuf::from(test_upload_dir)
+            .join("duplicate.txt")
+            .exists());
+
+        fs::remove_dir_all(test_upload_dir)?;
 
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_delete_file() -> Result<(), Error> {
+    async fn test_upload_if_none_match() -> Result<(), Error> {
+        let test_upload_dir = "test_if_none_match_upload";
+        fs::create_dir(test_upload_dir)?;
+
         let mut config = Config::default();
-        config.server.delete_tokens = Some(["test".to_string()].into());
-        config.server.upload_path = env::current_dir()?;
+        config.server.upload_path = PathBuf::from(&test_upload_dir);
+        config.paste.duplicate_files = Some(true);
 
         let app = test::init_service(
             App::new()
@@ -787,33 +7045,75 @@ mod tests {
         )
         .await;
 
-        let file_name = "test_file.txt";
-        let timestamp = util::get_system_time()?.as_secs().to_string();
-        test::call_service(
+        let response = test::call_service(
             &app,
-            get_multipart_request(&timestamp, "file", file_name).to_request(),
+            get_multipart_request("test", "file", "existing.txt").to_request(),
         )
         .await;
+        assert_eq!(StatusCode::OK, response.status());
 
-        let request = TestRequest::delete()
-            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
-            .uri(&format!("/{file_name}"))
-            .to_request();
-        let response = test::call_service(&app, request).await;
+        let checksum = util::sha256_digest("test".as_bytes())?;
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", "retry.txt")
+                .insert_header((IF_NONE_MATCH, format!("\"{checksum}\"")))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some("true"),
+            response
+                .headers()
+                .get(crate::header::EXISTING)
+                .and_then(|v| v.to_str().ok())
+        );
+        assert_body(response.into_body(), "http://localhost:8080/existing.txt\n").await?;
+        assert!(!PathBuf::from(test_upload_dir).join("retry.txt").exists());
 
+        let response = test::call_service(
+            &app,
+            get_multipart_request("other", "file", "other.txt")
+                .insert_header((IF_NONE_MATCH, checksum))
+                .to_request(),
+        )
+        .await;
         assert_eq!(StatusCode::OK, response.status());
-        assert_body(response.into_body(), "file deleted\n").await?;
+        assert!(response.headers().get(crate::header::EXISTING).is_none());
+        assert_body(response.into_body(), "http://localhost:8080/other.txt\n").await?;
+        assert!(PathBuf::from(test_upload_dir).join("other.txt").exists());
 
-        let path = PathBuf::from(file_name);
-        assert!(!path.exists());
+        fs::remove_dir_all(test_upload_dir)?;
 
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_delete_file_without_token_in_config() -> Result<(), Error> {
+    async fn test_upload_token_settings() -> Result<(), Error> {
+        let test_upload_dir = "test_token_settings_upload";
+        fs::create_dir(test_upload_dir)?;
+
         let mut config = Config::default();
-        config.server.upload_path = env::current_dir()?;
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.expose_list = Some(true);
+        config.server.token_settings = Some(HashMap::from([
+            (
+                String::from("short_token"),
+                TokenSettings {
+                    default_expiry: Some(Duration::from_secs(60 * 60)),
+                    random_url: Some(false),
+                    quota: None,
+                },
+            ),
+            (
+                String::from("long_token"),
+                TokenSettings {
+                    default_expiry: Some(Duration::from_secs(24 * 60 * 60)),
+                    random_url: Some(true),
+                    quota: None,
+                },
+            ),
+        ]));
 
         let app = test::init_service(
             App::new()
@@ -823,23 +7123,63 @@ mod tests {
         )
         .await;
 
-        let file_name = "test_file.txt";
-        let request = TestRequest::delete()
-            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
-            .uri(&format!("/{file_name}"))
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", "token.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+        for token in ["short_token", "long_token"] {
+            let response = test::call_service(
+                &app,
+                get_multipart_request("test", "file", "token.txt")
+                    .insert_header((AUTHORIZATION, token))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(StatusCode::OK, response.status());
+        }
+
+        let request = TestRequest::default()
+            .uri("/list")
+            .insert_header((AUTHORIZATION, "short_token"))
             .to_request();
-        let response = test::call_service(&app, request).await;
+        let result: Vec<ListItem> = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(2, result.len());
+        let short_file = result
+            .iter()
+            .find(|v| v.file_name == Path::new("token.txt"))
+            .expect("file is not uploaded with the original name");
+        let long_file = result
+            .iter()
+            .find(|v| v.file_name != Path::new("token.txt"))
+            .expect("file is not uploaded with a random name");
+        let short_expiry = short_file.expires_at_epoch.unwrap_or_default();
+        let long_expiry = long_file.expires_at_epoch.unwrap_or_default();
+        assert!(long_expiry - short_expiry >= 23 * 60 * 60);
 
-        assert_eq!(StatusCode::NOT_FOUND, response.status());
-        assert_body(response.into_body(), "").await?;
+        fs::remove_dir_all(test_upload_dir)?;
 
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_upload_file() -> Result<(), Error> {
+    async fn test_upload_token_quota() -> Result<(), Error> {
+        let test_upload_dir = "test_token_quota_upload";
+        fs::create_dir_all(PathBuf::from(test_upload_dir).join("oneshot"))?;
+
         let mut config = Config::default();
-        config.server.upload_path = env::current_dir()?;
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.max_content_length = Byte::from_u64(1000);
+        config.server.owner_secret = Some(String::from("secret"));
+        config.server.token_settings = Some(HashMap::from([(
+            String::from("quota_token"),
+            TokenSettings {
+                quota: Some(Byte::from_u64(10)),
+                ..Default::default()
+            },
+        )]));
 
         let app = test::init_service(
             App::new()
@@ -849,39 +7189,58 @@ mod tests {
         )
         .await;
 
-        let file_name = "test_file.txt";
-        let timestamp = util::get_system_time()?.as_secs().to_string();
+        for (data, field, file_name, status) in [
+            ("123456", "file", "a.txt", StatusCode::OK),
+            ("1234", "oneshot", "b.txt", StatusCode::OK),
+            ("1", "file", "c.txt", StatusCode::PAYLOAD_TOO_LARGE),
+        ] {
+            let response = test::call_service(
+                &app,
+                get_multipart_request(data, field, file_name)
+                    .insert_header((AUTHORIZATION, "quota_token"))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(status, response.status());
+        }
+        assert!(!PathBuf::from(test_upload_dir).join("c.txt").exists());
+
+        // deleted files are not counted
+        fs::remove_file(PathBuf::from(test_upload_dir).join("a.txt"))?;
         let response = test::call_service(
             &app,
-            get_multipart_request(&timestamp, "file", file_name).to_request(),
+            get_multipart_request("1", "file", "c.txt")
+                .insert_header((AUTHORIZATION, "quota_token"))
+                .to_request(),
         )
         .await;
         assert_eq!(StatusCode::OK, response.status());
-        assert_body(
-            response.into_body(),
-            &format!("http://localhost:8080/{file_name}\n"),
-        )
-        .await?;
 
-        let serve_request = TestRequest::get()
-            .uri(&format!("/{file_name}"))
-            .to_request();
-        let response = test::call_service(&app, serve_request).await;
-        assert_eq!(StatusCode::OK, response.status());
-        assert_body(response.into_body(), &timestamp).await?;
+        // the files that are uploaded with the form are also counted
+        for (payload, status) in [
+            ("content=12345&name=d", StatusCode::OK),
+            ("content=1&name=e", StatusCode::PAYLOAD_TOO_LARGE),
+        ] {
+            let request = TestRequest::post()
+                .uri("/")
+                .insert_header((AUTHORIZATION, "quota_token"))
+                .insert_header((
+                    header::CONTENT_TYPE,
+                    header::HeaderValue::from_static("application/x-www-form-urlencoded"),
+                ))
+                .set_payload(payload)
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(status, response.status());
+        }
 
-        fs::remove_file(file_name)?;
-        let serve_request = TestRequest::get()
-            .uri(&format!("/{file_name}"))
-            .to_request();
-        let response = test::call_service(&app, serve_request).await;
-        assert_eq!(StatusCode::NOT_FOUND, response.status());
+        fs::remove_dir_all(test_upload_dir)?;
 
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_upload_file_override_filename() -> Result<(), Error> {
+    async fn test_upload_expiring_file() -> Result<(), Error> {
         let mut config = Config::default();
         config.server.upload_path = env::current_dir()?;
 
@@ -894,14 +7253,13 @@ mod tests {
         .await;
 
         let file_name = "test_file.txt";
-        let header_filename = "fn_from_header.txt";
         let timestamp = util::get_system_time()?.as_secs().to_string();
         let response = test::call_service(
             &app,
             get_multipart_request(&timestamp, "file", file_name)
                 .insert_header((
-                    header::HeaderName::from_static("filename"),
-                    header::HeaderValue::from_static("fn_from_header.txt"),
+                    header::HeaderName::from_static("expire"),
+                    header::HeaderValue::from_static("20ms"),
                 ))
                 .to_request(),
         )
@@ -909,93 +7267,109 @@ mod tests {
         assert_eq!(StatusCode::OK, response.status());
         assert_body(
             response.into_body(),
-            &format!("http://localhost:8080/{header_filename}\n"),
+            &format!("http://localhost:8080/{file_name}\n"),
         )
         .await?;
 
         let serve_request = TestRequest::get()
-            .uri(&format!("/{header_filename}"))
+            .uri(&format!("/{file_name}"))
             .to_request();
         let response = test::call_service(&app, serve_request).await;
         assert_eq!(StatusCode::OK, response.status());
         assert_body(response.into_body(), &timestamp).await?;
 
-        fs::remove_file(header_filename)?;
+        thread::sleep(Duration::from_millis(40));
+
         let serve_request = TestRequest::get()
-            .uri(&format!("/{header_filename}"))
+            .uri(&format!("/{file_name}"))
             .to_request();
         let response = test::call_service(&app, serve_request).await;
         assert_eq!(StatusCode::NOT_FOUND, response.status());
 
+        if let Some(glob_path) = glob(&format!("{file_name}.[0-9]*"))
+            .map_err(error::ErrorInternalServerError)?
+            .next()
+        {
+            fs::remove_file(glob_path.map_err(error::ErrorInternalServerError)?)?;
+        }
+
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_upload_same_filename() -> Result<(), Error> {
+    async fn test_serve_gone_for_expired() -> Result<(), Error> {
+        let test_upload_dir = "test_gone_upload";
+        fs::create_dir(test_upload_dir)?;
+
         let mut config = Config::default();
-        config.server.upload_path = env::current_dir()?;
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.paste.gone_for_expired = Some(true);
 
         let app = test::init_service(
             App::new()
-                .app_data(Data::new(RwLock::new(config)))
-                .app_data(Data::new(Client::default()))
-                .configure(configure_routes),
-        )
-        .await;
-
-        let file_name = "test_file.txt";
-        let header_filename = "fn_from_header.txt";
-        let timestamp = util::get_system_time()?.as_secs().to_string();
-        let response = test::call_service(
-            &app,
-            get_multipart_request(&timestamp, "file", file_name)
-                .insert_header((
-                    header::HeaderName::from_static("filename"),
-                    header::HeaderValue::from_static("fn_from_header.txt"),
-                ))
-                .to_request(),
-        )
-        .await;
-        assert_eq!(StatusCode::OK, response.status());
-        assert_body(
-            response.into_body(),
-            &format!("http://localhost:8080/{header_filename}\n"),
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
         )
-        .await?;
+        .await;
 
-        let timestamp = util::get_system_time()?.as_secs().to_string();
         let response = test::call_service(
             &app,
-            get_multipart_request(&timestamp, "file", file_name)
+            get_multipart_request("expiring", "file", "expiring.txt")
                 .insert_header((
-                    header::HeaderName::from_static("filename"),
-                    header::HeaderValue::from_static("fn_from_header.txt"),
+                    header::HeaderName::from_static("expire"),
+                    header::HeaderValue::from_static("20ms"),
                 ))
                 .to_request(),
         )
         .await;
-        assert_eq!(StatusCode::CONFLICT, response.status());
-        assert_body(response.into_body(), "file already exists\n").await?;
+        assert_eq!(StatusCode::OK, response.status());
 
-        fs::remove_file(header_filename)?;
+        thread::sleep(Duration::from_millis(40));
+
+        for uri in ["/expiring.txt", "/expiring.txt/raw"] {
+            let request = TestRequest::get().uri(uri).to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::GONE, response.status());
+            assert_body(response.into_body(), "this file has expired\n").await?;
+        }
+
+        let request = TestRequest::get().uri("/unknown.txt").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+        let expired_time = util::get_system_time()?.as_millis() - 1;
+        let fanout_dir = PathBuf::from(test_upload_dir).join("9f").join("86");
+        fs::create_dir_all(&fanout_dir)?;
+        fs::write(
+            fanout_dir.join(format!("fanout[1].txt.{expired_time}")),
+            "test",
+        )?;
+        paste::store_fanout_dir(
+            Path::new(test_upload_dir),
+            "fanout[1].txt",
+            Some(Path::new("9f/86")),
+        )?;
+        let request = TestRequest::get().uri("/fanout%5B1%5D.txt").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::GONE, response.status());
+
+        fs::remove_dir_all(test_upload_dir)?;
 
         Ok(())
     }
 
     #[actix_web::test]
-    #[allow(deprecated)]
-    async fn test_upload_duplicate_file() -> Result<(), Error> {
-        let test_upload_dir = "test_upload";
+    async fn test_upload_mime_expiry() -> Result<(), Error> {
+        let test_upload_dir = "test_mime_expiry_upload";
         fs::create_dir(test_upload_dir)?;
 
         let mut config = Config::default();
-        config.server.upload_path = PathBuf::from(&test_upload_dir);
-        config.paste.duplicate_files = Some(false);
-        config.paste.random_url = Some(RandomURLConfig {
-            enabled: Some(true),
-            type_: RandomURLType::Alphanumeric,
-            ..Default::default()
-        });
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.paste.mime_expiry = vec![MimeExpiryMatcher {
+            regex: Regex::new("^image/.*$").ok(),
+            expiry: Duration::from_secs(3600),
+        }];
 
         let app = test::init_service(
             App::new()
@@ -1005,25 +7379,36 @@ mod tests {
         )
         .await;
 
+        let png_data = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
         let response = test::call_service(
             &app,
-            get_multipart_request("test", "file", "x").to_request(),
+            get_multipart_request_with_bytes(png_data, "file", "image.png").to_request(),
         )
         .await;
         assert_eq!(StatusCode::OK, response.status());
-        let body = response.into_body();
-        let first_body_bytes = actix_web::body::to_bytes(body).await?;
 
         let response = test::call_service(
             &app,
-            get_multipart_request("test", "file", "x").to_request(),
+            get_multipart_request("text", "file", "text.txt").to_request(),
         )
         .await;
         assert_eq!(StatusCode::OK, response.status());
-        let body = response.into_body();
-        let second_body_bytes = actix_web::body::to_bytes(body).await?;
 
-        assert_eq!(first_body_bytes, second_body_bytes);
+        let upload_path = PathBuf::from(test_upload_dir);
+        assert!(!upload_path.join("image.png").exists());
+        assert_eq!(
+            1,
+            glob(&upload_path.join("image.png.[0-9]*").to_string_lossy())
+                .map_err(error::ErrorInternalServerError)?
+                .count()
+        );
+        assert!(upload_path.join("text.txt").exists());
+        assert_eq!(
+            0,
+            glob(&upload_path.join("text.txt.[0-9]*").to_string_lossy())
+                .map_err(error::ErrorInternalServerError)?
+                .count()
+        );
 
         fs::remove_dir_all(test_upload_dir)?;
 
@@ -1031,9 +7416,17 @@ mod tests {
     }
 
     #[actix_web::test]
-    async fn test_upload_expiring_file() -> Result<(), Error> {
+    async fn test_upload_remote_file_origin() -> Result<(), Error> {
+        use std::io::{BufRead, BufReader};
+        use std::net::TcpListener;
+
+        let test_upload_dir = "test_remote_origin_upload";
+        fs::create_dir(test_upload_dir)?;
+
         let mut config = Config::default();
-        config.server.upload_path = env::current_dir()?;
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.paste.remote_allowed_origins = Some(vec![String::from("https://example.com/")]);
+        config.server.max_content_length = Byte::from_u64(1000);
 
         let app = test::init_service(
             App::new()
@@ -1043,47 +7436,66 @@ mod tests {
         )
         .await;
 
-        let file_name = "test_file.txt";
-        let timestamp = util::get_system_time()?.as_secs().to_string();
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(&stream);
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|v| v > 2) {
+                    line.clear();
+                }
+                let _ = stream.write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 6\r\nConnection: close\r\n\r\nremote",
+                );
+            }
+        });
+        let remote_url = format!("http://{address}/remote.txt");
+
+        for (name, value) in [
+            (header::ORIGIN, "https://example.org"),
+            (header::REFERER, "https://example.org/page"),
+        ] {
+            let response = test::call_service(
+                &app,
+                get_multipart_request(&remote_url, "remote", "remote.txt")
+                    .insert_header((name, value))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(StatusCode::FORBIDDEN, response.status());
+        }
         let response = test::call_service(
             &app,
-            get_multipart_request(&timestamp, "file", file_name)
-                .insert_header((
-                    header::HeaderName::from_static("expire"),
-                    header::HeaderValue::from_static("20ms"),
-                ))
-                .to_request(),
+            get_multipart_request(&remote_url, "remote", "remote.txt").to_request(),
         )
         .await;
-        assert_eq!(StatusCode::OK, response.status());
-        assert_body(
-            response.into_body(),
-            &format!("http://localhost:8080/{file_name}\n"),
-        )
-        .await?;
-
-        let serve_request = TestRequest::get()
-            .uri(&format!("/{file_name}"))
-            .to_request();
-        let response = test::call_service(&app, serve_request).await;
-        assert_eq!(StatusCode::OK, response.status());
-        assert_body(response.into_body(), &timestamp).await?;
-
-        thread::sleep(Duration::from_millis(40));
-
-        let serve_request = TestRequest::get()
-            .uri(&format!("/{file_name}"))
-            .to_request();
-        let response = test::call_service(&app, serve_request).await;
-        assert_eq!(StatusCode::NOT_FOUND, response.status());
-
-        if let Some(glob_path) = glob(&format!("{file_name}.[0-9]*"))
-            .map_err(error::ErrorInternalServerError)?
-            .next()
-        {
-            fs::remove_file(glob_path.map_err(error::ErrorInternalServerError)?)?;
+        assert_eq!(StatusCode::FORBIDDEN, response.status());
+
+        for (name, value, file_name) in [
+            (header::ORIGIN, "https://example.com", "a.txt"),
+            (header::REFERER, "https://example.com/page", "b.txt"),
+        ] {
+            let response = test::call_service(
+                &app,
+                get_multipart_request(
+                    &format!("http://{address}/{file_name}"),
+                    "remote",
+                    file_name,
+                )
+                .insert_header((name, value))
+                .to_request(),
+            )
+            .await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_eq!(
+                "remote",
+                fs::read_to_string(PathBuf::from(test_upload_dir).join(file_name))?
+            );
         }
 
+        fs::remove_dir_all(test_upload_dir)?;
+
         Ok(())
     }
 
@@ -1147,6 +7559,96 @@ mod tests {
         Ok(())
     }
 
+    #[actix_web::test]
+    async fn test_upload_remote_file_unreachable() -> Result<(), Error> {
+        use std::net::TcpListener;
+
+        let test_upload_dir = "test_remote_unreachable_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        // the port is released so that the connection is refused
+        let address = TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+
+        for (remote_url, status) in [
+            (
+                String::from("http://rustypaste.invalid/file.txt"),
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                format!("http://{address}/file.txt"),
+                StatusCode::BAD_GATEWAY,
+            ),
+        ] {
+            let response = test::call_service(
+                &app,
+                get_multipart_request(&remote_url, "remote", "file.txt").to_request(),
+            )
+            .await;
+            assert_eq!(status, response.status());
+        }
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_remote_file_override_filename() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?;
+        config.server.max_content_length = Byte::from_u128(30000).unwrap_or_default();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(
+                    ClientBuilder::new()
+                        .timeout(Duration::from_secs(30))
+                        .finish(),
+                ))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let header_filename = "remote_from_header.jpg";
+        let response = test::call_service(
+            &app,
+            get_multipart_request(
+                "https://upload.wikimedia.org/wikipedia/en/a/a9/Example.jpg",
+                "remote",
+                "Example.jpg",
+            )
+            .insert_header((
+                header::HeaderName::from_static("filename"),
+                header::HeaderValue::from_static("remote_from_header.jpg"),
+            ))
+            .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(
+            response.into_body().boxed(),
+            &format!("http://localhost:8080/{header_filename}\n"),
+        )
+        .await?;
+        assert!(PathBuf::from(header_filename).exists());
+
+        fs::remove_file(header_filename)?;
+
+        Ok(())
+    }
+
     #[actix_web::test]
     async fn test_upload_url() -> Result<(), Error> {
         let mut config = Config::default();
@@ -1247,6 +7749,125 @@ mod tests {
         Ok(())
     }
 
+    #[actix_web::test]
+    async fn test_serve_oneshot_concurrently() -> Result<(), Error> {
+        let test_upload_dir = "test_oneshot_concurrent_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config.clone())))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let oneshot_upload_path = PasteType::Oneshot
+            .get_path(&config.server.upload_path)
+            .expect("Bad upload path");
+        fs::create_dir_all(&oneshot_upload_path)?;
+        fs::write(oneshot_upload_path.join("oneshot.txt"), "test")?;
+
+        let (first_response, second_response) = future::join(
+            test::call_service(&app, TestRequest::get().uri("/oneshot.txt").to_request()),
+            test::call_service(&app, TestRequest::get().uri("/oneshot.txt").to_request()),
+        )
+        .await;
+        let mut statuses = [first_response.status(), second_response.status()];
+        statuses.sort();
+        assert_eq!([StatusCode::OK, StatusCode::NOT_FOUND], statuses);
+        assert!(!oneshot_upload_path.join("oneshot.txt").exists());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_oneshot_max_lifetime() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?;
+        config.paste.oneshot_max_lifetime = Some(Duration::from_millis(200));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config.clone())))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let oneshot_upload_path = PasteType::Oneshot
+            .get_path(&config.server.upload_path)
+            .expect("Bad upload path");
+        fs::create_dir_all(&oneshot_upload_path)?;
+
+        // the oneshot file is served once before it expires
+        let file_name = "oneshot_unexpired.txt";
+        let response = test::call_service(
+            &app,
+            get_multipart_request("unexpired", "oneshot", file_name).to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        let serve_request = TestRequest::get()
+            .uri(&format!("/{file_name}"))
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "unexpired").await?;
+        let serve_request = TestRequest::get()
+            .uri(&format!("/{file_name}"))
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+        for file in glob(
+            &oneshot_upload_path
+                .join(format!("{file_name}.*"))
+                .to_string_lossy(),
+        )
+        .map_err(error::ErrorInternalServerError)?
+        {
+            fs::remove_file(file.map_err(error::ErrorInternalServerError)?)?;
+        }
+
+        let file_name = "oneshot_lifetime.txt";
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "oneshot", file_name).to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(
+            response.into_body(),
+            &format!("http://localhost:8080/{file_name}\n"),
+        )
+        .await?;
+
+        thread::sleep(Duration::from_millis(250));
+        let expired_files = util::get_expired_files(&config.server.upload_path)
+            .into_iter()
+            .filter(|v| v.to_string_lossy().contains(&format!("{file_name}.")))
+            .collect::<Vec<PathBuf>>();
+        assert_eq!(1, expired_files.len());
+        for file in expired_files {
+            fs::remove_file(file)?;
+        }
+
+        let serve_request = TestRequest::get()
+            .uri(&format!("/{file_name}"))
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+        fs::remove_dir(oneshot_upload_path)?;
+
+        Ok(())
+    }
+
     #[actix_web::test]
     async fn test_upload_oneshot_url() -> Result<(), Error> {
         let mut config = Config::default();
@@ -0,0 +1,131 @@
+use crate::config::{TranscodeConfig, TranscodeFormat};
+use image::codecs::avif::AvifEncoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::{DynamicImage, ImageFormat, ImageResult};
+use std::io::Cursor;
+use std::path::Path;
+
+/// Default quality of the lossy formats.
+pub const DEFAULT_QUALITY: u8 = 80;
+
+/// Encoding speed of AVIF (1-10).
+const AVIF_SPEED: u8 = 8;
+
+/// Formats of the images that are transcoded.
+const SOURCE_FORMATS: &[ImageFormat] = &[ImageFormat::Png, ImageFormat::Jpeg];
+
+impl TranscodeFormat {
+    /// Returns the file extension of the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Webp => "webp",
+            Self::Avif => "avif",
+            Self::Jpeg => "jpg",
+            Self::Png => "png",
+        }
+    }
+
+    /// Returns the corresponding image format.
+    fn image_format(&self) -> ImageFormat {
+        match self {
+            Self::Webp => ImageFormat::WebP,
+            Self::Avif => ImageFormat::Avif,
+            Self::Jpeg => ImageFormat::Jpeg,
+            Self::Png => ImageFormat::Png,
+        }
+    }
+
+    /// Replaces the extension of the given file name with the extension of the format.
+    pub fn rename(&self, file_name: &str) -> String {
+        Path::new(file_name)
+            .with_extension(self.extension())
+            .to_string_lossy()
+            .to_string()
+    }
+}
+
+/// Converts the given image to the configured format.
+///
+/// Returns `None` if the data is not a PNG or JPEG image or it is already in the target format.
+///
+/// This is CPU bound, it should not be called on the async runtime.
+pub fn transcode(data: &[u8], config: &TranscodeConfig) -> ImageResult<Option<Vec<u8>>> {
+    let format = match image::guess_format(data) {
+        Ok(format)
+            if format != config.format.image_format() && SOURCE_FORMATS.contains(&format) =>
+        {
+            format
+        }
+        _ => return Ok(None),
+    };
+    let image = image::load_from_memory_with_format(data, format)?;
+    let quality = config.quality.unwrap_or(DEFAULT_QUALITY).clamp(1, 100);
+    let mut output = Vec::new();
+    match config.format {
+        TranscodeFormat::Webp => {
+            DynamicImage::ImageRgba8(image.to_rgba8())
+                .write_with_encoder(WebPEncoder::new_lossless(&mut output))?;
+        }
+        TranscodeFormat::Avif => {
+            DynamicImage::ImageRgba8(image.to_rgba8()).write_with_encoder(
+                AvifEncoder::new_with_speed_quality(&mut output, AVIF_SPEED, quality),
+            )?;
+        }
+        TranscodeFormat::Jpeg => {
+            DynamicImage::ImageRgb8(image.to_rgb8())
+                .write_with_encoder(JpegEncoder::new_with_quality(&mut output, quality))?;
+        }
+        TranscodeFormat::Png => {
+            image.write_to(&mut Cursor::new(&mut output), ImageFormat::Png)?;
+        }
+    }
+    Ok(Some(output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    fn create_png() -> ImageResult<Vec<u8>> {
+        let mut data = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::from_pixel(4, 4, image::Rgb([255, 0, 0])))
+            .write_to(&mut Cursor::new(&mut data), ImageFormat::Png)?;
+        Ok(data)
+    }
+
+    #[test]
+    fn test_transcode() -> ImageResult<()> {
+        let png = create_png()?;
+        let mut config = TranscodeConfig {
+            enabled: true,
+            format: TranscodeFormat::Webp,
+            quality: None,
+        };
+        let webp = transcode(&png, &config)?.expect("image is not transcoded");
+        assert_eq!(ImageFormat::WebP, image::guess_format(&webp)?);
+        assert_eq!(None, transcode(&webp, &config)?);
+        assert_eq!(None, transcode(b"plain text", &config)?);
+
+        config.format = TranscodeFormat::Jpeg;
+        let jpeg = transcode(&png, &config)?.expect("image is not transcoded");
+        assert_eq!(ImageFormat::Jpeg, image::guess_format(&jpeg)?);
+
+        config.format = TranscodeFormat::Png;
+        assert_eq!(None, transcode(&png, &config)?);
+
+        assert!(transcode(&png[..png.len() / 2], &TranscodeConfig::default()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rename() {
+        assert_eq!("image.webp", TranscodeFormat::Webp.rename("image.png"));
+        assert_eq!(
+            "photo.tar.jpg",
+            TranscodeFormat::Jpeg.rename("photo.tar.jpeg")
+        );
+        assert_eq!("image.avif", TranscodeFormat::Avif.rename("image"));
+    }
+}
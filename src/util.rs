@@ -1,15 +1,22 @@
-use crate::paste::PasteType;
+use crate::config::{ChecksumAlgorithm, Config, ServerConfig};
+use crate::paste::{self, PasteType};
 use actix_web::{error, Error as ActixError};
-use glob::glob;
+use chrono::DateTime;
+use chrono_tz::Tz;
+use glob::{glob, Pattern};
 use lazy_regex::{lazy_regex, Lazy, Regex};
 use path_clean::PathClean;
 use ring::digest::{Context, SHA256};
 use std::fmt::Write;
+use std::fs;
 use std::io::{BufReader, Read};
 use std::io::{Error as IoError, ErrorKind as IoErrorKind, Result as IoResult};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{RwLock, RwLockReadGuard, TryLockError};
+use std::thread;
 use std::time::Duration;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 /// Regex for matching the timestamp extension of a path.
 pub static TIMESTAMP_EXTENSION_REGEX: Lazy<Regex> = lazy_regex!(r#"\.[0-9]{10,}$"#);
@@ -21,6 +28,127 @@ pub fn get_system_time() -> Result<Duration, ActixError> {
         .map_err(error::ErrorInternalServerError)
 }
 
+/// Waiting for the configuration lock longer than this is logged as a warning.
+const SLOW_CONFIG_LOCK_WAIT: Duration = Duration::from_millis(100);
+
+/// Interval for retrying to acquire the configuration lock.
+const CONFIG_LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Maximum time (in milliseconds) to wait for the configuration lock, `0` for no limit.
+///
+/// See [`ServerConfig::config_lock_timeout`].
+static CONFIG_LOCK_TIMEOUT: AtomicU64 = AtomicU64::new(0);
+
+/// Sets the maximum time to wait for the configuration lock.
+///
+/// The readers wait until the lock is released if `timeout` is `None`.
+pub fn set_config_lock_timeout(timeout: Option<Duration>) {
+    let timeout = timeout
+        .map(|v| u64::try_from(v.as_millis()).unwrap_or(u64::MAX).max(1))
+        .unwrap_or(0);
+    CONFIG_LOCK_TIMEOUT.store(timeout, Ordering::Relaxed);
+}
+
+/// Acquires the shared configuration for reading.
+///
+/// Readers only wait while the configuration is being replaced (i.e. hot-reload),
+/// the time spent for waiting is logged.
+///
+/// Returns service unavailable (`503`) error if the lock cannot be acquired
+/// in [`config_lock_timeout`](ServerConfig::config_lock_timeout).
+pub fn read_config(config: &RwLock<Config>) -> Result<RwLockReadGuard<'_, Config>, ActixError> {
+    match config.try_read() {
+        Ok(config) => return Ok(config),
+        Err(TryLockError::Poisoned(_)) => {
+            return Err(error::ErrorInternalServerError("cannot acquire config"))
+        }
+        Err(TryLockError::WouldBlock) => {}
+    }
+    let start = Instant::now();
+    let config = match CONFIG_LOCK_TIMEOUT.load(Ordering::Relaxed) {
+        0 => config
+            .read()
+            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?,
+        timeout => loop {
+            match config.try_read() {
+                Ok(config) => break config,
+                Err(TryLockError::Poisoned(_)) => {
+                    return Err(error::ErrorInternalServerError("cannot acquire config"))
+                }
+                Err(TryLockError::WouldBlock)
+                    if start.elapsed() >= Duration::from_millis(timeout) =>
+                {
+                    warn!(
+                        "Timed out after {:?} waiting for the config lock",
+                        start.elapsed()
+                    );
+                    return Err(error::ErrorServiceUnavailable("config is being updated\n"));
+                }
+                Err(TryLockError::WouldBlock) => thread::sleep(CONFIG_LOCK_RETRY_INTERVAL),
+            }
+        },
+    };
+    let elapsed = start.elapsed();
+    if elapsed >= SLOW_CONFIG_LOCK_WAIT {
+        warn!("Waited {:?} for the config lock", elapsed);
+    } else {
+        debug!("Waited {:?} for the config lock", elapsed);
+    }
+    Ok(config)
+}
+
+/// Formats the given UNIX timestamp (in seconds) for display.
+///
+/// Dates are formatted in UTC unless a timezone is given.
+pub fn format_timestamp(timestamp: i64, timezone: Option<Tz>) -> String {
+    match timezone.zip(DateTime::from_timestamp(timestamp, 0)) {
+        Some((timezone, date_time)) => date_time
+            .with_timezone(&timezone)
+            .format("%Y-%m-%d %H:%M:%S %z")
+            .to_string(),
+        None => uts2ts::uts2ts(timestamp).as_string(),
+    }
+}
+
+/// Returns the file name to show in the logs.
+///
+/// If [`log_redact_filenames`] is enabled, the name is replaced with the beginning of
+/// its SHA-256 digest, which still allows correlating the log lines of the same file.
+///
+/// [`log_redact_filenames`]: ServerConfig::log_redact_filenames
+pub fn get_log_file_name(file_name: &str, server_config: &ServerConfig) -> String {
+    if !server_config.log_redact_filenames.unwrap_or(false) {
+        return file_name.to_string();
+    }
+    let digest = ring::digest::digest(&SHA256, file_name.as_bytes());
+    digest.as_ref()[..6]
+        .iter()
+        .fold(String::from("redacted-"), |mut output, byte| {
+            let _ = write!(output, "{byte:02x}");
+            output
+        })
+}
+
+/// Creates the upload directory along with the directories of the paste types.
+pub fn create_upload_dirs(upload_path: &Path) -> IoResult<()> {
+    std::fs::create_dir_all(upload_path)?;
+    for paste_type in &[PasteType::Url, PasteType::Oneshot, PasteType::OneshotUrl] {
+        std::fs::create_dir_all(paste_type.get_path(upload_path)?)?;
+    }
+    Ok(())
+}
+
+/// Prefix of the Unix domain socket addresses.
+const UNIX_SOCKET_PREFIX: &str = "unix:";
+
+/// Returns the socket path if the given address is in the `unix:/path/to.sock` form.
+pub fn get_unix_socket_path(address: &str) -> Option<PathBuf> {
+    address
+        .strip_prefix(UNIX_SOCKET_PREFIX)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+}
+
 /// Returns the first _unexpired_ path matched by a custom glob pattern.
 ///
 /// The file extension is accepted as a timestamp that points to the expiry date.
@@ -54,10 +182,91 @@ pub fn glob_match_file(mut path: PathBuf) -> Result<PathBuf, ActixError> {
     Ok(path)
 }
 
-/// Returns the found expired files in the possible upload locations.
+/// Detects the type of the file at the given path from its magic bytes.
+///
+/// Only the beginning of the file is read.
+pub fn infer_file_type(path: &Path) -> IoResult<Option<infer::Type>> {
+    let mut buffer = Vec::new();
+    fs::File::open(path)?.take(8192).read_to_end(&mut buffer)?;
+    Ok(infer::get(&buffer))
+}
+
+/// Checks if there is an expired version of the file at the given path.
+///
+/// The expired files are kept until they are removed by the cleanup routine.
+pub fn has_expired_file(path: &Path) -> Result<bool, ActixError> {
+    let system_time = get_system_time()?;
+    Ok(glob(&format!(
+        "{}.[0-9]*",
+        Pattern::escape(&path.to_string_lossy())
+    ))
+    .map_err(error::ErrorInternalServerError)?
+    .filter_map(Result::ok)
+    .filter_map(|path| {
+        path.extension()
+            .and_then(|v| v.to_str())
+            .and_then(|v| v.parse().ok())
+    })
+    .any(|extension| system_time >= Duration::from_millis(extension)))
+}
+
+/// Renders the given Markdown text as sanitized HTML.
+pub fn render_markdown(input: &str) -> String {
+    let parser = pulldown_cmark::Parser::new_ext(input, pulldown_cmark::Options::all());
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, parser);
+    ammonia::clean(&html)
+}
+
+/// Glob pattern that matches a single level of the fan-out directories.
+const FANOUT_DIR_PATTERN: &str = "[0-9a-f][0-9a-f]";
+
+/// Returns the fan-out directory of the given checksum (e.g. `ab/cd` for the depth of 2).
+pub fn get_fanout_dir(checksum: &str, depth: usize) -> PathBuf {
+    checksum
+        .as_bytes()
+        .chunks(2)
+        .take(depth)
+        .map(|v| String::from_utf8_lossy(v).to_string())
+        .collect()
+}
+
+/// Returns the glob pattern that matches the fan-out directories with the given depth.
+pub fn get_fanout_pattern(base_path: &Path, depth: usize) -> PathBuf {
+    base_path.join(vec![FANOUT_DIR_PATTERN; depth].join("/"))
+}
+
+/// Returns the _unexpired_ file with the given name in the fan-out directories.
+///
+/// The directory of the file is read from its metadata (see [`paste::store_fanout_dir`]).
+pub fn find_fanout_file(base_path: &Path, file_name: &str) -> Result<Option<PathBuf>, ActixError> {
+    if file_name.is_empty() || file_name.contains(['/', '\\']) || file_name == ".." {
+        return Ok(None);
+    }
+    let Some(fanout_dir) = paste::get_fanout_dir(base_path, file_name) else {
+        return Ok(None);
+    };
+    let path = glob_match_file(safe_path_join(base_path, fanout_dir)?.join(file_name))?;
+    Ok(Some(path).filter(|v| v.is_file()))
+}
+
+/// Checks if there is an expired version of the file with the given name in the fan-out directories.
+///
+/// The directory of the file is read from its metadata (see [`paste::store_fanout_dir`]).
+pub fn has_expired_fanout_file(base_path: &Path, file_name: &str) -> Result<bool, ActixError> {
+    if file_name.is_empty() || file_name.contains(['/', '\\']) || file_name == ".." {
+        return Ok(false);
+    }
+    let Some(fanout_dir) = paste::get_fanout_dir(base_path, file_name) else {
+        return Ok(false);
+    };
+    has_expired_file(&safe_path_join(base_path, fanout_dir)?.join(file_name))
+}
+
+/// Returns the files that match the given pattern in the possible upload locations.
 ///
 /// Fail-safe, omits errors.
-pub fn get_expired_files(base_path: &Path) -> Vec<PathBuf> {
+fn glob_upload_files(base_path: &Path, pattern: &str) -> Vec<PathBuf> {
     [
         PasteType::File,
         PasteType::Oneshot,
@@ -66,24 +275,97 @@ pub fn get_expired_files(base_path: &Path) -> Vec<PathBuf> {
     ]
     .into_iter()
     .filter_map(|v| v.get_path(base_path).ok())
-    .filter_map(|v| glob(&v.join("*.[0-9]*").to_string_lossy()).ok())
-    .flat_map(|glob| glob.filter_map(|v| v.ok()).collect::<Vec<PathBuf>>())
-    .filter(|path| {
-        if let Some(extension) = path
-            .extension()
-            .and_then(|v| v.to_str())
-            .and_then(|v| v.parse().ok())
-        {
-            get_system_time()
-                .map(|system_time| system_time > Duration::from_millis(extension))
-                .unwrap_or(false)
-        } else {
-            false
+    .flat_map(|v| {
+        let mut patterns = vec![v.join(pattern)];
+        if v == base_path {
+            // files in the fan-out directories
+            patterns.push(v.join(FANOUT_DIR_PATTERN).join("**").join(pattern));
         }
+        patterns
     })
+    .filter_map(|v| glob(&v.to_string_lossy()).ok())
+    .flat_map(|glob| glob.filter_map(|v| v.ok()).collect::<Vec<PathBuf>>())
+    .filter(|path| path.is_file())
     .collect()
 }
 
+/// Returns all of the stored files in the possible upload locations.
+///
+/// Fail-safe, omits errors.
+pub fn get_stored_files(base_path: &Path) -> Vec<PathBuf> {
+    glob_upload_files(base_path, "*")
+}
+
+/// Returns the found expired files in the possible upload locations.
+///
+/// Fail-safe, omits errors.
+pub fn get_expired_files(base_path: &Path) -> Vec<PathBuf> {
+    glob_upload_files(base_path, "*.[0-9]*")
+        .into_iter()
+        .filter(|path| {
+            if let Some(extension) = path
+                .extension()
+                .and_then(|v| v.to_str())
+                .and_then(|v| v.parse().ok())
+            {
+                get_system_time()
+                    .map(|system_time| system_time > Duration::from_millis(extension))
+                    .unwrap_or(false)
+            } else {
+                false
+            }
+        })
+        .collect()
+}
+
+/// Removes the empty directories in the upload locations and returns the removed ones.
+///
+/// The upload path and the directories of the paste types are never removed.
+///
+/// Fail-safe, omits errors.
+pub fn remove_empty_dirs(base_path: &Path) -> Vec<PathBuf> {
+    let protected_dirs = [
+        PasteType::File,
+        PasteType::Oneshot,
+        PasteType::Url,
+        PasteType::OneshotUrl,
+    ]
+    .into_iter()
+    .filter_map(|v| v.get_path(base_path).ok())
+    .map(|v| v.clean())
+    .collect::<Vec<PathBuf>>();
+    let mut removed_dirs = Vec::new();
+    remove_empty_dirs_recursive(base_path, &protected_dirs, &mut removed_dirs);
+    removed_dirs
+}
+
+/// Removes the given directory if it is empty after visiting its subdirectories.
+///
+/// Returns `true` if the directory is removed.
+fn remove_empty_dirs_recursive(
+    dir: &Path,
+    protected_dirs: &[PathBuf],
+    removed_dirs: &mut Vec<PathBuf>,
+) -> bool {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return false,
+    };
+    let mut is_empty = true;
+    for entry in entries.filter_map(Result::ok) {
+        let is_dir = entry.file_type().map(|v| v.is_dir()).unwrap_or(false);
+        if !is_dir || !remove_empty_dirs_recursive(&entry.path(), protected_dirs, removed_dirs) {
+            is_empty = false;
+        }
+    }
+    if is_empty && !protected_dirs.contains(&dir.clean()) && fs::remove_dir(dir).is_ok() {
+        removed_dirs.push(dir.to_path_buf());
+        true
+    } else {
+        false
+    }
+}
+
 /// Returns the SHA256 digest of the given input.
 pub fn sha256_digest<R: Read>(input: R) -> Result<String, ActixError> {
     let mut reader = BufReader::new(input);
@@ -104,12 +386,35 @@ pub fn sha256_digest<R: Read>(input: R) -> Result<String, ActixError> {
         .collect::<Vec<&u8>>()
         .iter()
         .try_fold::<String, _, IoResult<String>>(String::new(), |mut output, b| {
-            write!(output, "{b:02x}")
-                .map_err(|e| IoError::new(IoErrorKind::Other, e.to_string()))?;
+            write!(output, "{b:02x}").map_err(|e| IoError::other(e.to_string()))?;
             Ok(output)
         })?)
 }
 
+/// Returns the BLAKE3 digest of the given input.
+pub fn blake3_digest<R: Read>(input: R) -> Result<String, ActixError> {
+    let mut reader = BufReader::new(input);
+    let mut hasher = blake3::Hasher::new();
+    let mut buffer = [0; 1024];
+    loop {
+        let bytes_read = reader.read(&mut buffer)?;
+        if bytes_read != 0 {
+            hasher.update(&buffer[..bytes_read]);
+        } else {
+            break;
+        }
+    }
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Returns the digest of the given input using the given algorithm.
+pub fn digest<R: Read>(input: R, algorithm: ChecksumAlgorithm) -> Result<String, ActixError> {
+    match algorithm {
+        ChecksumAlgorithm::Sha256 => sha256_digest(input),
+        ChecksumAlgorithm::Blake3 => blake3_digest(input),
+    }
+}
+
 /// Joins the paths whilst ensuring the path doesn't drastically change.
 /// `base` is assumed to be a trusted value.
 pub fn safe_path_join<B: AsRef<Path>, P: AsRef<Path>>(base: B, part: P) -> IoResult<PathBuf> {
@@ -159,9 +464,8 @@ pub fn get_dir_size(path: &Path) -> IoResult<u64> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use actix_web::http::StatusCode;
     use std::env;
-    use std::fs;
-    use std::thread;
     #[test]
     fn test_system_time() -> Result<(), ActixError> {
         let system_time = get_system_time()?.as_millis();
@@ -170,6 +474,87 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_get_log_file_name() {
+        let mut server_config = ServerConfig::default();
+        assert_eq!(
+            "passwords.txt",
+            get_log_file_name("passwords.txt", &server_config)
+        );
+        server_config.log_redact_filenames = Some(true);
+        let redacted = get_log_file_name("passwords.txt", &server_config);
+        assert!(!redacted.contains("passwords"));
+        assert_eq!(21, redacted.len());
+        assert_eq!(redacted, get_log_file_name("passwords.txt", &server_config));
+        assert_ne!(redacted, get_log_file_name("other.txt", &server_config));
+    }
+
+    #[test]
+    fn test_read_config() -> Result<(), ActixError> {
+        let config = RwLock::new(Config::default());
+
+        // readers do not block each other
+        let reader = read_config(&config)?;
+        assert_eq!(None, read_config(&config)?.server.url);
+        drop(reader);
+
+        // readers wait for the config to be replaced
+        let barrier = std::sync::Barrier::new(2);
+        thread::scope(|s| -> Result<(), ActixError> {
+            s.spawn(|| {
+                let mut writer = config.write().expect("cannot acquire config");
+                barrier.wait();
+                thread::sleep(Duration::from_millis(10));
+                writer.server.url = Some(String::from("http://localhost"));
+            });
+            barrier.wait();
+            assert_eq!(
+                Some("http://localhost"),
+                read_config(&config)?.server.url.as_deref()
+            );
+            Ok(())
+        })?;
+
+        // readers give up after the timeout
+        set_config_lock_timeout(Some(Duration::from_millis(10)));
+        let writer = config.write().expect("cannot acquire config");
+        let error = read_config(&config).expect_err("lock should time out");
+        assert_eq!(
+            StatusCode::SERVICE_UNAVAILABLE,
+            error.as_response_error().status_code()
+        );
+        drop(writer);
+        assert!(read_config(&config).is_ok());
+        set_config_lock_timeout(None);
+
+        // poisoned lock
+        let _ = thread::scope(|s| {
+            s.spawn(|| {
+                let _writer = config.write();
+                panic!("poison the lock");
+            })
+            .join()
+        });
+        assert!(read_config(&config).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(
+            uts2ts::uts2ts(1700000000).as_string(),
+            format_timestamp(1700000000, None)
+        );
+        assert_eq!(
+            "2023-11-15 03:43:20 +0530",
+            format_timestamp(1700000000, Some(Tz::Asia__Kolkata))
+        );
+        assert_eq!(
+            "2023-11-14 14:13:20 -0800",
+            format_timestamp(1700000000, Some(Tz::America__Los_Angeles))
+        );
+    }
+
     #[test]
     fn test_glob_match() -> Result<(), ActixError> {
         let path = PathBuf::from(format!(
@@ -202,6 +587,81 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_digest() -> Result<(), ActixError> {
+        assert_eq!(
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+            digest("".as_bytes(), ChecksumAlgorithm::Blake3)?
+        );
+        assert_eq!(
+            sha256_digest("test".as_bytes())?,
+            digest("test".as_bytes(), ChecksumAlgorithm::Sha256)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_empty_dirs() -> Result<(), ActixError> {
+        let upload_path = PathBuf::from("test_remove_empty_dirs");
+        let empty_dir = upload_path.join("url").join("a").join("b");
+        let non_empty_dir = upload_path.join("oneshot").join("c");
+        fs::create_dir_all(&empty_dir)?;
+        fs::create_dir_all(&non_empty_dir)?;
+        fs::create_dir_all(upload_path.join("oneshot_url"))?;
+        fs::write(non_empty_dir.join("file.txt"), "test")?;
+
+        let removed_dirs = remove_empty_dirs(&upload_path);
+        assert_eq!(
+            vec![empty_dir.clone(), upload_path.join("url").join("a")],
+            removed_dirs
+        );
+        assert!(!empty_dir.exists());
+        assert!(upload_path.join("url").exists());
+        assert!(upload_path.join("oneshot_url").exists());
+        assert!(non_empty_dir.join("file.txt").exists());
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let html = render_markdown("# title\n\n**bold** [link](https://example.com)");
+        assert!(html.contains("<h1>title</h1>"));
+        assert!(html.contains("<strong>bold</strong>"));
+        assert!(html.contains("<a href=\"https://example.com\""));
+        assert_eq!(
+            "<p>xss</p>",
+            render_markdown("<script>alert(1)</script>\n\nxss").trim()
+        );
+        assert!(!render_markdown("[x](javascript:alert(1))").contains("javascript"));
+        assert!(!render_markdown("<img src=x onerror=alert(1)>").contains("onerror"));
+    }
+
+    #[test]
+    fn test_fanout_file() -> Result<(), ActixError> {
+        assert_eq!(PathBuf::from("ab/cd"), get_fanout_dir("abcdef", 2));
+        assert_eq!(PathBuf::from("ab"), get_fanout_dir("abcdef", 1));
+
+        let upload_path = PathBuf::from("fanout_test");
+        let fanout_dir = upload_path.join("ab").join("cd");
+        fs::create_dir_all(&fanout_dir)?;
+        fs::write(fanout_dir.join("file.txt"), "test")?;
+        assert_eq!(None, find_fanout_file(&upload_path, "file.txt")?);
+        paste::store_fanout_dir(&upload_path, "file.txt", Some(Path::new("ab/cd")))?;
+        assert_eq!(
+            Some(fanout_dir.join("file.txt")),
+            find_fanout_file(&upload_path, "file.txt")?
+        );
+        assert_eq!(None, find_fanout_file(&upload_path, "other.txt")?);
+        assert_eq!(None, find_fanout_file(&upload_path, "../cd/file.txt")?);
+        paste::store_fanout_dir(&upload_path, "other.txt", Some(Path::new("../..")))?;
+        assert!(find_fanout_file(&upload_path, "other.txt").is_err());
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+
     #[test]
     fn test_get_expired_files() -> Result<(), ActixError> {
         let current_dir = env::current_dir()?;
@@ -219,6 +679,36 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_has_expired_file() -> Result<(), ActixError> {
+        let path = PathBuf::from("has_expired.txt");
+        assert!(!has_expired_file(&path)?);
+        let expiration_time = get_system_time()?.as_millis() + 50;
+        let expired_path = PathBuf::from(format!("has_expired.txt.{expiration_time}"));
+        fs::write(&expired_path, String::new())?;
+        assert!(!has_expired_file(&path)?);
+        thread::sleep(Duration::from_millis(75));
+        assert!(has_expired_file(&path)?);
+        fs::remove_file(expired_path)?;
+
+        let path = PathBuf::from("has_expired[1].txt");
+        let expired_path = PathBuf::from(format!("has_expired[1].txt.{expiration_time}"));
+        fs::write(&expired_path, String::new())?;
+        assert!(has_expired_file(&path)?);
+        fs::remove_file(expired_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_unix_socket_path() {
+        assert_eq!(
+            Some(PathBuf::from("/run/rustypaste.sock")),
+            get_unix_socket_path("unix:/run/rustypaste.sock")
+        );
+        assert_eq!(None, get_unix_socket_path("unix:"));
+        assert_eq!(None, get_unix_socket_path("127.0.0.1:8000"));
+    }
+
     #[test]
     fn test_safe_join_path() {
         assert_eq!(safe_path_join("/foo", "bar").ok(), Some("/foo/bar".into()));